@@ -1,7 +1,60 @@
+DROP TABLE IF EXISTS guild_settings CASCADE;
+DROP TABLE IF EXISTS poll_group CASCADE;
 DROP TABLE IF EXISTS poll CASCADE;
 DROP TABLE IF EXISTS poll_option CASCADE;
+DROP TABLE IF EXISTS nomination CASCADE;
+DROP TABLE IF EXISTS poll_role CASCADE;
+DROP TABLE IF EXISTS poll_weight_role CASCADE;
+DROP TABLE IF EXISTS poll_weight_user CASCADE;
 DROP TABLE IF EXISTS ballot CASCADE;
 DROP TABLE IF EXISTS ballot_choice CASCADE;
+DROP TABLE IF EXISTS voter CASCADE;
+DROP TABLE IF EXISTS voter_roll CASCADE;
+DROP TABLE IF EXISTS delegation CASCADE;
+DROP TABLE IF EXISTS poll_template CASCADE;
+DROP TABLE IF EXISTS poll_template_option CASCADE;
+DROP TABLE IF EXISTS poll_template_role CASCADE;
+DROP TABLE IF EXISTS poll_template_weight_role CASCADE;
+DROP TABLE IF EXISTS poll_recurrence CASCADE;
+DROP TABLE IF EXISTS reminder_sent CASCADE;
+DROP TABLE IF EXISTS reminder_opt_out CASCADE;
+
+-- Per-server configuration; servers without a row use the defaults.
+CREATE TABLE guild_settings
+(
+    id_server       VARCHAR(20) NOT NULL,
+    -- Members with this role may manage polls, in addition to administrators.
+    id_manager_role VARCHAR(20),
+    -- Defaults for new polls, which may override them.
+    results         VARCHAR(16) NOT NULL,
+    method          VARCHAR(16) NOT NULL,
+    ranks           INT         NOT NULL,
+    secret          bool        NOT NULL,
+    -- Where new polls are announced; NULL for the channel they were created in.
+    id_announce_channel VARCHAR(20),
+    locale          VARCHAR(16) NOT NULL,
+
+    CONSTRAINT guild_settings_pk PRIMARY KEY (id_server)
+);
+
+-- Polls voted on together as one election, e.g. for several offices; they share a deadline and
+-- are closed together.
+CREATE TABLE poll_group
+(
+    id            INT         NOT NULL GENERATED ALWAYS AS IDENTITY,
+    time_created  timestamptz NOT NULL,
+    id_server     VARCHAR(20) NOT NULL,
+    id_created_by VARCHAR(20) NOT NULL,
+    open          bool        NOT NULL,
+    name          VARCHAR(24) NOT NULL,
+    title         TEXT        NOT NULL,
+    time_close    timestamptz,
+    id_channel    VARCHAR(20),
+    id_message    VARCHAR(20),
+
+    CONSTRAINT poll_group_pk PRIMARY KEY (id),
+    CONSTRAINT poll_group_id_server_name_uniq UNIQUE (id_server, name)
+);
 
 CREATE TABLE poll
 (
@@ -13,42 +66,283 @@ CREATE TABLE poll
     name          VARCHAR(24) NOT NULL,
     question      TEXT        NOT NULL,
     ranks         INT         NOT NULL,
+    method        VARCHAR(16) NOT NULL,
+    seats         INT         NOT NULL,
+    secret        bool        NOT NULL,
+    write_ins     bool        NOT NULL,
+    min_member_days  INT,
+    min_account_days INT,
+    quorum_ballots   INT,
+    quorum_percent   INT,
+    time_close    timestamptz,
+    -- Set while the poll collects nominations for its options; voting opens once they close.
+    nominating         bool NOT NULL,
+    nomination_seconds INT  NOT NULL,
+    time_nominations_close timestamptz,
+    voting_hours       INT,
+    -- Only used by the runoff method: how many options go to a runoff, and for how long it is open.
+    runoff_candidates INT NOT NULL,
+    runoff_hours      INT,
+    -- Set on a runoff poll to the poll that had no majority.
+    id_runoff_of      INT,
+    -- Hours before the deadline to remind members who have not voted, and whether by DM instead
+    -- of mentioning them in the channel of the poll's status message.
+    reminder_hours    INT[] NOT NULL,
+    reminder_dm       bool  NOT NULL,
+    -- Who may see results while the poll is open.
+    results           VARCHAR(16) NOT NULL,
+    time_roll_cutoff timestamptz,
+    time_roll_frozen timestamptz,
+    id_channel    VARCHAR(20),
+    id_message    VARCHAR(20),
+    id_group      INT,
 
     CONSTRAINT poll_pk PRIMARY KEY (id),
-    CONSTRAINT poll_id_server_name_uniq UNIQUE (id_server, name)
+    CONSTRAINT poll_quorum_check CHECK (quorum_ballots IS NULL OR quorum_percent IS NULL),
+    CONSTRAINT poll_id_server_name_uniq UNIQUE (id_server, name),
+    CONSTRAINT poll_id_group_fk FOREIGN KEY (id_group) REFERENCES poll_group (id),
+    CONSTRAINT poll_id_runoff_of_fk FOREIGN KEY (id_runoff_of) REFERENCES poll (id)
 );
 
 CREATE TABLE poll_option
 (
-    id_poll INT  NOT NULL,
-    id      INT  NOT NULL GENERATED ALWAYS AS IDENTITY,
-    option  TEXT NOT NULL,
+    id_poll     INT  NOT NULL,
+    id          INT  NOT NULL GENERATED ALWAYS AS IDENTITY,
+    option      TEXT NOT NULL,
+    description TEXT,
+    url         TEXT,
+    -- A Unicode emoji, or a custom emoji as <:name:id>.
+    emoji       VARCHAR(64),
 
     CONSTRAINT poll_option_pk PRIMARY KEY (id),
     CONSTRAINT poll_option_uniq UNIQUE (id_poll, option),
     CONSTRAINT poll_option_id_poll_fk FOREIGN KEY (id_poll) REFERENCES poll (id)
 );
 
+-- Members proposing options for a poll in its nomination phase; everyone after the first member
+-- to propose an option is seconding it.
+CREATE TABLE nomination
+(
+    id_poll      INT         NOT NULL,
+    option       TEXT        NOT NULL,
+    id_user      VARCHAR(20) NOT NULL,
+    time_created timestamptz NOT NULL,
+
+    CONSTRAINT nomination_pk PRIMARY KEY (id_poll, option, id_user),
+    CONSTRAINT nomination_id_poll_fk FOREIGN KEY (id_poll) REFERENCES poll (id)
+);
+
+-- Roles that decide who may vote on a poll; members need one of the required roles, if there are
+-- any, and none of the excluded ones.
+CREATE TABLE poll_role
+(
+    id_poll  INT         NOT NULL,
+    id_role  VARCHAR(20) NOT NULL,
+    excluded bool        NOT NULL,
+
+    CONSTRAINT poll_role_pk PRIMARY KEY (id_poll, id_role),
+    CONSTRAINT poll_role_id_poll_fk FOREIGN KEY (id_poll) REFERENCES poll (id)
+);
+
+-- Vote weights for members with a role; a member with several weighted roles gets the highest.
+CREATE TABLE poll_weight_role
+(
+    id_poll INT         NOT NULL,
+    id_role VARCHAR(20) NOT NULL,
+    weight  INT         NOT NULL,
+
+    CONSTRAINT poll_weight_role_pk PRIMARY KEY (id_poll, id_role),
+    CONSTRAINT poll_weight_role_id_poll_fk FOREIGN KEY (id_poll) REFERENCES poll (id)
+);
+
+-- Vote weights for individual members; these take precedence over role weights.
+CREATE TABLE poll_weight_user
+(
+    id_poll INT         NOT NULL,
+    id_user VARCHAR(20) NOT NULL,
+    weight  INT         NOT NULL,
+
+    CONSTRAINT poll_weight_user_pk PRIMARY KEY (id_poll, id_user),
+    CONSTRAINT poll_weight_user_id_poll_fk FOREIGN KEY (id_poll) REFERENCES poll (id)
+);
+
 CREATE TABLE ballot
 (
     id           INT         NOT NULL GENERATED ALWAYS AS IDENTITY,
     id_poll      INT         NOT NULL,
-    id_user      VARCHAR(20) NOT NULL,
+    -- Exactly one of id_user and voter_token is set; secret polls only store the token. So that
+    -- their ballots say less about who cast them, secret polls also record proxy ballots on the
+    -- voter row instead, leaving by_proxy false, and round time_created down to the hour.
+    id_user      VARCHAR(20),
+    voter_token  CHAR(64),
+    weight       INT         NOT NULL,
+    by_proxy     bool        NOT NULL,
     time_created timestamptz NOT NULL,
     invalidated  bool        NOT NULL,
 
     CONSTRAINT ballot_pk PRIMARY KEY (id),
-    CONSTRAINT ballot_valid_uniq UNIQUE (id_poll, id_user, time_created, invalidated),
+    CONSTRAINT ballot_voter_check CHECK (num_nonnulls(id_user, voter_token) = 1),
     CONSTRAINT ballot_id_poll_fk FOREIGN KEY (id_poll) REFERENCES poll (id)
 );
 
+-- Each voter has at most one counted ballot per poll. Partial indexes are needed because one of
+-- id_user and voter_token is always NULL, and NULLs never conflict in a unique constraint.
+CREATE UNIQUE INDEX ballot_valid_user_uniq ON ballot (id_poll, id_user) WHERE NOT invalidated AND id_user IS NOT NULL;
+CREATE UNIQUE INDEX ballot_valid_token_uniq ON ballot (id_poll, voter_token) WHERE NOT invalidated AND voter_token IS NOT NULL;
+
 CREATE TABLE ballot_choice
 (
     id_ballot INT NOT NULL,
-    id_option INT NOT NULL,
+    -- Exactly one of id_option and write_in is set; write_in holds a candidate not listed on the
+    -- poll, spelled as it was first written in.
+    id_option INT,
+    write_in  TEXT,
     rank      INT NOT NULL,
 
-    CONSTRAINT ballot_choice_pk PRIMARY KEY (id_ballot, id_option),
+    CONSTRAINT ballot_choice_pk PRIMARY KEY (id_ballot, rank),
+    CONSTRAINT ballot_choice_check CHECK (num_nonnulls(id_option, write_in) = 1),
     CONSTRAINT ballot_choice_ballot_fk FOREIGN KEY (id_ballot) REFERENCES ballot (id),
     CONSTRAINT ballot_choice_option_fk FOREIGN KEY (id_option) REFERENCES poll_option (id)
 );
+
+-- Who has a counted ballot on a secret poll, kept apart from what they voted for.
+CREATE TABLE voter
+(
+    id_poll  INT         NOT NULL,
+    id_user  VARCHAR(20) NOT NULL,
+    -- Whether the member's ballot was cast by their delegate.
+    by_proxy bool        NOT NULL,
+
+    CONSTRAINT voter_pk PRIMARY KEY (id_poll, id_user),
+    CONSTRAINT voter_id_poll_fk FOREIGN KEY (id_poll) REFERENCES poll (id)
+);
+
+-- The members allowed to vote on a poll, captured when its voter roll was frozen.
+CREATE TABLE voter_roll
+(
+    id_poll INT         NOT NULL,
+    id_user VARCHAR(20) NOT NULL,
+
+    CONSTRAINT voter_roll_pk PRIMARY KEY (id_poll, id_user),
+    CONSTRAINT voter_roll_id_poll_fk FOREIGN KEY (id_poll) REFERENCES poll (id)
+);
+
+-- A member letting another member vote for them, on one poll or on every poll in a server.
+CREATE TABLE delegation
+(
+    id           INT         NOT NULL GENERATED ALWAYS AS IDENTITY,
+    id_server    VARCHAR(20) NOT NULL,
+    -- NULL for a delegation covering every poll in the server.
+    id_poll      INT,
+    id_delegator VARCHAR(20) NOT NULL,
+    id_delegate  VARCHAR(20) NOT NULL,
+    time_created timestamptz NOT NULL,
+    time_revoked timestamptz,
+
+    CONSTRAINT delegation_pk PRIMARY KEY (id),
+    CONSTRAINT delegation_id_poll_fk FOREIGN KEY (id_poll) REFERENCES poll (id)
+);
+
+-- Reminders already sent for a poll, by lead time; cleared when the poll's deadline changes.
+CREATE TABLE reminder_sent
+(
+    id_poll      INT         NOT NULL,
+    hours_before INT         NOT NULL,
+    time_sent    timestamptz NOT NULL,
+
+    CONSTRAINT reminder_sent_pk PRIMARY KEY (id_poll, hours_before),
+    CONSTRAINT reminder_sent_id_poll_fk FOREIGN KEY (id_poll) REFERENCES poll (id)
+);
+
+-- Members who do not want voting reminders for polls in a server.
+CREATE TABLE reminder_opt_out
+(
+    id_server VARCHAR(20) NOT NULL,
+    id_user   VARCHAR(20) NOT NULL,
+
+    CONSTRAINT reminder_opt_out_pk PRIMARY KEY (id_server, id_user)
+);
+
+-- A saved poll definition that new polls can be created from; columns mirror those of poll.
+CREATE TABLE poll_template
+(
+    id             INT         NOT NULL GENERATED ALWAYS AS IDENTITY,
+    time_created   timestamptz NOT NULL,
+    id_server      VARCHAR(20) NOT NULL,
+    id_created_by  VARCHAR(20) NOT NULL,
+    name           VARCHAR(24) NOT NULL,
+    question       TEXT        NOT NULL,
+    ranks          INT         NOT NULL,
+    method         VARCHAR(16) NOT NULL,
+    seats          INT         NOT NULL,
+    secret         bool        NOT NULL,
+    write_ins      bool        NOT NULL,
+    min_member_days  INT,
+    min_account_days INT,
+    quorum_ballots   INT,
+    quorum_percent   INT,
+    runoff_candidates INT NOT NULL,
+    runoff_hours      INT,
+    reminder_hours    INT[] NOT NULL,
+    reminder_dm       bool  NOT NULL,
+    results           VARCHAR(16) NOT NULL,
+    -- How long polls created from the template stay open; NULL to close them manually.
+    duration_hours INT,
+
+    CONSTRAINT poll_template_pk PRIMARY KEY (id),
+    CONSTRAINT poll_template_quorum_check CHECK (quorum_ballots IS NULL OR quorum_percent IS NULL),
+    CONSTRAINT poll_template_id_server_name_uniq UNIQUE (id_server, name)
+);
+
+CREATE TABLE poll_template_option
+(
+    id_template INT  NOT NULL,
+    id          INT  NOT NULL GENERATED ALWAYS AS IDENTITY,
+    option      TEXT NOT NULL,
+    description TEXT,
+    url         TEXT,
+    emoji       VARCHAR(64),
+
+    CONSTRAINT poll_template_option_pk PRIMARY KEY (id),
+    CONSTRAINT poll_template_option_uniq UNIQUE (id_template, option),
+    CONSTRAINT poll_template_option_id_template_fk FOREIGN KEY (id_template) REFERENCES poll_template (id) ON DELETE CASCADE
+);
+
+CREATE TABLE poll_template_role
+(
+    id_template INT         NOT NULL,
+    id_role     VARCHAR(20) NOT NULL,
+    excluded    bool        NOT NULL,
+
+    CONSTRAINT poll_template_role_pk PRIMARY KEY (id_template, id_role),
+    CONSTRAINT poll_template_role_id_template_fk FOREIGN KEY (id_template) REFERENCES poll_template (id) ON DELETE CASCADE
+);
+
+CREATE TABLE poll_template_weight_role
+(
+    id_template INT         NOT NULL,
+    id_role     VARCHAR(20) NOT NULL,
+    weight      INT         NOT NULL,
+
+    CONSTRAINT poll_template_weight_role_pk PRIMARY KEY (id_template, id_role),
+    CONSTRAINT poll_template_weight_role_id_template_fk FOREIGN KEY (id_template) REFERENCES poll_template (id) ON DELETE CASCADE
+);
+
+-- A template that a new poll is created from every period. Recurrences refer to their template by
+-- name, so replacing the template with /template save changes the polls created after it.
+CREATE TABLE poll_recurrence
+(
+    id            INT         NOT NULL GENERATED ALWAYS AS IDENTITY,
+    time_created  timestamptz NOT NULL,
+    id_server     VARCHAR(20) NOT NULL,
+    id_created_by VARCHAR(20) NOT NULL,
+    template      VARCHAR(24) NOT NULL,
+    period_hours  INT         NOT NULL,
+    time_next     timestamptz NOT NULL,
+    id_channel    VARCHAR(20) NOT NULL,
+    id_last_poll  INT,
+
+    CONSTRAINT poll_recurrence_pk PRIMARY KEY (id),
+    CONSTRAINT poll_recurrence_id_server_template_uniq UNIQUE (id_server, template),
+    CONSTRAINT poll_recurrence_id_last_poll_fk FOREIGN KEY (id_last_poll) REFERENCES poll (id)
+);
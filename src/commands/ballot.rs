@@ -0,0 +1,547 @@
+use chrono::{DateTime, Duration, Utc};
+use evlog::meta;
+use serenity::builder::CreateInteractionResponseData;
+use serenity::client::Context;
+use serenity::model::channel::ReactionType;
+use serenity::model::interactions::message_component::{ButtonStyle, MessageComponentInteraction};
+
+use crate::db;
+use crate::db::schema::{Choice, Poll, PollOption};
+use crate::handler::BotData;
+use crate::helpers::component_resp;
+use crate::runtime::get_logger;
+use crate::support::{ballots, eligibility, poll_status, text};
+use crate::support::numbers::num_word;
+
+pub const VOTE: &str = "vote";
+pub const VOTE_PICK: &str = "vote-pick";
+pub const VOTE_PAGE: &str = "vote-page";
+pub const VOTE_RESET: &str = "vote-reset";
+pub const VOTE_SUBMIT: &str = "vote-submit";
+pub const VOTE_DM: &str = "vote-dm";
+pub const VOTE_DM_POLL: &str = "vote-dm-poll";
+pub const VOTE_WITHDRAW: &str = "vote-withdraw";
+pub const VOTE_GROUP: &str = "vote-group";
+
+// Discord allows at most 25 options in a select menu.
+const PAGE_SIZE: usize = 25;
+
+// Drafts untouched for this long are discarded the next time any member starts one, so that drafts
+// abandoned or left on closed polls do not pile up.
+const DRAFT_LIFETIME_HOURS: i64 = 1;
+
+/// A component ballot being ranked, keyed in `BotData::ballot_drafts` by (user ID, poll ID).
+pub struct BallotDraft {
+    /// Option IDs in rank order.
+    pub choices: Vec<i32>,
+    pub time_updated: DateTime<Utc>,
+}
+
+impl BallotDraft {
+    fn new() -> Self {
+        Self {
+            choices: Vec::new(),
+            time_updated: Utc::now(),
+        }
+    }
+}
+
+// Starts an empty draft for the member, discarding any stale drafts.
+fn start_draft(data: &BotData, key: (u64, i32)) {
+    let now = Utc::now();
+    data.ballot_drafts.retain(|_, v| now - v.time_updated < Duration::hours(DRAFT_LIFETIME_HOURS));
+    data.ballot_drafts.insert(key, BallotDraft::new());
+}
+
+fn custom_id_args(interaction: &MessageComponentInteraction) -> Vec<&str> {
+    interaction.data.custom_id.split(':').skip(1).collect()
+}
+
+fn poll_id_arg(interaction: &MessageComponentInteraction) -> anyhow::Result<i32> {
+    match custom_id_args(interaction).first().and_then(|v| v.parse::<i32>().ok()) {
+        None => Err(anyhow::anyhow!("malformed custom ID '{}'", interaction.data.custom_id)),
+        Some(v) => Ok(v),
+    }
+}
+
+async fn load_open_poll(ctx: &Context, interaction: &MessageComponentInteraction, data: &BotData, id_poll: i32) -> anyhow::Result<Option<Poll>> {
+    let poll = match db::model::get_poll(data.db_client.conn(), id_poll).await {
+        Ok(v) => match v {
+            None => {
+                component_resp::reply_ephemeral(ctx, interaction, |d| d.content("This poll no longer exists.")).await?;
+                return Ok(None);
+            }
+            Some(v) => v,
+        },
+        Err(e) => {
+            component_resp::reply_ephemeral(ctx, interaction, |d| d.content("Error occurred upon attempt to look up poll.")).await?;
+            return Err(e);
+        }
+    };
+
+    if !poll.accepting_votes() {
+        data.ballot_drafts.remove(&(*interaction.user.id.as_u64(), poll.id));
+
+        get_logger().info("User attempted to vote on closed poll.", meta! {
+            "InteractionID" => interaction.id,
+            "PollID" => poll.id,
+            "PollName" => poll.name,
+        });
+        component_resp::reply_ephemeral(ctx, interaction, |d| d.content(format!("Voting is closed for poll **'{}'**.", poll.name))).await?;
+        return Ok(None);
+    }
+
+    Ok(Some(poll))
+}
+
+/// Replies with the reason and returns false if the member may not vote on `poll`.
+async fn check_eligible(ctx: &Context, interaction: &MessageComponentInteraction, data: &BotData, poll: &Poll) -> anyhow::Result<bool> {
+    match eligibility::check(&ctx.http, data.db_client.conn(), poll, interaction.member.as_ref(), *interaction.user.id.as_u64()).await? {
+        None => Ok(true),
+        Some(reason) => {
+            get_logger().info("Ineligible user attempted to vote.", meta! {
+                "InteractionID" => interaction.id,
+                "PollID" => poll.id,
+                "PollName" => poll.name,
+            });
+            component_resp::reply_ephemeral(ctx, interaction, |d| d.content(reason)).await?;
+            Ok(false)
+        }
+    }
+}
+
+/// Finds the first poll in a group, after poll `after` if given, that the member may vote on.
+async fn next_group_poll(ctx: &Context, interaction: &MessageComponentInteraction, data: &BotData, id_group: i32, after: Option<i32>) -> anyhow::Result<Option<Poll>> {
+    let polls = db::model::list_group_polls(data.db_client.conn(), id_group).await?;
+
+    let start = match after {
+        None => 0,
+        Some(id) => polls.iter().position(|v| v.id == id).map_or(0, |i| i + 1),
+    };
+
+    for poll in polls.into_iter().skip(start) {
+        if !poll.accepting_votes() {
+            continue;
+        }
+        if eligibility::check(&ctx.http, data.db_client.conn(), &poll, interaction.member.as_ref(), *interaction.user.id.as_u64()).await?.is_none() {
+            return Ok(Some(poll));
+        }
+    }
+
+    Ok(None)
+}
+
+fn draft_message<'a>(d: &'a mut CreateInteractionResponseData, poll: &Poll, draft: &[i32], page: usize) -> &'a mut CreateInteractionResponseData {
+    let remaining = poll.options.iter()
+        .filter(|v| !draft.contains(&v.id))
+        .collect::<Vec<&PollOption>>();
+
+    let pages = (remaining.len() + PAGE_SIZE - 1) / PAGE_SIZE;
+    let page = page.min(pages.saturating_sub(1));
+    let next_rank = draft.len() as u8 + 1;
+    let can_pick = draft.len() < poll.settings.ranks as usize && !remaining.is_empty();
+
+    let mut content = format!("**{}**\n", poll.question);
+    if draft.is_empty() {
+        content.push_str("You haven't ranked any options yet.\n");
+    }
+    for (i, id) in draft.iter().enumerate() {
+        if let Some(opt) = poll.options.iter().find(|v| v.id == *id) {
+            content.push_str(&format!("**{}.** {}\n", num_word(i as u8 + 1), poll_status::option_line(&opt.option, &opt.details)));
+        }
+    }
+    if can_pick {
+        content.push_str(&format!("\nPick your {} choice below, or submit your ballot as it is.", num_word(next_rank)));
+    } else {
+        content.push_str("\nSubmit to cast your ballot.");
+    }
+
+    d.content(content);
+    d.components(|c| {
+        if can_pick {
+            c.create_action_row(|r| r.create_select_menu(|m| {
+                m.custom_id(format!("{}:{}", VOTE_PICK, poll.id))
+                    .placeholder(format!("Your {} choice", num_word(next_rank)))
+                    .min_values(1)
+                    .max_values(1)
+                    .options(|o| {
+                        for opt in remaining.iter().skip(page * PAGE_SIZE).take(PAGE_SIZE) {
+                            o.create_option(|so| {
+                                so.label(text::truncate(&opt.option, 100)).value(opt.id);
+                                if let Some(v) = &opt.details.description {
+                                    so.description(text::truncate(v, 100));
+                                }
+                                if let Some(v) = opt.details.emoji.as_deref().and_then(|v| ReactionType::try_from(v).ok()) {
+                                    so.emoji(v);
+                                }
+                                so
+                            });
+                        }
+                        o
+                    })
+            }));
+        }
+
+        c.create_action_row(|r| {
+            if can_pick && pages > 1 {
+                r.create_button(|b| b
+                    .custom_id(format!("{}:{}:{}:prev", VOTE_PAGE, poll.id, (page + pages - 1) % pages))
+                    .label("Previous options")
+                    .style(ButtonStyle::Secondary));
+                r.create_button(|b| b
+                    .custom_id(format!("{}:{}:{}:next", VOTE_PAGE, poll.id, (page + 1) % pages))
+                    .label("More options")
+                    .style(ButtonStyle::Secondary));
+            }
+            r.create_button(|b| b
+                .custom_id(format!("{}:{}", VOTE_SUBMIT, poll.id))
+                .label("Submit")
+                .style(ButtonStyle::Success)
+                .disabled(draft.is_empty()));
+            r.create_button(|b| b
+                .custom_id(format!("{}:{}", VOTE_RESET, poll.id))
+                .label("Start over")
+                .style(ButtonStyle::Danger)
+                .disabled(draft.is_empty()))
+        })
+    });
+
+    d
+}
+
+/// Handles the "Vote" button on a poll's status message by opening a private ranking flow.
+pub async fn vote_start(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let poll = match load_open_poll(&ctx, &interaction, data, poll_id_arg(&interaction)?).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    if !check_eligible(&ctx, &interaction, data, &poll).await? {
+        return Ok(());
+    }
+
+    let key = (*interaction.user.id.as_u64(), poll.id);
+    start_draft(data, key);
+
+    component_resp::reply_ephemeral(&ctx, &interaction, |d| draft_message(d, &poll, &[], 0)).await?;
+
+    Ok(())
+}
+
+/// Handles the "Vote on all questions" button on an election's status message, and the "Next
+/// question" button shown after each ballot cast through it, by opening the ranking flow for the
+/// next poll in the election the member may vote on.
+pub async fn vote_group(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let args = custom_id_args(&interaction);
+    let id_group = match args.first().and_then(|v| v.parse::<i32>().ok()) {
+        None => return Err(anyhow::anyhow!("malformed custom ID '{}'", interaction.data.custom_id)),
+        Some(v) => v,
+    };
+    // Set when continuing from a ballot just cast, whose message is replaced with the next poll.
+    let after = args.get(1).and_then(|v| v.parse::<i32>().ok());
+
+    let poll = match next_group_poll(&ctx, &interaction, data, id_group, after).await {
+        Ok(v) => v,
+        Err(e) => {
+            component_resp::reply_ephemeral(&ctx, &interaction, |d| d.content("Error occurred upon attempt to look up election.")).await?;
+            return Err(e);
+        }
+    };
+
+    let poll = match poll {
+        None => {
+            let content = "There are no more open questions in this election that you may vote on.";
+            match after {
+                None => component_resp::reply_ephemeral(&ctx, &interaction, |d| d.content(content)).await?,
+                Some(_) => component_resp::update(&ctx, &interaction, |d| {
+                    d.content(content);
+                    d.set_embeds(Vec::new());
+                    d.components(|c| c)
+                }).await?,
+            }
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    let key = (*interaction.user.id.as_u64(), poll.id);
+    start_draft(data, key);
+
+    match after {
+        None => component_resp::reply_ephemeral(&ctx, &interaction, |d| draft_message(d, &poll, &[], 0)).await?,
+        Some(_) => component_resp::update(&ctx, &interaction, |d| {
+            d.set_embeds(Vec::new());
+            draft_message(d, &poll, &[], 0)
+        }).await?,
+    }
+
+    Ok(())
+}
+
+/// Handles the "Vote by DM" button by moving the ranking flow into a direct message, so that
+/// nothing about it is shown in the server, not even an ephemeral message.
+pub async fn vote_dm(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let poll = match load_open_poll(&ctx, &interaction, data, poll_id_arg(&interaction)?).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    // The poll whose button was pressed comes first, so that it is listed however many others are open.
+    let polls = match db::model::list_open_polls(data.db_client.conn(), poll.id_server).await {
+        Ok(v) => v.into_iter()
+            .filter(|v| v.accepting_votes() && v.id != poll.id)
+            .take(PAGE_SIZE - 1)
+            .collect::<Vec<Poll>>(),
+        Err(e) => {
+            component_resp::reply_ephemeral(&ctx, &interaction, |d| d.content("Error occurred upon attempt to list open polls.")).await?;
+            return Err(e);
+        }
+    };
+
+    let sent = match interaction.user.create_dm_channel(&ctx).await {
+        Ok(dm) => dm.send_message(&ctx.http, |m| {
+            m.content("Choose a poll to vote on. Your ballot is only shown here.");
+            m.components(|c| c.create_action_row(|r| r.create_select_menu(|m| m
+                .custom_id(VOTE_DM_POLL)
+                .placeholder("Choose a poll")
+                .options(|o| {
+                    for poll in std::iter::once(&poll).chain(&polls) {
+                        o.create_option(|so| so
+                            .label(text::truncate(&poll.name, 100))
+                            .description(text::truncate(&poll.question, 100))
+                            .value(poll.id));
+                    }
+                    o
+                }))))
+        }).await.map(|_| ()),
+        Err(e) => Err(e),
+    };
+
+    match sent {
+        Ok(()) => {
+            component_resp::reply_ephemeral(&ctx, &interaction, |d| d.content("I've sent you a direct message to vote in.")).await?;
+        }
+        Err(e) => {
+            get_logger().info("Failed to send voting direct message.", meta! {
+                "InteractionID" => interaction.id,
+                "PollID" => poll.id,
+                "Error" => e,
+            });
+            component_resp::reply_ephemeral(&ctx, &interaction, |d| d.content(
+                "I couldn't send you a direct message; allow direct messages from server members and try again."
+            )).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles choosing a poll in the direct message sent by [`vote_dm`].
+pub async fn vote_dm_poll(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let id_poll = match interaction.data.values.first().and_then(|v| v.parse::<i32>().ok()) {
+        None => return Err(anyhow::anyhow!("no poll selected in '{}'", interaction.data.custom_id)),
+        Some(v) => v,
+    };
+
+    let poll = match load_open_poll(&ctx, &interaction, data, id_poll).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    if !check_eligible(&ctx, &interaction, data, &poll).await? {
+        return Ok(());
+    }
+
+    let key = (*interaction.user.id.as_u64(), poll.id);
+    start_draft(data, key);
+
+    component_resp::update(&ctx, &interaction, |d| draft_message(d, &poll, &[], 0)).await?;
+
+    Ok(())
+}
+
+pub async fn vote_pick(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let poll = match load_open_poll(&ctx, &interaction, data, poll_id_arg(&interaction)?).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let picked = interaction.data.values.first()
+        .and_then(|v| v.parse::<i32>().ok())
+        .filter(|v| poll.options.iter().any(|opt| opt.id == *v));
+
+    let key = (*interaction.user.id.as_u64(), poll.id);
+    let draft = {
+        let mut draft = data.ballot_drafts.entry(key).or_insert_with(BallotDraft::new);
+
+        match picked {
+            Some(v) if !draft.choices.contains(&v) && draft.choices.len() < poll.settings.ranks as usize => draft.choices.push(v),
+            _ => {}
+        }
+        draft.time_updated = Utc::now();
+
+        draft.choices.clone()
+    };
+
+    component_resp::update(&ctx, &interaction, |d| draft_message(d, &poll, &draft, 0)).await?;
+
+    Ok(())
+}
+
+pub async fn vote_page(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let poll = match load_open_poll(&ctx, &interaction, data, poll_id_arg(&interaction)?).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let page = custom_id_args(&interaction).get(1).and_then(|v| v.parse::<usize>().ok()).unwrap_or(0);
+
+    let key = (*interaction.user.id.as_u64(), poll.id);
+    let draft = data.ballot_drafts.get(&key).map(|v| v.choices.clone()).unwrap_or_default();
+
+    component_resp::update(&ctx, &interaction, |d| draft_message(d, &poll, &draft, page)).await?;
+
+    Ok(())
+}
+
+pub async fn vote_reset(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let poll = match load_open_poll(&ctx, &interaction, data, poll_id_arg(&interaction)?).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let key = (*interaction.user.id.as_u64(), poll.id);
+    start_draft(data, key);
+
+    component_resp::update(&ctx, &interaction, |d| draft_message(d, &poll, &[], 0)).await?;
+
+    Ok(())
+}
+
+pub async fn vote_submit(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let poll = match load_open_poll(&ctx, &interaction, data, poll_id_arg(&interaction)?).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    if !check_eligible(&ctx, &interaction, data, &poll).await? {
+        return Ok(());
+    }
+
+    let id_user = *interaction.user.id.as_u64();
+    let draft = match data.ballot_drafts.remove(&(id_user, poll.id)) {
+        Some((_, v)) if !v.choices.is_empty() => v.choices,
+        _ => {
+            component_resp::update(&ctx, &interaction, |d| draft_message(d, &poll, &[], 0)).await?;
+            return Ok(());
+        }
+    };
+
+    let ballot = draft.iter().enumerate().map(|(i, v)| (Choice::Option(*v), i as u8 + 1)).collect::<Vec<(Choice, u8)>>();
+    let (cast, existed) = match ballots::cast_ballot(&ctx.http, data.db_client.conn(), &poll, interaction.member.as_ref(), id_user, false, &ballot).await {
+        Ok(v) => v,
+        Err(e) => {
+            component_resp::reply_ephemeral(&ctx, &interaction, |d| d.content("Error occurred upon attempt to cast ballot.")).await?;
+            return Err(e);
+        }
+    };
+
+    // Within an election, offer the next question the member may vote on, if there is one. The
+    // ballot has been cast by now, so a failed lookup only loses the shortcut to the next question.
+    let (content, next) = match poll.id_group {
+        None => ("", None),
+        Some(id_group) => match next_group_poll(&ctx, &interaction, data, id_group, Some(poll.id)).await {
+            Ok(Some(_)) => ("", Some(id_group)),
+            Ok(None) => ("You have voted on every question in this election that you may vote on.", None),
+            Err(e) => {
+                get_logger().error("Failed to look up next poll in election.", meta! {
+                    "InteractionID" => interaction.id,
+                    "GroupID" => id_group,
+                    "Error" => e,
+                });
+                ("", None)
+            }
+        },
+    };
+
+    component_resp::update(&ctx, &interaction, |d| {
+        d.content(content);
+        d.create_embed(|e| ballots::ballot_embed(e, &poll, &cast, &existed));
+        d.components(|c| {
+            if let Some(id_group) = next {
+                c.create_action_row(|r| r.create_button(|b| b
+                    .custom_id(format!("{}:{}:{}", VOTE_GROUP, id_group, poll.id))
+                    .label("Next question")
+                    .style(ButtonStyle::Primary)));
+            }
+            c
+        })
+    }).await?;
+
+    Ok(())
+}
+
+pub async fn vote_withdraw(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let poll = match load_open_poll(&ctx, &interaction, data, poll_id_arg(&interaction)?).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let id_user = *interaction.user.id.as_u64();
+    data.ballot_drafts.remove(&(id_user, poll.id));
+
+    let withdrawn = match ballots::withdraw_ballot(&ctx.http, data.db_client.conn(), &poll, id_user).await {
+        Ok(v) => v,
+        Err(e) => {
+            component_resp::reply_ephemeral(&ctx, &interaction, |d| d.content("Error occurred upon attempt to withdraw ballot.")).await?;
+            return Err(e);
+        }
+    };
+
+    let content = match withdrawn {
+        None => format!("You have no ballot on poll **'{}'** to withdraw.", poll.name),
+        Some(v) => {
+            get_logger().info("User withdrew ballot.", meta! {
+                "InteractionID" => interaction.id,
+                "PollID" => poll.id,
+                "BallotID" => v.id,
+            });
+            format!("Withdrew your ballot on poll **'{}'**; it will not be counted unless you vote again.", poll.name)
+        }
+    };
+
+    component_resp::update(&ctx, &interaction, |d| {
+        d.content(content);
+        d.set_embeds(Vec::new());
+        d.components(|c| c)
+    }).await?;
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use evlog::meta;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::id::GuildId;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandInteractionDataOption, ApplicationCommandOptionType};
+use serenity::model::Permissions;
+
+use crate::db;
+use crate::db::schema::{GuildSettings, Method, Visibility};
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp};
+use crate::runtime::get_logger;
+use crate::support::validate;
+
+pub const EASYVOTE: &str = "easyvote";
+
+pub fn easyvote_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(EASYVOTE)
+        .description("Configure the bot for this server")
+        .create_option(|opt| opt
+            .name("config")
+            .description("Show or change this server's settings and poll defaults; only administrators may change them")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("manager-role")
+                .description("Let members with this role create, change, and close polls")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Role))
+            .create_sub_option(|opt| opt
+                .name("clear-manager-role")
+                .description("Only let administrators manage polls")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Boolean))
+            .create_sub_option(|opt| {
+                opt
+                    .name("results")
+                    .description("Who may see new polls' results while they are open, by default")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::String);
+
+                for visibility in Visibility::ALL {
+                    opt.add_string_choice(visibility.label(), visibility.as_str());
+                }
+
+                opt
+            })
+            .create_sub_option(|opt| {
+                opt
+                    .name("method")
+                    .description("How new polls' ballots are counted by default")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::String);
+
+                for method in Method::ALL {
+                    opt.add_string_choice(method.label(), method.as_str());
+                }
+
+                opt
+            })
+            .create_sub_option(|opt| opt
+                .name("ranks")
+                .description("How many choices new polls allow by default (min: 2, max: 20)")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Integer))
+            .create_sub_option(|opt| opt
+                .name("secret")
+                .description("Whether new polls use secret ballots by default; vote weights stay on the ballots")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Boolean))
+            .create_sub_option(|opt| opt
+                .name("announcement-channel")
+                .description("Announce new polls and elections in this channel")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Channel))
+            .create_sub_option(|opt| opt
+                .name("clear-announcement-channel")
+                .description("Announce new polls in the channel they are created in")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Boolean))
+            .create_sub_option(|opt| opt
+                .name("locale")
+                .description("This server's language and region, such as en-US")
+                .required(false)
+                .kind(ApplicationCommandOptionType::String)));
+
+    cmd
+}
+
+fn describe(settings: &GuildSettings) -> String {
+    let manager_role = match settings.id_manager_role {
+        None => "None; only administrators manage polls".to_owned(),
+        Some(v) => format!("<@&{}>", v),
+    };
+
+    let channel = match settings.id_announce_channel {
+        None => "The channel each poll is created in".to_owned(),
+        Some(v) => format!("<#{}>", v),
+    };
+    let ballots = match settings.secret {
+        true => "Secret",
+        false => "Not secret",
+    };
+
+    format!(
+        "**Manager role:** {}\n**Announcement channel:** {}\n**Locale:** {}\n\n\
+         Defaults for new polls:\n**Method:** {}\n**Ranks:** {}\n**Ballots:** {}\n**Results visible to:** {}",
+        manager_role, channel, settings.locale, settings.method.label(), settings.ranks, ballots, settings.results.label()
+    )
+}
+
+async fn easyvote_config(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    let mut settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    if opt.options.is_empty() {
+        command_resp::reply_deferred_result(&ctx, &interaction, describe(&settings)).await?;
+        return Ok(());
+    }
+
+    // Managers may not change settings, since that would let them choose who else is a manager.
+    let member = interaction.member.as_ref().unwrap();
+    if !member.permissions.map_or(false, |v| v.contains(Permissions::ADMINISTRATOR)) {
+        command_resp::reply_deferred_result(&ctx, &interaction, "Only members with the 'Administrator' permission may change this server's settings.").await?;
+        return Ok(());
+    }
+
+    if let Some(v) = command_opt::find_role_opt(&opt.options, "manager-role") {
+        settings.id_manager_role = Some(*v.as_u64());
+    }
+    if command_opt::find_boolean_opt(&opt.options, "clear-manager-role") == Some(true) {
+        settings.id_manager_role = None;
+    }
+    if let Some(v) = command_opt::find_string_opt(&opt.options, "results") {
+        settings.results = Visibility::parse(&v).unwrap();
+    }
+    if let Some(v) = command_opt::find_string_opt(&opt.options, "method") {
+        settings.method = Method::parse(&v).unwrap();
+    }
+    if let Some(v) = command_opt::find_boolean_opt(&opt.options, "secret") {
+        settings.secret = v;
+    }
+    if let Some(v) = command_opt::find_channel_opt(&opt.options, "announcement-channel") {
+        settings.id_announce_channel = Some(*v.as_u64());
+    }
+    if command_opt::find_boolean_opt(&opt.options, "clear-announcement-channel") == Some(true) {
+        settings.id_announce_channel = None;
+    }
+
+    if let Some(v) = command_opt::find_integer_opt(&opt.options, "ranks") {
+        settings.ranks = match validate::ranks(v) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(());
+            }
+        };
+    }
+    if let Some(v) = command_opt::find_string_opt(&opt.options, "locale") {
+        settings.locale = match validate::locale(&v) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(());
+            }
+        };
+    }
+
+    if let Err(e) = db::model::set_guild_settings(data.db_client.conn(), *guild_id.as_u64(), &settings).await {
+        command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to save settings.").await?;
+        return Err(e);
+    }
+
+    get_logger().info("Updated server settings.", meta! {
+        "InteractionID" => interaction.id,
+        "GuildID" => guild_id,
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!("Updated this server's settings.\n{}", describe(&settings))).await?;
+
+    Ok(())
+}
+
+pub async fn easyvote(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    command_resp::reply_deferred_ack_ephemeral(&ctx, &interaction).await?;
+
+    let sub = &interaction.data.options[0];
+
+    let guild_id = interaction.guild_id.as_ref().unwrap();
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    if sub.name == "config" {
+        easyvote_config(&ctx, &interaction, sub, data, guild_id).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,519 @@
+use std::borrow::Cow;
+
+use evlog::meta;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::http::AttachmentType;
+use serenity::model::guild::Member;
+use serenity::model::id::GuildId;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandInteractionDataOption, ApplicationCommandOptionType};
+use serenity::model::interactions::InteractionApplicationCommandCallbackDataFlags;
+
+use crate::db;
+use crate::db::schema::PollGroup;
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp};
+use crate::runtime::get_logger;
+use crate::support::{groups, permissions, poll_status, tally, validate};
+
+pub const ELECTION: &str = "election";
+
+pub fn election_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(ELECTION)
+        .description("Manage elections: several polls voted on together")
+        .create_option(|opt| opt
+            .name("create")
+            .description("Create an election to add polls to")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("name")
+                .description("Unique identifier for this election; no whitespace, may only contain a-z, 0-9, and -")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("title")
+                .description("What the election is for, e.g. 'Officer elections'")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("duration")
+                .description("Hours until every poll in the election closes; omit to close it manually")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Integer)))
+        .create_option(|opt| opt
+            .name("add")
+            .description("Add an open poll to an election; it takes on the election's deadline")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("name")
+                .description("The name of the election")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("poll")
+                .description("The name of the poll to add")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|opt| opt
+            .name("remove")
+            .description("Remove a poll from an election")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("name")
+                .description("The name of the election")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("poll")
+                .description("The name of the poll to remove")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|opt| opt
+            .name("schedule")
+            .description("Change when an election and every poll in it closes")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("name")
+                .description("The name of the election")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("duration")
+                .description("Hours from now until the election closes; 0 to close it manually")
+                .required(true)
+                .kind(ApplicationCommandOptionType::Integer)))
+        .create_option(|opt| opt
+            .name("close")
+            .description("Close an election and every poll in it")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("name")
+                .description("The name of the election")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|opt| opt
+            .name("results")
+            .description("Tally every poll in an election")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("name")
+                .description("The name of the election")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String)));
+
+    cmd
+}
+
+// Replies and returns `None` unless the server has an election named `name`.
+async fn find_group(ctx: &Context, interaction: &ApplicationCommandInteraction, data: &BotData, guild_id: &GuildId, name: &str) -> anyhow::Result<Option<PollGroup>> {
+    match db::model::get_server_poll_group(data.db_client.conn(), *guild_id.as_u64(), name).await {
+        Ok(Some(v)) => Ok(Some(v)),
+        Ok(None) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, format!("No election named **'{}'** was found.", name)).await?;
+            Ok(None)
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up election.").await?;
+            Err(e)
+        }
+    }
+}
+
+// Refreshes the status messages of a group and its polls, logging rather than failing, since the
+// change they reflect has already been made.
+async fn refresh_status_messages(ctx: &Context, data: &BotData, id_group: i32, id_polls: &[i32]) {
+    for id_poll in id_polls {
+        if let Err(e) = poll_status::update_status_message(&ctx.http, data.db_client.conn(), *id_poll).await {
+            get_logger().error("Failed to update poll status message.", meta! {
+                "PollID" => id_poll,
+                "Error" => e,
+            });
+        }
+    }
+
+    if let Err(e) = groups::update_status_message(&ctx.http, data.db_client.conn(), id_group).await {
+        get_logger().error("Failed to update election status message.", meta! {
+            "GroupID" => id_group,
+            "Error" => e,
+        });
+    }
+}
+
+async fn election_create(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId, member: &Member) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/election create").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let title = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "title").await?.unwrap();
+
+    //
+
+    let name = match validate::poll_name(&name) {
+        Ok(v) => v,
+        Err(e) => {
+            get_logger().info("Invalid name passed to /election create.", meta! {
+                "InteractionID" => interaction.id,
+                "Name" => name,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    let time_close = match command_opt::find_integer_opt(&opt.options, "duration").map(validate::deadline) {
+        None => None,
+        Some(Ok(v)) => Some(v),
+        Some(Err(e)) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    match db::model::get_server_poll_group(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, format!(
+                "An election with the name `{}` has already been created in this server.", name
+            )).await?;
+            return Ok(());
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to check for existing election by name.").await?;
+            return Err(e);
+        }
+    }
+
+    //
+
+    let group = match db::model::add_poll_group(data.db_client.conn(), *guild_id.as_u64(), *member.user.id.as_u64(), &name, title.trim(), time_close).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to add election to database.").await?;
+            return Err(e);
+        }
+    };
+
+    get_logger().info("Created election.", meta! {
+        "InteractionID" => interaction.id,
+        "GroupID" => group.id,
+        "GuildID" => guild_id,
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!(
+        "Created election **'{}'**; add polls to it with `/election add`.", group.name
+    )).await?;
+
+    let channel = super::poll::announce_channel(interaction, data, guild_id).await?;
+
+    groups::post_status_message(&ctx.http, data.db_client.conn(), &group, channel).await?;
+
+    Ok(())
+}
+
+async fn election_add(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/election add").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let poll_name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "poll").await?.unwrap();
+
+    let group = match find_group(ctx, interaction, data, guild_id, &name).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    if !group.open {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!("Election **'{}'** is closed.", group.name)).await?;
+        return Ok(());
+    }
+
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &poll_name).await {
+        Ok(v) => match v {
+            Some(v) if v.open => v,
+            _ => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("No open poll named **'{}'** was found.", poll_name)).await?;
+                return Ok(());
+            }
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
+            return Err(e);
+        }
+    };
+
+    if poll.nominations.is_some() {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "Poll **'{}'** is still collecting nominations; add it once voting has opened.", poll.name
+        )).await?;
+        return Ok(());
+    }
+
+    match poll.id_group {
+        None => {}
+        Some(v) if v == group.id => {
+            command_resp::reply_deferred_result(&ctx, &interaction, format!(
+                "Poll **'{}'** is already part of election **'{}'**.", poll.name, group.name
+            )).await?;
+            return Ok(());
+        }
+        Some(_) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, format!(
+                "Poll **'{}'** is already part of another election; remove it from that one first.", poll.name
+            )).await?;
+            return Ok(());
+        }
+    }
+
+    let id_polls = db::model::list_group_polls(data.db_client.conn(), group.id).await?
+        .into_iter()
+        .map(|v| v.id)
+        .collect::<Vec<i32>>();
+
+    if id_polls.len() >= validate::MAX_GROUP_POLLS {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "Election **'{}'** already has the most polls allowed ({}).", group.name, validate::MAX_GROUP_POLLS
+        )).await?;
+        return Ok(());
+    }
+
+    //
+
+    db::model::set_poll_group(data.db_client.conn(), poll.id, Some(group.id), group.time_close).await?;
+
+    get_logger().info("Added poll to election.", meta! {
+        "InteractionID" => interaction.id,
+        "PollID" => poll.id,
+        "GroupID" => group.id,
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!(
+        "Added poll **'{}'** to election **'{}'**; it now closes with the election.", poll.name, group.name
+    )).await?;
+
+    refresh_status_messages(ctx, data, group.id, &[poll.id]).await;
+
+    Ok(())
+}
+
+async fn election_remove(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/election remove").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let poll_name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "poll").await?.unwrap();
+
+    let group = match find_group(ctx, interaction, data, guild_id, &name).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &poll_name).await {
+        Ok(v) => match v {
+            Some(v) if v.id_group == Some(group.id) => v,
+            _ => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!(
+                    "Election **'{}'** has no poll named **'{}'**.", group.name, poll_name
+                )).await?;
+                return Ok(());
+            }
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
+            return Err(e);
+        }
+    };
+
+    // The poll keeps the election's deadline, which can then be changed with `/poll edit`.
+    db::model::set_poll_group(data.db_client.conn(), poll.id, None, poll.time_close).await?;
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!(
+        "Removed poll **'{}'** from election **'{}'**.", poll.name, group.name
+    )).await?;
+
+    refresh_status_messages(ctx, data, group.id, &[poll.id]).await;
+
+    Ok(())
+}
+
+async fn election_schedule(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/election schedule").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let duration = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_integer_opt, "duration").await?.unwrap();
+
+    let group = match find_group(ctx, interaction, data, guild_id, &name).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    if !group.open {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!("Election **'{}'** is closed.", group.name)).await?;
+        return Ok(());
+    }
+
+    // A duration of 0 removes the deadline.
+    let time_close = match duration {
+        0 => None,
+        v => match validate::deadline(v) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(());
+            }
+        }
+    };
+
+    db::model::set_group_deadline(data.db_client.conn(), group.id, time_close).await?;
+
+    command_resp::reply_deferred_result(&ctx, &interaction, match time_close {
+        None => format!("Election **'{}'** will stay open until it is closed with `/election close`.", group.name),
+        Some(v) => format!("Election **'{}'** now closes <t:{}:R>.", group.name, v.timestamp()),
+    }).await?;
+
+    let id_polls = db::model::list_group_polls(data.db_client.conn(), group.id).await?
+        .into_iter()
+        .map(|v| v.id)
+        .collect::<Vec<i32>>();
+
+    refresh_status_messages(ctx, data, group.id, &id_polls).await;
+
+    Ok(())
+}
+
+async fn election_close(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/election close").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+
+    let group = match find_group(ctx, interaction, data, guild_id, &name).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    if !group.open {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!("Election **'{}'** is already closed.", group.name)).await?;
+        return Ok(());
+    }
+
+    let announcement = match groups::close(&ctx.http, data.db_client.conn(), &group).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to close election.").await?;
+            return Err(e);
+        }
+    };
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!("Closed election **'{}'**.", group.name)).await?;
+    interaction.channel_id.say(&ctx.http, announcement).await?;
+
+    Ok(())
+}
+
+async fn election_results(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+
+    let group = match db::model::get_server_poll_group(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => match v {
+            None => {
+                command_resp::reply_deferred_private(&ctx, &interaction, format!("Failed to find election with name **'{}'**.", name)).await?;
+                return Ok(());
+            }
+            Some(v) => v,
+        }
+        Err(e) => {
+            command_resp::reply_deferred_private(&ctx, &interaction, "Error occurred upon attempt to look up election.").await?;
+            return Err(e);
+        }
+    };
+
+    let polls = match db::model::list_group_polls(data.db_client.conn(), group.id).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_private(&ctx, &interaction, "Error occurred upon attempt to look up election's polls.").await?;
+            return Err(e);
+        }
+    };
+
+    if polls.is_empty() {
+        command_resp::reply_deferred_private(&ctx, &interaction, format!("Election **'{}'** has no polls.", group.name)).await?;
+        return Ok(());
+    }
+
+    let member = interaction.member.as_ref().unwrap();
+    let settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+    if polls.iter().any(|poll| !permissions::may_tally(&settings, poll, member)) {
+        command_resp::reply_deferred_private(&ctx, &interaction, format!(
+            "Results of election **'{}'** are hidden until its polls close.", group.name
+        )).await?;
+        return Ok(());
+    }
+
+    let mut results = Vec::new();
+    for poll in polls {
+        match tally::tally(&ctx.http, data.db_client.conn(), &poll).await {
+            Ok(v) => results.push((poll, v)),
+            Err(e) => {
+                command_resp::reply_deferred_private(&ctx, &interaction, format!(
+                    "Error occurred upon attempt to tally poll **'{}'**.", poll.name
+                )).await?;
+                return Err(e);
+            }
+        }
+    }
+
+    // As with /poll tally, results hidden from others are only shown to the member who asked.
+    let private = results.iter().any(|(poll, _)| !permissions::results_public(poll));
+    if private {
+        interaction.delete_original_interaction_response(&ctx.http).await?;
+    }
+
+    //
+
+    interaction.create_followup_message(&ctx.http, |r| {
+        if private {
+            r.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+        }
+        r.create_embed(|e| groups::results_embed(e, &group, &results))
+            .add_file(AttachmentType::Bytes {
+                data: Cow::from(groups::receipt_list(&results).into_bytes()),
+                filename: format!("receipts-{}.txt", group.name),
+            })
+    }).await?;
+
+    Ok(())
+}
+
+pub async fn election(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    let sub = &interaction.data.options[0];
+
+    // Results are for everyone; confirmations and errors only for the invoking member.
+    match sub.name.as_str() {
+        "results" => command_resp::reply_deferred_ack(&ctx, &interaction).await?,
+        _ => command_resp::reply_deferred_ack_ephemeral(&ctx, &interaction).await?,
+    }
+
+    let guild_id = interaction.guild_id.as_ref().unwrap();
+    let member = interaction.member.as_ref().unwrap();
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    match sub.name.as_str() {
+        "create" => election_create(&ctx, &interaction, sub, data, guild_id, member).await?,
+        "add" => election_add(&ctx, &interaction, sub, data, guild_id).await?,
+        "remove" => election_remove(&ctx, &interaction, sub, data, guild_id).await?,
+        "schedule" => election_schedule(&ctx, &interaction, sub, data, guild_id).await?,
+        "close" => election_close(&ctx, &interaction, sub, data, guild_id).await?,
+        "results" => election_results(&ctx, &interaction, sub, data, guild_id).await?,
+        _ => {}
+    }
+
+    Ok(())
+}
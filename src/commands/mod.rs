@@ -1,26 +1,144 @@
+pub mod ballot;
+mod easyvote;
+mod election;
+mod nomination;
 mod poll;
+mod template;
+pub mod wizard;
 
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
-use crate::helpers::command_def::{CommandDef, InteractionHandler};
+use crate::helpers::command_def::{AutocompleteHandler, CommandDef, ComponentDef, ComponentHandler, InteractionHandler, ModalDef, ModalHandler};
 
 pub const COMMANDS: &[CommandDef] = &[
     CommandDef {
         name: poll::POLL,
         builder: poll::poll_builder,
         handler: |c, i| Box::pin(async move { poll::poll(c, i).await }),
-        re_register: false,
+        autocomplete: None,
+        re_register: true,
         whitelisted_servers: None,
     },
     CommandDef {
-        name: "vote",
-        builder: |cmd| cmd,
+        name: poll::VOTE,
+        builder: poll::vote_builder,
         handler: |c, i| Box::pin(async move { poll::vote(c, i).await }),
-        re_register: false,
+        autocomplete: Some(|c, i| Box::pin(async move { poll::vote_autocomplete(c, i).await })),
+        re_register: true,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: election::ELECTION,
+        builder: election::election_builder,
+        handler: |c, i| Box::pin(async move { election::election(c, i).await }),
+        autocomplete: None,
+        re_register: true,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: template::TEMPLATE,
+        builder: template::template_builder,
+        handler: |c, i| Box::pin(async move { template::template(c, i).await }),
+        autocomplete: None,
+        re_register: true,
+        whitelisted_servers: None,
+    },
+    CommandDef {
+        name: easyvote::EASYVOTE,
+        builder: easyvote::easyvote_builder,
+        handler: |c, i| Box::pin(async move { easyvote::easyvote(c, i).await }),
+        autocomplete: None,
+        re_register: true,
         whitelisted_servers: None,
     }
 ];
 
+pub const COMPONENTS: &[ComponentDef] = &[
+    ComponentDef {
+        prefix: ballot::VOTE,
+        handler: |c, i| Box::pin(async move { ballot::vote_start(c, i).await }),
+    },
+    ComponentDef {
+        prefix: ballot::VOTE_GROUP,
+        handler: |c, i| Box::pin(async move { ballot::vote_group(c, i).await }),
+    },
+    ComponentDef {
+        prefix: ballot::VOTE_DM,
+        handler: |c, i| Box::pin(async move { ballot::vote_dm(c, i).await }),
+    },
+    ComponentDef {
+        prefix: ballot::VOTE_DM_POLL,
+        handler: |c, i| Box::pin(async move { ballot::vote_dm_poll(c, i).await }),
+    },
+    ComponentDef {
+        prefix: ballot::VOTE_PICK,
+        handler: |c, i| Box::pin(async move { ballot::vote_pick(c, i).await }),
+    },
+    ComponentDef {
+        prefix: ballot::VOTE_PAGE,
+        handler: |c, i| Box::pin(async move { ballot::vote_page(c, i).await }),
+    },
+    ComponentDef {
+        prefix: ballot::VOTE_RESET,
+        handler: |c, i| Box::pin(async move { ballot::vote_reset(c, i).await }),
+    },
+    ComponentDef {
+        prefix: ballot::VOTE_SUBMIT,
+        handler: |c, i| Box::pin(async move { ballot::vote_submit(c, i).await }),
+    },
+    ComponentDef {
+        prefix: ballot::VOTE_WITHDRAW,
+        handler: |c, i| Box::pin(async move { ballot::vote_withdraw(c, i).await }),
+    },
+    ComponentDef {
+        prefix: wizard::WIZARD_METHOD,
+        handler: |c, i| Box::pin(async move { wizard::wizard_method(c, i).await }),
+    },
+    ComponentDef {
+        prefix: wizard::WIZARD_SEATS,
+        handler: |c, i| Box::pin(async move { wizard::wizard_seats(c, i).await }),
+    },
+    ComponentDef {
+        prefix: wizard::WIZARD_RANKS,
+        handler: |c, i| Box::pin(async move { wizard::wizard_ranks(c, i).await }),
+    },
+    ComponentDef {
+        prefix: wizard::WIZARD_DURATION,
+        handler: |c, i| Box::pin(async move { wizard::wizard_duration(c, i).await }),
+    },
+    ComponentDef {
+        prefix: wizard::WIZARD_SECRET,
+        handler: |c, i| Box::pin(async move { wizard::wizard_secret(c, i).await }),
+    },
+    ComponentDef {
+        prefix: wizard::WIZARD_OPTIONS,
+        handler: |c, i| Box::pin(async move { wizard::wizard_options(c, i).await }),
+    },
+    ComponentDef {
+        prefix: wizard::WIZARD_ELIGIBILITY,
+        handler: |c, i| Box::pin(async move { wizard::wizard_eligibility(c, i).await }),
+    },
+    ComponentDef {
+        prefix: wizard::WIZARD_ROLES,
+        handler: |c, i| Box::pin(async move { wizard::wizard_roles(c, i).await }),
+    },
+    ComponentDef {
+        prefix: wizard::WIZARD_CONFIRM,
+        handler: |c, i| Box::pin(async move { wizard::wizard_confirm(c, i).await }),
+    },
+    ComponentDef {
+        prefix: wizard::WIZARD_CANCEL,
+        handler: |c, i| Box::pin(async move { wizard::wizard_cancel(c, i).await }),
+    },
+];
+
+pub const MODALS: &[ModalDef] = &[
+    ModalDef {
+        prefix: wizard::WIZARD_OPTIONS_MODAL,
+        handler: |c, i| Box::pin(async move { wizard::wizard_options_submit(c, i).await }),
+    },
+];
+
 static COMMAND_MAP: Lazy<DashMap<String, InteractionHandler>> = Lazy::new(|| {
     let map = DashMap::new();
 
@@ -37,3 +155,60 @@ pub fn get_handler(command_name: &str) -> Option<InteractionHandler> {
         .as_ref()
         .map(|entry| *entry.value())
 }
+
+static AUTOCOMPLETE_MAP: Lazy<DashMap<String, AutocompleteHandler>> = Lazy::new(|| {
+    let map = DashMap::new();
+
+    for cmd in COMMANDS {
+        if let Some(autocomplete) = cmd.autocomplete {
+            map.insert(cmd.name.to_string(), autocomplete);
+        }
+    }
+
+    map
+});
+
+pub fn get_autocomplete_handler(command_name: &str) -> Option<AutocompleteHandler> {
+    AUTOCOMPLETE_MAP
+        .get(command_name)
+        .as_ref()
+        .map(|entry| *entry.value())
+}
+
+static COMPONENT_MAP: Lazy<DashMap<String, ComponentHandler>> = Lazy::new(|| {
+    let map = DashMap::new();
+
+    for component in COMPONENTS {
+        map.insert(component.prefix.to_string(), component.handler);
+    }
+
+    map
+});
+
+pub fn get_component_handler(custom_id: &str) -> Option<ComponentHandler> {
+    let prefix = custom_id.split(':').next()?;
+
+    COMPONENT_MAP
+        .get(prefix)
+        .as_ref()
+        .map(|entry| *entry.value())
+}
+
+static MODAL_MAP: Lazy<DashMap<String, ModalHandler>> = Lazy::new(|| {
+    let map = DashMap::new();
+
+    for modal in MODALS {
+        map.insert(modal.prefix.to_string(), modal.handler);
+    }
+
+    map
+});
+
+pub fn get_modal_handler(custom_id: &str) -> Option<ModalHandler> {
+    let prefix = custom_id.split(':').next()?;
+
+    MODAL_MAP
+        .get(prefix)
+        .as_ref()
+        .map(|entry| *entry.value())
+}
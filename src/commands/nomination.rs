@@ -0,0 +1,257 @@
+use evlog::meta;
+use serenity::client::Context;
+use serenity::model::guild::Member;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandInteractionDataOption};
+
+use crate::db;
+use crate::db::schema::Nominations;
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp};
+use crate::runtime::get_logger;
+use crate::support::{eligibility, nominations, poll_status, validate};
+
+/// Creates a poll that collects its options from members with `/poll nominate` before voting opens.
+pub async fn poll_nominations(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId, member: &Member) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/poll nominations").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let question = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "question").await?.unwrap();
+
+    //
+
+    let name = match validate::poll_name(&name) {
+        Ok(v) => v,
+        Err(e) => {
+            get_logger().info("Invalid name passed to /poll nominations.", meta! {
+                "InteractionID" => interaction.id,
+                "Name" => name,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    let guild_settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+    let mut settings = guild_settings.poll_settings();
+
+    if let Some(ranks) = command_opt::find_integer_opt(&opt.options, "ranks") {
+        settings.ranks = match validate::ranks(ranks) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(());
+            }
+        };
+    }
+
+    let seconds = match command_opt::find_integer_opt(&opt.options, "seconds").map(validate::seconds) {
+        None => 0,
+        Some(Ok(v)) => v,
+        Some(Err(e)) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    let time_close = match command_opt::find_integer_opt(&opt.options, "duration").map(validate::deadline) {
+        None => None,
+        Some(Ok(v)) => Some(v),
+        Some(Err(e)) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    // Validated like a deadline, but only applied once voting opens.
+    let voting_hours = match command_opt::find_integer_opt(&opt.options, "voting-duration") {
+        None => None,
+        Some(v) => match validate::hours_from_now("voting-duration", v) {
+            Ok(_) => Some(v as u32),
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(());
+            }
+        },
+    };
+
+    //
+
+    let server_has_poll_name = match db::model::check_server_has_poll_name(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to check for existing poll by name.").await?;
+            return Err(e);
+        }
+    };
+
+    if server_has_poll_name {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "A poll with the name `{}` has already been created in this server.", name
+        )).await?;
+        return Ok(());
+    }
+
+    let rules = Nominations {
+        seconds,
+        time_close,
+        voting_hours,
+    };
+
+    let poll = match db::model::add_poll(
+        data.db_client.conn(),
+        *guild_id.as_u64(),
+        *member.user.id.as_u64(),
+        &name,
+        &question,
+        &settings,
+        None,
+        Some(&rules),
+        &[],
+    ).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to add poll to database.").await?;
+            return Err(e);
+        }
+    };
+
+    let channel = guild_settings.id_announce_channel.map_or(interaction.channel_id, ChannelId);
+
+    // The poll exists by now, so its creator is told it was created even if this fails.
+    let posted = match poll_status::post_status_message(&ctx.http, data.db_client.conn(), &poll, channel).await {
+        Ok(_) => true,
+        Err(e) => {
+            get_logger().error("Failed to post poll status message.", meta! {
+                "PollID" => poll.id,
+                "ChannelID" => channel,
+                "Error" => e,
+            });
+            false
+        }
+    };
+
+    command_resp::reply_deferred_result(&ctx, &interaction, match posted {
+        true => format!("Created poll **'{}'**; members can now nominate options with `/poll nominate`.", poll.name),
+        false => format!(
+            "Created poll **'{}'**, but its status message could not be posted in <#{}>; members can still nominate options with `/poll nominate`.", poll.name, channel
+        ),
+    }).await?;
+
+    Ok(())
+}
+
+/// Nominates an option for a poll in its nomination phase, or seconds it if someone else already has.
+pub async fn poll_nominate(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId, member: &Member) -> anyhow::Result<()> {
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let option = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "option").await?.unwrap();
+    let option = option.trim();
+
+    let id_user = *member.user.id.as_u64();
+
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &name).await? {
+        None => {
+            command_resp::reply_deferred_result(&ctx, &interaction, format!("Failed to find poll with name **'{}'**.", name)).await?;
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    if !poll.accepting_nominations() {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!("Poll **'{}'** is not collecting nominations.", poll.name)).await?;
+        return Ok(());
+    }
+
+    // Proposing options is part of taking part in the poll, so it is limited to those who may vote.
+    if let Some(reason) = eligibility::check(&ctx.http, data.db_client.conn(), &poll, Some(member), id_user).await? {
+        command_resp::reply_deferred_result(&ctx, &interaction, reason).await?;
+        return Ok(());
+    }
+
+    // Nominations become options as written, so they are held to the same rules now rather than
+    // keeping voting from starting later.
+    let (option_name, _) = match validate::option(option) {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    let nominated = db::model::get_nominations(data.db_client.conn(), poll.id).await?;
+
+    let reply = match nominations::find(&nominated, &option_name) {
+        Some(existing) => {
+            if existing.id_nominated_by == id_user || existing.id_seconded_by.contains(&id_user) {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("You already support **'{}'**.", existing.option)).await?;
+                return Ok(());
+            }
+
+            db::model::add_nomination(data.db_client.conn(), poll.id, &existing.option, id_user).await?;
+            format!("Seconded **'{}'** on poll **'{}'**.", existing.option, poll.name)
+        }
+        None => {
+            if nominated.len() >= validate::MAX_OPTIONS {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!(
+                    "Poll **'{}'** already has the maximum of {} nominations.", poll.name, validate::MAX_OPTIONS
+                )).await?;
+                return Ok(());
+            }
+
+            db::model::add_nomination(data.db_client.conn(), poll.id, option, id_user).await?;
+            format!("Nominated **'{}'** on poll **'{}'**.", option, poll.name)
+        }
+    };
+
+    get_logger().info("Member supported a nomination.", meta! {
+        "InteractionID" => interaction.id,
+        "PollID" => poll.id,
+        "Option" => option,
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, reply).await?;
+
+    poll_status::update_status_message(&ctx.http, data.db_client.conn(), poll.id).await?;
+
+    Ok(())
+}
+
+/// Ends a poll's nomination phase now and opens voting on the qualifying nominations.
+pub async fn poll_promote(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &name).await? {
+        None => {
+            command_resp::reply_deferred_result(&ctx, &interaction, format!("Failed to find poll with name **'{}'**.", name)).await?;
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    if !super::poll::check_poll_manager(ctx, interaction, data, &poll, "/poll promote").await? {
+        return Ok(());
+    }
+
+    if !poll.open {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!("Poll **'{}'** is closed.", poll.name)).await?;
+        return Ok(());
+    }
+
+    let n_options = match nominations::start_voting(data.db_client.conn(), &poll).await? {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!("Opened voting on poll **'{}'** with {} option(s).", poll.name, n_options)).await?;
+
+    interaction.channel_id.say(&ctx.http, nominations::voting_announcement(&poll.name, n_options)).await?;
+
+    poll_status::update_status_message(&ctx.http, data.db_client.conn(), poll.id).await?;
+
+    Ok(())
+}
@@ -1,26 +1,33 @@
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 
 use evlog::meta;
 use itertools::Itertools;
-use once_cell::sync::Lazy;
-use regex::Regex;
 use serenity::builder::CreateApplicationCommand;
 use serenity::client::Context;
+use serenity::http::AttachmentType;
 use serenity::model::guild::Member;
-use serenity::model::id::GuildId;
+use serenity::model::id::{ChannelId, GuildId};
 use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandInteractionDataOptionValue, ApplicationCommandOptionType};
-use serenity::model::Permissions;
+use serenity::model::interactions::autocomplete::AutocompleteInteraction;
+use serenity::model::interactions::InteractionApplicationCommandCallbackDataFlags;
+use serenity::model::interactions::message_component::ButtonStyle;
 use serenity::model::prelude::application_command::ApplicationCommandInteractionDataOption;
 
-use crate::{db, stv};
+use crate::db;
+use crate::commands::{ballot, nomination, template, wizard};
+use crate::db::schema::{Choice, Method, OptionDetails, Poll, Visibility};
 use crate::handler::BotData;
 use crate::helpers::{command_opt, command_resp};
 use crate::runtime::get_logger;
+use crate::support::{ballots, eligibility, permissions, poll_status, quorum, receipts, runoff, tally, text, validate, voter_roll};
 use crate::support::numbers::num_word;
 
 pub const POLL: &str = "poll";
+pub const VOTE: &str = "vote";
 
-static VALIDATE_POLL_NAME: Lazy<Regex> = Lazy::new(|| Regex::new("^[a-z0-9-]+$").unwrap());
+// Discord allows at most 25 autocomplete suggestions.
+const AUTOCOMPLETE_LIMIT: usize = 25;
 
 pub fn poll_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
     cmd.name(POLL)
@@ -41,14 +48,9 @@ pub fn poll_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                     .description("The question you want users to vote on")
                     .required(true)
                     .kind(ApplicationCommandOptionType::String))
-                .create_sub_option(|opt| opt
-                    .name("ranks")
-                    .description("How many choices to allow (suggested: 3, min: 2, max: 20)")
-                    .required(true)
-                    .kind(ApplicationCommandOptionType::Integer))
                 .create_sub_option(|opt| opt
                     .name("opt-1")
-                    .description("Option 1")
+                    .description("Option 1; add details as `Name | description | link | emoji`")
                     .required(true)
                     .kind(ApplicationCommandOptionType::String))
                 .create_sub_option(|opt| opt
@@ -60,7 +62,12 @@ pub fn poll_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                     .name("opt-3")
                     .description("Option 3")
                     .required(true)
-                    .kind(ApplicationCommandOptionType::String));
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| opt
+                    .name("ranks")
+                    .description("How many choices to allow (min: 2, max: 20); defaults to the server's setting")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer));
 
             for i in 4..=20 {
                 opt.create_sub_option(|opt| opt
@@ -70,7 +77,319 @@ pub fn poll_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                     .kind(ApplicationCommandOptionType::String));
             }
 
+            opt.create_sub_option(|opt| opt
+                .name("duration")
+                .description("Hours until voting closes automatically (max: 2160); omit to close manually")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Integer));
+
+            opt
+        })
+        .create_option(|opt| {
+            opt
+                .name("wizard")
+                .description("Create a new poll step by step, with any number of options")
+                .kind(ApplicationCommandOptionType::SubCommand)
+
+                .create_sub_option(|opt| opt
+                    .name("name")
+                    .description("Unique identifier for this poll; no whitespace, may only contain a-z, 0-9, and -")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| opt
+                    .name("question")
+                    .description("The question you want users to vote on")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+        })
+        .create_option(|opt| {
+            opt
+                .name("nominations")
+                .description("Create a new poll whose options are nominated by members before voting opens")
+                .kind(ApplicationCommandOptionType::SubCommand)
+
+                .create_sub_option(|opt| opt
+                    .name("name")
+                    .description("Unique identifier for this poll; no whitespace, may only contain a-z, 0-9, and -")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| opt
+                    .name("question")
+                    .description("The question you want users to vote on")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| opt
+                    .name("ranks")
+                    .description("How many choices to allow (min: 2, max: 20); defaults to the server's setting")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("seconds")
+                    .description("Members besides the nominator who must second a nomination (default: 0, max: 100)")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("duration")
+                    .description("Hours until nominations close and voting opens (max: 2160); omit to use /poll promote")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("voting-duration")
+                    .description("Hours voting stays open once it starts (max: 2160); omit to close manually")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+        })
+        .create_option(|opt| {
+            opt
+                .name("nominate")
+                .description("Nominate an option for a poll, or second one someone else nominated")
+                .kind(ApplicationCommandOptionType::SubCommand)
+
+                .create_sub_option(|opt| opt
+                    .name("name")
+                    .description("The name of the poll")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| opt
+                    .name("option")
+                    .description("The option to nominate or second")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+        })
+        .create_option(|opt| {
+            opt
+                .name("promote")
+                .description("Close nominations on a poll and open voting on the seconded nominations")
+                .kind(ApplicationCommandOptionType::SubCommand)
+
+                .create_sub_option(|opt| opt
+                    .name("name")
+                    .description("The name of the poll")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+        })
+        .create_option(|opt| {
+            opt
+                .name("edit")
+                .description("Change the rules of an open poll")
+                .kind(ApplicationCommandOptionType::SubCommand)
+
+                .create_sub_option(|opt| opt
+                    .name("name")
+                    .description("The name of the poll to edit")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| {
+                    opt
+                        .name("method")
+                        .description("How ballots are counted; only before any ballots are cast")
+                        .required(false)
+                        .kind(ApplicationCommandOptionType::String);
+
+                    for method in Method::ALL {
+                        opt.add_string_choice(method.label(), method.as_str());
+                    }
+
+                    opt
+                })
+                .create_sub_option(|opt| opt
+                    .name("seats")
+                    .description("Number of winners for single transferable vote; only before any ballots are cast")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("ranks")
+                    .description("How many choices to allow (min: 2, max: 20); only before any ballots are cast")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("quorum")
+                    .description("Ballots needed for the results to count; 0 removes the quorum")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("quorum-percent")
+                    .description("Percentage of eligible voters needed for the results to count; 0 removes the quorum")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("secret")
+                    .description("Hide who cast each ballot; vote weights stay visible. Only before any ballots are cast")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Boolean))
+                .create_sub_option(|opt| opt
+                    .name("write-ins")
+                    .description("Let voters rank candidates not listed on the poll with /vote cast; only before any ballots are cast")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Boolean))
+                .create_sub_option(|opt| opt
+                    .name("duration")
+                    .description("Hours from now until voting closes (max: 2160); 0 removes the deadline")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("runoff")
+                    .description("For the runoff method, how many options go to a runoff without a majority (min: 2, max: 10)")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("runoff-duration")
+                    .description("Hours a runoff stays open (max: 2160); 0 to close it manually")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("reminders")
+                    .description("Hours before the deadline to remind members who have not voted, e.g. 24, 1; none to stop")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| opt
+                    .name("remind-by")
+                    .description("How to send reminders")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::String)
+                    .add_string_choice("Mention members in the poll's channel", "mention")
+                    .add_string_choice("DM members", "dm"))
+                .create_sub_option(|opt| {
+                    opt
+                        .name("results")
+                        .description("Who may see results while the poll is open")
+                        .required(false)
+                        .kind(ApplicationCommandOptionType::String);
+
+                    for visibility in Visibility::ALL {
+                        opt.add_string_choice(visibility.label(), visibility.as_str());
+                    }
+
+                    opt
+                })
+        })
+        .create_option(|opt| {
+            opt
+                .name("eligibility")
+                .description("Restrict who may vote on an open poll; only before any ballots are cast")
+                .kind(ApplicationCommandOptionType::SubCommand)
+
+                .create_sub_option(|opt| opt
+                    .name("name")
+                    .description("The name of the poll to restrict")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| opt
+                    .name("require-role")
+                    .description("Allow members with this role; once any are added, only they may vote")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Role))
+                .create_sub_option(|opt| opt
+                    .name("exclude-role")
+                    .description("Prevent members with this role from voting")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Role))
+                .create_sub_option(|opt| opt
+                    .name("remove-role")
+                    .description("Remove this role from the required and excluded roles")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Role))
+                .create_sub_option(|opt| opt
+                    .name("member-days")
+                    .description("Days voters must have been in this server (max: 3650); 0 removes the minimum")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("account-days")
+                    .description("Minimum age of voters' Discord accounts in days (max: 3650); 0 removes the minimum")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+        })
+        .create_option(|opt| {
+            opt
+                .name("weights")
+                .description("Give some voters' ballots more weight; only before any ballots are cast")
+                .kind(ApplicationCommandOptionType::SubCommand)
+
+                .create_sub_option(|opt| opt
+                    .name("name")
+                    .description("The name of the poll")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| opt
+                    .name("weight")
+                    .description("How many votes a ballot counts as (max: 1000); 0 removes the role's or member's weight")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+                .create_sub_option(|opt| opt
+                    .name("role")
+                    .description("Give members with this role the weight")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Role))
+                .create_sub_option(|opt| opt
+                    .name("user")
+                    .description("Give this member the weight, whatever their roles")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::User))
+                .create_sub_option(|opt| opt
+                    .name("table")
+                    .description("Weights for many members at once, as user:weight pairs separated by commas")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::String))
+        })
+        .create_option(|opt| {
+            opt
+                .name("roll")
+                .description("Fix, export, or remove the list of members allowed to vote on a poll")
+                .kind(ApplicationCommandOptionType::SubCommand)
+
+                .create_sub_option(|opt| opt
+                    .name("name")
+                    .description("The name of the poll")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| opt
+                    .name("action")
+                    .description("What to do with the voter roll")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String)
+                    .add_string_choice("Freeze: snapshot the members eligible to vote", "freeze")
+                    .add_string_choice("Export: download the list of members on the roll", "export")
+                    .add_string_choice("Clear: let eligible members vote without a roll", "clear"))
+                .create_sub_option(|opt| opt
+                    .name("in-hours")
+                    .description("With 'Freeze', snapshot the roll this many hours from now instead of immediately")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
+        })
+        .create_option(|opt| {
+            opt
+                .name("info")
+                .description("Show a poll's settings and who may vote on it")
+                .kind(ApplicationCommandOptionType::SubCommand)
+
+                .create_sub_option(|opt| opt
+                    .name("name")
+                    .description("The name of the poll")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+        })
+        .create_option(|opt| {
             opt
+                .name("clone")
+                .description("Create a new poll with the question, options, and rules of an existing one")
+                .kind(ApplicationCommandOptionType::SubCommand)
+
+                .create_sub_option(|opt| opt
+                    .name("name")
+                    .description("The name of the poll to copy")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| opt
+                    .name("new-name")
+                    .description("Unique identifier for the new poll; no whitespace, may only contain a-z, 0-9, and -")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+                .create_sub_option(|opt| opt
+                    .name("duration")
+                    .description("Hours until voting closes, instead of the copied poll's; 0 to close it manually")
+                    .required(false)
+                    .kind(ApplicationCommandOptionType::Integer))
         })
         .create_option(|opt| {
             opt
@@ -84,6 +403,18 @@ pub fn poll_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                     .required(true)
                     .kind(ApplicationCommandOptionType::String))
         })
+        .create_option(|opt| {
+            opt
+                .name("delete")
+                .description("Delete a poll and its ballots")
+                .kind(ApplicationCommandOptionType::SubCommand)
+
+                .create_sub_option(|opt| opt
+                    .name("name")
+                    .description("The name of the poll to delete")
+                    .required(true)
+                    .kind(ApplicationCommandOptionType::String))
+        })
         .create_option(|opt| {
             opt
                 .name("tally")
@@ -100,81 +431,136 @@ pub fn poll_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicatio
     cmd
 }
 
-async fn poll_create(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId, member: &Member) -> anyhow::Result<()> {
-    let member_id = interaction.member.as_ref().unwrap();
-
-    let permissions = match member_id.permissions {
-        None => {
-            get_logger().info("Could not read interaction invoker's permissions.", meta! {
-                "InteractionID" => interaction.id,
-                "GuildID" => guild_id,
-            });
-            command_resp::reply_deferred_result(&ctx, &interaction, "Could not read interaction invoker's permissions.").await.unwrap();
-            return Ok(());
-        }
-        Some(v) => v,
-    };
+/// Replies and returns false unless the invoking member manages polls in the server.
+pub(super) async fn check_manager(ctx: &Context, interaction: &ApplicationCommandInteraction, data: &BotData, guild_id: &GuildId, command: &str) -> anyhow::Result<bool> {
+    let member = interaction.member.as_ref().unwrap();
+    let settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
 
-    if !permissions.contains(Permissions::ADMINISTRATOR) && !member_id.user.id.as_u64() == 95882096597274624 {
-        get_logger().info("Non-administrator attempted to add watch.", meta! {
+    if !permissions::is_manager(&settings, member) {
+        get_logger().info("Non-manager attempted to use manager command.", meta! {
             "InteractionID" => interaction.id,
             "GuildID" => guild_id,
+            "Command" => command,
         });
-        command_resp::reply_deferred_result(&ctx, &interaction, "Only members with the 'Administrator' permission may use /poll create.").await.unwrap();
-        return Ok(());
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "Only {} may use {}.", permissions::describe_managers(&settings), command
+        )).await?;
+        return Ok(false);
     }
 
-    //
+    Ok(true)
+}
 
-    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
-    let question = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "question").await?.unwrap();
+/// The channel to announce a new poll in: the server's announcement channel if it has one, and
+/// otherwise the channel the command was used in.
+pub(super) async fn announce_channel(interaction: &ApplicationCommandInteraction, data: &BotData, guild_id: &GuildId) -> anyhow::Result<ChannelId> {
+    let settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
 
-    //
+    Ok(settings.id_announce_channel.map_or(interaction.channel_id, ChannelId))
+}
 
-    let name = name.trim().to_lowercase();
-    match VALIDATE_POLL_NAME.is_match(&name) {
-        true => {}
-        false => {
-            get_logger().info("Invalid name passed to /poll create.", meta! {
-                "InteractionID" => interaction.id,
-                "Name" => name,
-            });
-            command_resp::reply_deferred_result(&ctx, &interaction, format!(
-                "Name '{}' is invalid; may only contain letters, numbers, and dashes (-)", name
-            )).await?;
-            return Ok(());
-        }
+/// Confirms a newly created poll to its creator, noting if it could not be announced.
+#[must_use]
+pub(super) fn created_message(poll: &Poll, channel: ChannelId, announced: bool) -> String {
+    match announced {
+        true => format!("Created poll **'{}'**.", poll.name),
+        false => format!(
+            "Created poll **'{}'**, but it could not be announced in <#{}>; check that the bot may send messages there.", poll.name, channel
+        ),
     }
+}
+
+/// Replies and returns false unless the invoking member created `poll` or manages polls in its
+/// server.
+pub(super) async fn check_poll_manager(ctx: &Context, interaction: &ApplicationCommandInteraction, data: &BotData, poll: &Poll, command: &str) -> anyhow::Result<bool> {
+    let member = interaction.member.as_ref().unwrap();
+    let settings = db::model::get_guild_settings(data.db_client.conn(), poll.id_server).await?;
 
-    let ranks = command_opt::find_integer_opt(&opt.options, "ranks").unwrap();
-    if ranks < 2 || ranks > 20 {
-        get_logger().info("Attempted to create poll with invalid number of ranks.", meta! {
+    if !permissions::may_manage_poll(&settings, poll, member) {
+        get_logger().info("Member attempted to manage poll they did not create.", meta! {
             "InteractionID" => interaction.id,
-            "Name" => name,
-            "Ranks" => ranks,
+            "PollID" => poll.id,
+            "Command" => command,
         });
         command_resp::reply_deferred_result(&ctx, &interaction, format!(
-            "`ranks` must be greater than 1 and less than 21; got {}.", ranks
+            "Only the creator of poll **'{}'** and {} may use {} on it.", poll.name, permissions::describe_managers(&settings), command
         )).await?;
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+async fn poll_create(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId, member: &Member) -> anyhow::Result<()> {
+    if !check_manager(ctx, interaction, data, guild_id, "/poll create").await? {
         return Ok(());
     }
-    let ranks = ranks as u8;
 
     //
 
-    let server_has_poll_name = match db::model::check_server_has_poll_name(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let question = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "question").await?.unwrap();
+
+    //
+
+    let name = match validate::poll_name(&name) {
         Ok(v) => v,
         Err(e) => {
-            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to check for existing poll by name.").await?;
-            return Err(e);
+            get_logger().info("Invalid name passed to /poll create.", meta! {
+                "InteractionID" => interaction.id,
+                "Name" => name,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
         }
     };
 
-    if server_has_poll_name {
-        get_logger().info("Attempted to register duplicate server/name combo with /poll create.", meta! {
-            "InteractionID" => interaction.id,
-            "Name" => name,
-        });
+    let guild_settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+    let mut settings = guild_settings.poll_settings();
+
+    if let Some(ranks) = command_opt::find_integer_opt(&opt.options, "ranks") {
+        settings.ranks = match validate::ranks(ranks) {
+            Ok(v) => v,
+            Err(e) => {
+                get_logger().info("Attempted to create poll with invalid number of ranks.", meta! {
+                    "InteractionID" => interaction.id,
+                    "Name" => name,
+                    "Ranks" => ranks,
+                });
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(());
+            }
+        };
+    }
+
+    let time_close = match command_opt::find_integer_opt(&opt.options, "duration").map(validate::deadline) {
+        None => None,
+        Some(Ok(v)) => Some(v),
+        Some(Err(e)) => {
+            get_logger().info("Attempted to create poll with invalid duration.", meta! {
+                "InteractionID" => interaction.id,
+                "Name" => name,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    //
+
+    let server_has_poll_name = match db::model::check_server_has_poll_name(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to check for existing poll by name.").await?;
+            return Err(e);
+        }
+    };
+
+    if server_has_poll_name {
+        get_logger().info("Attempted to register duplicate server/name combo with /poll create.", meta! {
+            "InteractionID" => interaction.id,
+            "Name" => name,
+        });
         command_resp::reply_deferred_result(&ctx, &interaction, format!(
             "A poll with the name `{}` has already been created in this server.", name
         )).await?;
@@ -207,14 +593,17 @@ async fn poll_create(ctx: &Context, interaction: &ApplicationCommandInteraction,
         }
     }
 
-    if opts.len() < 2 {
-        get_logger().info("Fewer than 2 options passed to /poll create.", meta! {
-            "InteractionID" => interaction.id,
-            "Name" => name,
-        });
-        command_resp::reply_deferred_result(&ctx, &interaction, "At least 2 options must be passed to /poll create.").await?;
-        return Ok(());
-    }
+    let opts = match validate::options(opts) {
+        Ok(v) => v,
+        Err(e) => {
+            get_logger().info("Invalid options passed to /poll create.", meta! {
+                "InteractionID" => interaction.id,
+                "Name" => name,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
 
     let poll = match db::model::add_poll(
         data.db_client.conn(),
@@ -222,7 +611,9 @@ async fn poll_create(ctx: &Context, interaction: &ApplicationCommandInteraction,
         *member.user.id.as_u64(),
         &name,
         &question,
-        ranks,
+        &settings,
+        time_close,
+        None,
         &opts,
     ).await {
         Ok(v) => v,
@@ -232,189 +623,1064 @@ async fn poll_create(ctx: &Context, interaction: &ApplicationCommandInteraction,
         }
     };
 
-    interaction.create_followup_message(&ctx.http, |r| r.create_embed(|e| {
-        e.title("New poll created");
-        e.thumbnail("https://i.imgur.com/fWgQ8b6.png");
+    let channel = guild_settings.id_announce_channel.map_or(interaction.channel_id, ChannelId);
+    let announced = poll_status::announce(&ctx.http, data.db_client.conn(), &poll, channel).await;
+
+    command_resp::reply_deferred_result(&ctx, &interaction, created_message(&poll, channel, announced)).await?;
 
-        e.field("Poll", format!("{} ({})", poll.name, poll.id), false);
+    Ok(())
+}
 
-        let mut opt_string = String::new();
-        for (i, opt) in opts.iter().enumerate() {
-            opt_string.push_str(&format!("**{}.** {}\n", i + 1, opt));
+async fn poll_edit(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => match v {
+            Some(v) if v.open => v,
+            _ => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("No open poll named **'{}'** was found.", name)).await?;
+                return Ok(());
+            }
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
+            return Err(e);
+        }
+    };
+
+    if !check_poll_manager(ctx, interaction, data, &poll, "/poll edit").await? {
+        return Ok(());
+    }
+
+    let method = command_opt::find_string_opt(&opt.options, "method");
+    let seats = command_opt::find_integer_opt(&opt.options, "seats");
+    let ranks = command_opt::find_integer_opt(&opt.options, "ranks");
+    let secret = command_opt::find_boolean_opt(&opt.options, "secret");
+    let write_ins = command_opt::find_boolean_opt(&opt.options, "write-ins");
+    let quorum = command_opt::find_integer_opt(&opt.options, "quorum");
+    let quorum_percent = command_opt::find_integer_opt(&opt.options, "quorum-percent");
+    let duration = command_opt::find_integer_opt(&opt.options, "duration");
+    let runoff = command_opt::find_integer_opt(&opt.options, "runoff");
+    let runoff_duration = command_opt::find_integer_opt(&opt.options, "runoff-duration");
+    let reminders = command_opt::find_string_opt(&opt.options, "reminders");
+    let remind_by = command_opt::find_string_opt(&opt.options, "remind-by");
+    let results = command_opt::find_string_opt(&opt.options, "results");
+
+    if duration.is_some() && poll.id_group.is_some() {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "Poll **'{}'** is part of an election and closes with it; change its deadline with `/election schedule`.", poll.name
+        )).await?;
+        return Ok(());
+    }
+
+    let mut settings = poll.settings.clone();
+
+    // Runoff rules only apply once the poll closes, so unlike the counting rules below they may be
+    // changed after ballots are cast.
+    if runoff.is_some() || runoff_duration.is_some() {
+        let validated = runoff.map_or(Ok(settings.runoff.candidates), validate::runoff_candidates)
+            .and_then(|c| runoff_duration.map_or(Ok(settings.runoff.hours), validate::runoff_hours).map(|h| (c, h)));
+
+        match validated {
+            Ok((c, h)) => {
+                settings.runoff.candidates = c;
+                settings.runoff.hours = h;
+            }
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(());
+            }
+        }
+    }
+
+    // Likewise for reminders, which are only about who has yet to vote.
+    if let Some(v) = reminders {
+        match validate::reminder_hours(&v) {
+            Ok(v) => settings.reminders.hours = v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(());
+            }
+        }
+    }
+    if let Some(v) = &remind_by {
+        settings.reminders.dm = v == "dm";
+    }
+    // And for who may see results, which does not change how they are counted.
+    if let Some(v) = results {
+        settings.results = Visibility::parse(&v).unwrap();
+    }
+
+    let counting_changed = method.is_some() || seats.is_some() || ranks.is_some() || secret.is_some() || write_ins.is_some() || quorum.is_some() || quorum_percent.is_some();
+
+    if counting_changed {
+        let turnout = db::model::count_valid_ballots(data.db_client.conn(), poll.id).await?;
+        if turnout > 0 {
+            get_logger().info("Attempted to change counting rules of poll with ballots.", meta! {
+                "InteractionID" => interaction.id,
+                "PollID" => poll.id,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, format!(
+                "Poll **'{}'** already has {} ballot(s); its method, seats, ranks, quorum, secrecy, and write-ins can no longer be changed.", poll.name, turnout
+            )).await?;
+            return Ok(());
+        }
+
+        if let Some(v) = method {
+            settings.method = Method::parse(&v).unwrap();
+        }
+        if let Some(v) = secret {
+            settings.secret = v;
         }
-        e.field("Options", opt_string, false);
+        if let Some(v) = write_ins {
+            settings.write_ins = v;
+        }
+
+        let validated = ranks.map_or(Ok(settings.ranks), validate::ranks)
+            .and_then(|r| validate::seats(settings.method, seats.unwrap_or(settings.seats as i64), poll.options.len()).map(|s| (r, s)))
+            .and_then(|(r, s)| {
+                let q = match (quorum, quorum_percent) {
+                    (None, None) => Ok(settings.quorum),
+                    (ballots, percent) => validate::quorum(ballots, percent),
+                };
+                q.map(|q| (r, s, q))
+            });
+
+        match validated {
+            Ok((r, s, q)) => {
+                settings.ranks = r;
+                settings.seats = s;
+                settings.quorum = q;
+            }
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(());
+            }
+        }
+    }
+
+    // Mentions would show everyone who has yet to vote on a secret poll, so it only reminds by DM.
+    if settings.secret && remind_by.as_deref() == Some("mention") {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "Poll **'{}'** is secret, so its reminders can only be sent by DM.", poll.name
+        )).await?;
+        return Ok(());
+    }
+
+    if counting_changed || settings.runoff != poll.settings.runoff || settings.reminders != poll.settings.reminders || settings.results != poll.settings.results {
+        db::model::update_poll_settings(data.db_client.conn(), poll.id, &settings).await?;
+    }
+
+    if let Some(v) = duration {
+        // A duration of 0 removes the deadline.
+        let time_close = match v {
+            0 => None,
+            v => match validate::deadline(v) {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                    return Ok(());
+                }
+            }
+        };
+
+        db::model::set_poll_deadline(data.db_client.conn(), poll.id, time_close).await?;
+    }
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!("Updated poll **'{}'**.", poll.name)).await?;
+
+    poll_status::update_status_message(&ctx.http, data.db_client.conn(), poll.id).await?;
+
+    Ok(())
+}
+
+async fn poll_eligibility(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => match v {
+            Some(v) if v.open => v,
+            _ => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("No open poll named **'{}'** was found.", name)).await?;
+                return Ok(());
+            }
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
+            return Err(e);
+        }
+    };
+
+    if !check_poll_manager(ctx, interaction, data, &poll, "/poll eligibility").await? {
+        return Ok(());
+    }
+
+    // Changing who may vote once voting has begun would leave ballots cast under different rules.
+    let turnout = db::model::count_valid_ballots(data.db_client.conn(), poll.id).await?;
+    if turnout > 0 {
+        get_logger().info("Attempted to change eligibility of poll with ballots.", meta! {
+            "InteractionID" => interaction.id,
+            "PollID" => poll.id,
+        });
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "Poll **'{}'** already has {} ballot(s); who may vote on it can no longer be changed.", poll.name, turnout
+        )).await?;
+        return Ok(());
+    }
+
+    if poll.time_roll_frozen.is_some() {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "The voter roll for poll **'{}'** is frozen; clear it with `/poll roll` before changing who may vote.", poll.name
+        )).await?;
+        return Ok(());
+    }
+
+    let require_role = command_opt::find_role_opt(&opt.options, "require-role");
+    let exclude_role = command_opt::find_role_opt(&opt.options, "exclude-role");
+    let remove_role = command_opt::find_role_opt(&opt.options, "remove-role");
+    let member_days = command_opt::find_integer_opt(&opt.options, "member-days");
+    let account_days = command_opt::find_integer_opt(&opt.options, "account-days");
+
+    let mut settings = poll.settings.clone();
+    let rules = &mut settings.eligibility;
+
+    for role in [require_role, exclude_role, remove_role].iter().flatten() {
+        rules.required_roles.retain(|v| v != role.as_u64());
+        rules.excluded_roles.retain(|v| v != role.as_u64());
+    }
+    if let Some(v) = require_role {
+        rules.required_roles.push(*v.as_u64());
+    }
+    if let Some(v) = exclude_role {
+        rules.excluded_roles.push(*v.as_u64());
+    }
+
+    let validated = member_days.map_or(Ok(rules.min_member_days), |v| validate::days("member-days", v))
+        .and_then(|m| account_days.map_or(Ok(rules.min_account_days), |v| validate::days("account-days", v)).map(|a| (m, a)));
+
+    match validated {
+        Ok((m, a)) => {
+            rules.min_member_days = m;
+            rules.min_account_days = a;
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    }
+
+    db::model::update_poll_settings(data.db_client.conn(), poll.id, &settings).await?;
+    voter_roll::forget_eligible_count(poll.id);
+
+    let described = eligibility::describe(&settings.eligibility).unwrap_or_else(|| "Anyone in this server".to_owned());
+    command_resp::reply_deferred_result(&ctx, &interaction, format!("Updated who may vote on poll **'{}'**:\n{}", poll.name, described)).await?;
+
+    poll_status::update_status_message(&ctx.http, data.db_client.conn(), poll.id).await?;
+
+    Ok(())
+}
+
+async fn poll_weights(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => match v {
+            Some(v) if v.open => v,
+            _ => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("No open poll named **'{}'** was found.", name)).await?;
+                return Ok(());
+            }
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
+            return Err(e);
+        }
+    };
+
+    if !check_poll_manager(ctx, interaction, data, &poll, "/poll weights").await? {
+        return Ok(());
+    }
+
+    // Ballots keep the weight they were cast with, so changing weights afterwards would count
+    // equal voters differently.
+    let turnout = db::model::count_valid_ballots(data.db_client.conn(), poll.id).await?;
+    if turnout > 0 {
+        get_logger().info("Attempted to change weights of poll with ballots.", meta! {
+            "InteractionID" => interaction.id,
+            "PollID" => poll.id,
+        });
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "Poll **'{}'** already has {} ballot(s); its vote weights can no longer be changed.", poll.name, turnout
+        )).await?;
+        return Ok(());
+    }
+
+    let weight = command_opt::find_integer_opt(&opt.options, "weight");
+    let role = command_opt::find_role_opt(&opt.options, "role");
+    let user = command_opt::find_user_opt(&opt.options, "user");
+    let table = command_opt::find_string_opt(&opt.options, "table");
+
+    if role.is_none() && user.is_none() && table.is_none() {
+        command_resp::reply_deferred_result(&ctx, &interaction, "Give a `role`, `user`, or `table` to weight.").await?;
+        return Ok(());
+    }
+
+    // A table gives each member's weight itself, so a `weight` alongside it would be ambiguous.
+    if weight.is_some() && table.is_some() {
+        command_resp::reply_deferred_result(&ctx, &interaction, "`weight` cannot be combined with `table`; give each member's weight in `table` as `user:weight` pairs.").await?;
+        return Ok(());
+    }
+
+    let weight = match (weight, role.is_some() || user.is_some()) {
+        (None, true) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "`weight` is required with `role` or `user`.").await?;
+            return Ok(());
+        }
+        (None, false) => None,
+        (Some(v), _) => match validate::weight(v) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(());
+            }
+        },
+    };
+
+    let mut user_weights = match table.as_deref().map(validate::weight_table) {
+        None => Vec::new(),
+        Some(Ok(v)) => v,
+        Some(Err(e)) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    if let (Some(user), Some(weight)) = (&user, weight) {
+        user_weights.push((*user.id.as_u64(), weight));
+    }
+
+    if let (Some(role), Some(weight)) = (role, weight) {
+        let mut settings = poll.settings.clone();
+
+        settings.role_weights.retain(|(v, _)| v != role.as_u64());
+        if weight > 0 {
+            settings.role_weights.push((*role.as_u64(), weight));
+        }
+
+        db::model::update_poll_settings(data.db_client.conn(), poll.id, &settings).await?;
+    }
+
+    if !user_weights.is_empty() {
+        db::model::set_user_weights(data.db_client.conn(), poll.id, &user_weights).await?;
+    }
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!("Updated vote weights for poll **'{}'**.", poll.name)).await?;
+
+    poll_status::update_status_message(&ctx.http, data.db_client.conn(), poll.id).await?;
+
+    Ok(())
+}
+
+async fn poll_roll(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let action = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "action").await?.unwrap();
+    let in_hours = command_opt::find_integer_opt(&opt.options, "in-hours");
+
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => match v {
+            None => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("Failed to find poll with name **'{}'**.", name)).await?;
+                return Ok(());
+            }
+            Some(v) => v,
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
+            return Err(e);
+        }
+    };
+
+    if !check_poll_manager(ctx, interaction, data, &poll, "/poll roll").await? {
+        return Ok(());
+    }
+
+    if action == "export" {
+        let roll = match poll.time_roll_frozen {
+            None => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("Poll **'{}'** has no frozen voter roll.", poll.name)).await?;
+                return Ok(());
+            }
+            Some(_) => db::model::get_voter_roll(data.db_client.conn(), poll.id).await?,
+        };
+
+        interaction.create_followup_message(&ctx.http, |r| {
+            r.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+            r.content(format!("{} member(s) on the voter roll for poll **'{}'**.", roll.len(), poll.name));
+            r.add_file(AttachmentType::Bytes {
+                data: Cow::from(roll.iter().map(|v| v.to_string()).join("\n").into_bytes()),
+                filename: format!("roll-{}.txt", poll.name),
+            })
+        }).await?;
+
+        return Ok(());
+    }
+
+    if !poll.open {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!("Poll **'{}'** is closed.", poll.name)).await?;
+        return Ok(());
+    }
+
+    // Like the eligibility rules, the roll decides who may vote, so it is fixed once voting has begun.
+    let turnout = db::model::count_valid_ballots(data.db_client.conn(), poll.id).await?;
+    if turnout > 0 {
+        get_logger().info("Attempted to change voter roll of poll with ballots.", meta! {
+            "InteractionID" => interaction.id,
+            "PollID" => poll.id,
+        });
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "Poll **'{}'** already has {} ballot(s); its voter roll can no longer be changed.", poll.name, turnout
+        )).await?;
+        return Ok(());
+    }
+
+    let result = match (action.as_str(), in_hours) {
+        ("clear", _) => {
+            db::model::clear_voter_roll(data.db_client.conn(), poll.id).await?;
+            format!("Removed the voter roll for poll **'{}'**; any eligible member may vote.", poll.name)
+        }
+        ("freeze", Some(v)) => {
+            let cutoff = match validate::hours_from_now("in-hours", v) {
+                Ok(v) => v,
+                Err(e) => {
+                    command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                    return Ok(());
+                }
+            };
+            db::model::clear_voter_roll(data.db_client.conn(), poll.id).await?;
+            db::model::set_roll_cutoff(data.db_client.conn(), poll.id, Some(cutoff)).await?;
+            format!("The voter roll for poll **'{}'** will be frozen <t:{}:R>.", poll.name, cutoff.timestamp())
+        }
+        ("freeze", None) => {
+            let n = match voter_roll::freeze(&ctx.http, data.db_client.conn(), &poll).await {
+                Ok(v) => v,
+                Err(e) if e.is::<voter_roll::MissingMembersIntent>() => {
+                    command_resp::reply_deferred_result(&ctx, &interaction, format!("The voter roll could not be frozen: {}.", e)).await?;
+                    return Ok(());
+                }
+                Err(e) => {
+                    command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to list server members.").await?;
+                    return Err(e);
+                }
+            };
+            format!("Froze the voter roll for poll **'{}'** with {} member(s).", poll.name, n)
+        }
+        _ => return Ok(()),
+    };
+
+    command_resp::reply_deferred_result(&ctx, &interaction, result).await?;
+
+    poll_status::update_status_message(&ctx.http, data.db_client.conn(), poll.id).await?;
+
+    Ok(())
+}
+
+async fn poll_info(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => match v {
+            None => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("Failed to find poll with name **'{}'**.", name)).await?;
+                return Ok(());
+            }
+            Some(v) => v,
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
+            return Err(e);
+        }
+    };
+
+    let turnout = poll_status::Turnout::count(data.db_client.conn(), &poll).await?;
+    let nominated = poll_status::load_nominations(data.db_client.conn(), &poll).await?;
+    let n_user_weights = db::model::count_user_weights(data.db_client.conn(), poll.id).await?;
+
+    interaction.create_followup_message(&ctx.http, |r| {
+        r.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+        r.create_embed(|e| poll_status::info_embed(e, &poll, &turnout, &nominated, n_user_weights))
+    }).await?;
+
+    Ok(())
+}
+
+async fn poll_clone(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId, member: &Member) -> anyhow::Result<()> {
+    if !check_manager(ctx, interaction, data, guild_id, "/poll clone").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let new_name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "new-name").await?.unwrap();
+
+    let source = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => match v {
+            None => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("Failed to find poll with name **'{}'**.", name)).await?;
+                return Ok(());
+            }
+            Some(v) => v,
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
+            return Err(e);
+        }
+    };
+
+    let options = source.options.iter()
+        .map(|v| (v.option.clone(), v.details.clone()))
+        .collect::<Vec<(String, OptionDetails)>>();
+
+    if options.len() < 2 {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!("Poll **'{}'** has no options to copy yet.", source.name)).await?;
+        return Ok(());
+    }
+
+    let duration = command_opt::find_integer_opt(&opt.options, "duration")
+        .or_else(|| template::duration_hours(&source).map(|v| v as i64));
+
+    let channel = announce_channel(interaction, data, guild_id).await?;
+
+    let poll = match template::create_from(ctx, interaction, data, guild_id, member, &new_name, &source.question, &source.settings, duration, &options).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let user_weights = db::model::get_user_weights(data.db_client.conn(), source.id).await?;
+    if !user_weights.is_empty() {
+        db::model::set_user_weights(data.db_client.conn(), poll.id, &user_weights).await?;
+    }
+
+    get_logger().info("Cloned poll.", meta! {
+        "InteractionID" => interaction.id,
+        "PollID" => poll.id,
+        "SourcePollID" => source.id,
+    });
+
+    let announced = poll_status::announce(&ctx.http, data.db_client.conn(), &poll, channel).await;
+
+    command_resp::reply_deferred_result(&ctx, &interaction, created_message(&poll, channel, announced)).await?;
+
+    Ok(())
+}
+
+async fn poll_close(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => match v {
+            Some(v) if v.open => v,
+            _ => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("No open poll named **'{}'** was found.", name)).await?;
+                return Ok(());
+            }
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
+            return Err(e);
+        }
+    };
+
+    if !check_poll_manager(ctx, interaction, data, &poll, "/poll close").await? {
+        return Ok(());
+    }
+
+    let closed = match db::model::close_poll_by_id(data.db_client.conn(), poll.id).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to close poll.").await?;
+            return Err(e);
+        }
+    };
+
+    // The scheduler may have closed the poll at its deadline since it was looked up.
+    if !closed {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!("Poll **'{}'** is already closed.", poll.name)).await?;
+        return Ok(());
+    }
+
+    get_logger().info("Closed poll.", meta! {
+        "InteractionID" => interaction.id,
+        "PollID" => poll.id,
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!("Closed poll **'{}'**.", poll.name)).await?;
+
+    // The poll is closed either way, so a failed announcement must not keep its status message and
+    // any runoff from being updated.
+    let announcement = match quorum::closed_announcement(&ctx.http, data.db_client.conn(), poll.id).await {
+        Ok(v) => v,
+        Err(e) => {
+            get_logger().error("Failed to check quorum of closed poll.", meta! {
+                "InteractionID" => interaction.id,
+                "PollID" => poll.id,
+                "Error" => e,
+            });
+            format!("Voting closed for poll **'{}'**.", poll.name)
+        }
+    };
+
+    if let Err(e) = interaction.channel_id.say(&ctx.http, announcement).await {
+        get_logger().error("Failed to announce poll closure.", meta! {
+            "InteractionID" => interaction.id,
+            "PollID" => poll.id,
+            "Error" => e,
+        });
+    }
 
-        e
-    })).await?;
+    if let Err(e) = poll_status::update_status_message(&ctx.http, data.db_client.conn(), poll.id).await {
+        get_logger().error("Failed to update poll status message.", meta! {
+            "PollID" => poll.id,
+            "Error" => e,
+        });
+    }
 
-    let guild = ctx.http.get_guild(*guild_id.as_u64()).await?;
-    crate::support::register_polls::register_polls(data.db_client.conn(), &ctx, &guild).await.unwrap();
+    runoff::start_if_needed(&ctx.http, data.db_client.conn(), poll.id, Some(interaction.channel_id)).await?;
 
     Ok(())
 }
 
-async fn poll_close(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId, member: &Member) -> anyhow::Result<()> {
+async fn poll_delete(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
     let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
 
-    let closed = match db::model::close_poll(data.db_client.conn(), *guild_id.as_u64(), *member.user.id.as_u64(), &name).await {
-        Ok(v) => v,
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => match v {
+            None => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("Failed to find poll with name **'{}'**.", name)).await?;
+                return Ok(());
+            }
+            Some(v) => v,
+        }
         Err(e) => {
-            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up and close poll.").await?;
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
             return Err(e);
         }
     };
 
-    if closed {
-        command_resp::reply_deferred_result(&ctx, &interaction, format!("Voting closed for poll **'{}'**.", name)).await?;
-    } else {
-        command_resp::reply_deferred_result(&ctx, &interaction, format!("No open poll named **'{}'** was found.", name)).await?;
+    if !check_poll_manager(ctx, interaction, data, &poll, "/poll delete").await? {
+        return Ok(());
+    }
+
+    if let Err(e) = db::model::delete_poll(data.db_client.conn(), poll.id).await {
+        command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to delete poll.").await?;
+        return Err(e);
+    }
+
+    get_logger().info("Deleted poll.", meta! {
+        "InteractionID" => interaction.id,
+        "PollID" => poll.id,
+    });
+
+    // The poll is gone either way, so a status message that cannot be removed is only logged.
+    if let (Some(id_channel), Some(id_message)) = (poll.id_channel, poll.id_message) {
+        if let Err(e) = ChannelId(id_channel).delete_message(&ctx.http, id_message).await {
+            get_logger().error("Failed to delete poll status message.", meta! {
+                "PollID" => poll.id,
+                "Error" => e,
+            });
+        }
     }
 
+    command_resp::reply_deferred_result(&ctx, &interaction, format!("Deleted poll **'{}'**.", poll.name)).await?;
+
     Ok(())
 }
 
 async fn poll_tally(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId, member: &Member) -> anyhow::Result<()> {
     let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
-    let seats = 150;
 
     let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &name).await {
         Ok(v) => match v {
             None => {
-                command_resp::reply_deferred_result(&ctx, &interaction, format!("Failed to find poll with name **'{}'**.", name)).await?;
+                command_resp::reply_deferred_private(&ctx, &interaction, format!("Failed to find poll with name **'{}'**.", name)).await?;
                 return Ok(());
             }
             Some(v) => v,
         }
         Err(e) => {
-            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
+            command_resp::reply_deferred_private(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
             return Err(e);
         }
     };
 
-    let ballots = match db::model::get_valid_ballots(data.db_client.conn(), poll.id).await {
+    let settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+    if !permissions::may_tally(&settings, &poll, member) {
+        command_resp::reply_deferred_private(&ctx, &interaction, match poll.settings.results {
+            Visibility::Managers => format!(
+                "Results of poll **'{}'** are hidden until it closes, except from its creator and {}.", poll.name, permissions::describe_managers(&settings)
+            ),
+            _ => format!("Results of poll **'{}'** are hidden until it closes.", poll.name),
+        }).await?;
+        return Ok(());
+    }
+
+    let tally = match tally::tally(&ctx.http, data.db_client.conn(), &poll).await {
         Ok(v) => v,
         Err(e) => {
-            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up valid ballots for poll.").await?;
+            command_resp::reply_deferred_private(&ctx, &interaction, "Error occurred upon attempt to tally ballots.").await?;
             return Err(e);
         }
     };
+    let conclusive = tally.conclusive();
 
-    //
+    let runoff = match poll.settings.method {
+        Method::Runoff => db::model::get_runoff(data.db_client.conn(), poll.id).await?,
+        _ => None,
+    };
 
-    let mut stv_candidates = Vec::new();
-    for opt in &poll.options {
-        stv_candidates.push(opt.option.clone());
+    // Results that are hidden from others while the poll is open are only shown to the member
+    // who tallied it.
+    let private = !permissions::results_public(&poll);
+    if private {
+        interaction.delete_original_interaction_response(&ctx.http).await?;
     }
 
-    let mut stv_votes = Vec::new();
-    for ballot in &ballots {
-        let mut stv_vote = Vec::new();
+    //
 
-        for choice in ballot.choices.iter().sorted_by_key(|v| v.rank).map(|v| v.id_option) {
-            for opt in &poll.options {
-                if choice == opt.id {
-                    stv_vote.push(opt.option.clone());
-                    break;
-                }
-            }
+    interaction.create_followup_message(&ctx.http, |r| {
+        if private {
+            r.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
         }
+        r.create_embed(|e| {
+            e.title(if conclusive { "Poll results" } else { "Poll results (inconclusive)" });
+            e.thumbnail("https://i.imgur.com/fWgQ8b6.png");
+
+            e.field("Poll", format!("{} ({})", poll.name, poll.id), false);
+
+            e.field(if conclusive { "Winners" } else { "Leading options" }, tally.winners_list(), false);
+            if poll.settings.method == Method::Runoff {
+                e.field("Majority", match (tally.majority(), &runoff) {
+                    (Some(v), _) => format!("**{}** won a majority of first choices", v),
+                    (None, Some(v)) => format!("None; the runoff is poll **'{}'**", v.name),
+                    (None, None) if poll.open => "None yet; without one, a runoff is held when the poll closes, if it meets its quorum".to_owned(),
+                    (None, None) => "None".to_owned(),
+                }, false);
+            }
+            if let Some(v) = &tally.quorum {
+                e.field("Quorum", v.describe(), false);
+            }
+            if tally.total_weight != tally.ballots.len() as u64 {
+                e.field("Weighted votes", format!("{} from {} ballot(s)", tally.total_weight, tally.ballots.len()), false);
+            }
+            e.field("Receipts", format!(
+                "{} ballot(s) counted; every voter can find their ballot's receipt code in the attached list.", tally.ballots.len()
+            ), false);
+
+            e
+        }).add_file(AttachmentType::Bytes {
+            data: Cow::from(receipts::receipt_list(&tally.ballots).into_bytes()),
+            filename: format!("receipts-{}.txt", poll.name),
+        })
+    }).await?;
 
-        stv_votes.push(stv_vote);
-    }
+    Ok(())
+}
 
-    let stv_election = stv::Election::new(stv_candidates, stv_votes, seats);
+pub fn vote_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(VOTE)
+        .description("Vote on a poll")
+        .create_option(|opt| {
+            opt
+                .name("cast")
+                .description("Cast or replace your ballot on a poll")
+                .kind(ApplicationCommandOptionType::SubCommand)
 
-    let stv_results = match stv_election.results() {
-        Ok(v) => v,
-        Err(e) => {
-            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to tally ballots.").await?;
-            return Err(e);
-        }
+                .create_sub_option(|opt| opt
+                    .name("poll")
+                    .description("The poll to vote on")
+                    .required(true)
+                    .set_autocomplete(true)
+                    .kind(ApplicationCommandOptionType::String));
+
+            for i in 1..=20 {
+                opt.create_sub_option(|opt| opt
+                    .name(format!("choice-{}", i))
+                    .description(format!("Your {} choice", num_word(i)))
+                    .required(i == 1)
+                    .set_autocomplete(true)
+                    .kind(ApplicationCommandOptionType::String));
+            }
+
+            opt.create_sub_option(|opt| opt
+                .name("for")
+                .description("Vote on behalf of a member who delegated their vote to you")
+                .kind(ApplicationCommandOptionType::User))
+        })
+        .create_option(|opt| opt
+            .name("mine")
+            .description("Show or withdraw your current ballot on a poll")
+            .kind(ApplicationCommandOptionType::SubCommand)
+
+            .create_sub_option(|opt| opt
+                .name("poll")
+                .description("The poll you voted on")
+                .required(true)
+                .set_autocomplete(true)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|opt| opt
+            .name("delegate")
+            .description("Let another member vote for you until you revoke it or vote yourself")
+            .kind(ApplicationCommandOptionType::SubCommand)
+
+            .create_sub_option(|opt| opt
+                .name("to")
+                .description("The member who may vote for you")
+                .required(true)
+                .kind(ApplicationCommandOptionType::User))
+            .create_sub_option(|opt| opt
+                .name("poll")
+                .description("Only delegate your vote on this poll; otherwise on every poll in this server")
+                .set_autocomplete(true)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|opt| opt
+            .name("revoke")
+            .description("Stop another member from voting for you")
+            .kind(ApplicationCommandOptionType::SubCommand)
+
+            .create_sub_option(|opt| opt
+                .name("poll")
+                .description("Revoke the delegation for this poll; otherwise the one for every poll in this server")
+                .set_autocomplete(true)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|opt| opt
+            .name("reminders")
+            .description("Choose whether the bot reminds you about polls in this server you have not voted on")
+            .kind(ApplicationCommandOptionType::SubCommand)
+
+            .create_sub_option(|opt| opt
+                .name("enabled")
+                .description("Whether to receive reminders")
+                .required(true)
+                .kind(ApplicationCommandOptionType::Boolean)))
+}
+
+pub async fn vote_autocomplete(ctx: Context, interaction: AutocompleteInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let guild_id = match interaction.guild_id {
+        None => return Ok(()),
+        Some(v) => v,
     };
 
-    let mut winners = Vec::new();
-    for (opt, votes) in stv_results.elected() {
-        winners.push((opt.as_str().to_owned(), *votes));
-    }
-    winners.sort_by_key(|(opt, votes)| -(*votes as i64));
+    let sub = match interaction.data.options.first() {
+        None => return Ok(()),
+        Some(v) => v,
+    };
 
-    //
+    let focused = match sub.options.iter().find(|v| v.focused) {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+    let typed = focused.value.as_ref().and_then(|v| v.as_str()).unwrap_or("").trim().to_lowercase();
+
+    let mut suggestions = Vec::new();
 
-    interaction.create_followup_message(&ctx.http, |r| r.create_embed(|e| {
-        e.title("Poll results");
-        e.thumbnail("https://i.imgur.com/fWgQ8b6.png");
+    if focused.name == "poll" {
+        let polls = db::model::list_open_polls(data.db_client.conn(), *guild_id.as_u64()).await?;
 
-        e.field("Poll", format!("{} ({})", poll.name, poll.id), false);
+        for poll in polls.iter()
+            .filter(|v| v.accepting_votes())
+            .filter(|v| v.name.contains(&typed) || v.question.to_lowercase().contains(&typed))
+            .take(AUTOCOMPLETE_LIMIT) {
+            suggestions.push((text::truncate(&format!("{} - {}", poll.name, poll.question), 100), poll.name.clone()));
+        }
+    } else {
+        let poll_name = sub.options.iter()
+            .find(|v| v.name == "poll")
+            .and_then(|v| v.value.as_ref())
+            .and_then(|v| v.as_str());
+
+        let poll = match poll_name {
+            None => None,
+            Some(v) => db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), v).await?,
+        };
+
+        if let Some(poll) = poll {
+            let taken = sub.options.iter()
+                .filter(|v| v.name.starts_with("choice-") && !v.focused)
+                .filter_map(|v| v.value.as_ref().and_then(|v| v.as_str()))
+                .filter_map(|v| poll.find_option(v))
+                .map(|v| v.id)
+                .collect::<HashSet<i32>>();
+
+            for opt in poll.options.iter()
+                .filter(|v| !taken.contains(&v.id))
+                .filter(|v| v.option.to_lowercase().contains(&typed))
+                .take(AUTOCOMPLETE_LIMIT) {
+                let label = match &opt.details.description {
+                    None => opt.option.clone(),
+                    Some(v) => format!("{} - {}", opt.option, v),
+                };
+                suggestions.push((text::truncate(&label, 100), text::truncate(&opt.option, 100)));
+            }
 
-        let mut res_string = String::new();
+            // Suggesting what others wrote in helps voters for the same candidate match spellings,
+            // but shows which write-ins are getting votes, so it is only done for members who may
+            // see the results.
+            let may_see_write_ins = match &interaction.member {
+                _ if !poll.settings.write_ins => false,
+                _ if permissions::results_public(&poll) => true,
+                None => false,
+                Some(member) => {
+                    let settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+                    permissions::may_tally(&settings, &poll, member)
+                }
+            };
 
-        let mut last = u64::MAX;
-        let mut curr = 0;
-        for (opt, votes) in winners {
-            if votes < last {
-                curr += 1;
-                last = votes;
+            if may_see_write_ins && suggestions.len() < AUTOCOMPLETE_LIMIT {
+                for v in db::model::get_write_ins(data.db_client.conn(), poll.id).await?.into_iter()
+                    .filter(|v| v.to_lowercase().contains(&typed))
+                    .take(AUTOCOMPLETE_LIMIT - suggestions.len()) {
+                    suggestions.push((format!("{} (write-in)", v), v));
+                }
             }
-            res_string.push_str(&format!("**{}**. **{}** (cumulative votes: {})\n", curr, opt, votes));
         }
+    }
 
-        e.field("Winners", res_string, false);
-
-        e
-    })).await?;
+    interaction.create_autocomplete_response(&ctx.http, |r| {
+        for (name, value) in suggestions {
+            r.add_string_choice(name, value);
+        }
+        r
+    }).await?;
 
     Ok(())
 }
 
-pub async fn vote(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
-    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
-
-    let sub = &interaction.data.options[0];
+async fn vote_cast(
+    ctx: &Context,
+    interaction: &ApplicationCommandInteraction,
+    sub: &ApplicationCommandInteractionDataOption,
+    data: &BotData,
+    guild_id: &GuildId,
+) -> anyhow::Result<()> {
     let id_user = *interaction.user.id.as_u64();
     // let id_user = Utc::now().time().num_seconds_from_midnight() as u64;
 
-    let data = ctx.data.read().await;
-    let data = data.get::<BotData>().unwrap();
+    let poll_name = command_opt::find_required(ctx, interaction, &sub.options, command_opt::find_string_opt, "poll").await?.unwrap();
 
     //
 
-    let poll = match db::model::get_server_poll(data.db_client.conn(), *interaction.guild_id.unwrap().as_u64(), &sub.name).await {
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &poll_name).await {
         Ok(v) => match v {
             None => {
-                get_logger().info("Failed to find a poll with the name of the /vote sub-option.", meta! {
+                get_logger().info("Failed to find a poll with the name passed to /vote cast.", meta! {
                     "InteractionID" => interaction.id,
-                    "PollName" => sub.name,
+                    "PollName" => poll_name,
                 });
-                command_resp::reply_deferred_result(&ctx, &interaction, format!("Failed to find poll with name **'{}'**.", sub.name)).await?;
+                command_resp::reply_deferred_result(ctx, interaction, format!("Failed to find poll with name **'{}'**.", poll_name)).await?;
                 return Ok(());
             }
             Some(v) => v,
         }
         Err(e) => {
-            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to find poll by name.").await?;
+            command_resp::reply_deferred_result(ctx, interaction, "Error occurred upon attempt to find poll by name.").await?;
             return Err(e);
         }
     };
 
-    if !poll.open {
+    if poll.accepting_nominations() {
+        command_resp::reply_deferred_result(ctx, interaction, format!(
+            "Poll **'{}'** is still collecting nominations; voting has not opened yet.", poll.name
+        )).await?;
+        return Ok(());
+    }
+
+    if !poll.accepting_votes() {
         get_logger().info("User attempted to vote on closed poll.", meta! {
             "InteractionID" => interaction.id,
             "PollID" => poll.id,
             "PollName" => poll.name,
         });
-        command_resp::reply_deferred_result(&ctx, &interaction, format!("Voting is closed for poll **'{}'**.", sub.name)).await?;
+        command_resp::reply_deferred_result(ctx, interaction, format!("Voting is closed for poll **'{}'**.", poll_name)).await?;
+        return Ok(());
+    }
+
+    // A delegate votes as the member who delegated to them: the ballot, its eligibility and its
+    // weight are all the delegator's.
+    let proxy_for = command_opt::find_user_opt(&sub.options, "for");
+    let (id_voter, member) = match &proxy_for {
+        None => (id_user, interaction.member.as_ref()),
+        Some(v) => {
+            let id_delegator = *v.id.as_u64();
+
+            if db::model::get_delegate(data.db_client.conn(), *guild_id.as_u64(), poll.id, id_delegator).await? != Some(id_user) {
+                get_logger().info("User attempted to vote for a member who has not delegated to them.", meta! {
+                    "InteractionID" => interaction.id,
+                    "PollID" => poll.id,
+                    "PollName" => poll.name,
+                    "DelegatorID" => id_delegator,
+                });
+                command_resp::reply_deferred_result(ctx, interaction, format!(
+                    "<@{}> has not delegated their vote on poll **'{}'** to you.", id_delegator, poll.name
+                )).await?;
+                return Ok(());
+            }
+
+            // A member's own ballot always takes precedence over their delegate's.
+            if ballots::cast_by_proxy(data.db_client.conn(), &poll, id_delegator).await? == Some(false) {
+                command_resp::reply_deferred_result(ctx, interaction, format!(
+                    "<@{}> has already voted on poll **'{}'** themselves.", id_delegator, poll.name
+                )).await?;
+                return Ok(());
+            }
+
+            (id_delegator, None)
+        }
+    };
+
+    if let Some(reason) = eligibility::check(&ctx.http, data.db_client.conn(), &poll, member, id_voter).await? {
+        get_logger().info("Ineligible user attempted to vote.", meta! {
+            "InteractionID" => interaction.id,
+            "PollID" => poll.id,
+            "PollName" => poll.name,
+            "VoterID" => id_voter,
+        });
+        let reason = match &proxy_for {
+            None => reason,
+            Some(_) => format!("<@{}> cannot vote on this poll: {}", id_voter, reason),
+        };
+        command_resp::reply_deferred_result(ctx, interaction, reason).await?;
         return Ok(());
     }
 
     //
 
+    // Write-ins are merged with those already cast, and with earlier ones on this ballot.
+    let mut write_ins = match poll.settings.write_ins {
+        true => db::model::get_write_ins(data.db_client.conn(), poll.id).await?,
+        false => Vec::new(),
+    };
+
     let mut chosen = HashSet::new();
     let mut choices = HashMap::new();
 
-    for choice in &sub.options {
+    for choice in sub.options.iter().filter(|v| v.name.starts_with("choice-")) {
         let n = choice.name.split_once('-').unwrap();
         let n = n.1.parse::<u8>().unwrap();
 
+        if n > poll.settings.ranks {
+            get_logger().info("User ranked more choices than the poll allows.", meta! {
+                "InteractionID" => interaction.id,
+                "PollID" => poll.id,
+                "PollName" => poll.name,
+                "ChoiceN" => n,
+            });
+            command_resp::reply_deferred_result(ctx, interaction, format!(
+                "Poll **'{}'** only allows {} ranked choices; `choice-{}` is not allowed.", poll.name, poll.settings.ranks, n
+            )).await?;
+            return Ok(());
+        }
+
         let v = match choice.resolved.as_ref().unwrap() {
             ApplicationCommandInteractionDataOptionValue::String(v) => v,
             _ => {
@@ -423,116 +1689,341 @@ pub async fn vote(ctx: Context, interaction: ApplicationCommandInteraction) -> a
                     "PollID" => poll.id,
                     "PollName" => poll.name,
                 });
-                command_resp::reply_deferred_result(&ctx, &interaction, format!("Invalid value; expected string, got '{:?}'.", choice.kind)).await?;
+                command_resp::reply_deferred_result(ctx, interaction, format!("Invalid value; expected string, got '{:?}'.", choice.kind)).await?;
                 return Ok(());
             }
         };
 
-        for opt in &poll.options {
-            if &opt.option == v {
-                if chosen.contains(&opt.option) {
-                    get_logger().info("User chose same option in multiple choice positions.", meta! {
-                        "InteractionID" => interaction.id,
-                        "PollID" => poll.id,
-                        "PollName" => poll.name,
-                        "ChoiceN" => n,
-                    });
-                    command_resp::reply_deferred_result(&ctx, &interaction, format!(
-                        "Duplicate choice selection '{}'. Only choose the same option once; e.g., don't choose option 'A' for both `choice-1` and `choice-3`.",
-                        opt.option
-                    )).await?;
-                    return Ok(());
+        let choice = match poll.find_option(v) {
+            Some(opt) => Choice::Option(opt.id),
+            None if poll.settings.write_ins => match validate::write_in(v) {
+                Ok(v) => {
+                    let v = ballots::merge_write_in(&write_ins, v);
+                    if !write_ins.contains(&v) {
+                        write_ins.push(v.clone());
+                    }
+                    Choice::WriteIn(v)
                 }
-
-                if choices.contains_key(&n) {
-                    // Should never happen
-                    get_logger().info("Duplicate choice-n argument passed to /vote sub-option.", meta! {
-                        "InteractionID" => interaction.id,
-                        "PollID" => poll.id,
-                        "PollName" => poll.name,
-                        "ChoiceN" => n,
-                    });
-                    command_resp::reply_deferred_result(&ctx, &interaction, format!("Duplicate choice number '{}'.", n)).await?;
+                Err(e) => {
+                    command_resp::reply_deferred_result(ctx, interaction, e).await?;
                     return Ok(());
                 }
+            },
+            None => {
+                get_logger().info("User chose an option that is not on the poll.", meta! {
+                    "InteractionID" => interaction.id,
+                    "PollID" => poll.id,
+                    "PollName" => poll.name,
+                    "ChoiceN" => n,
+                });
+                command_resp::reply_deferred_result(ctx, interaction, format!(
+                    "'{}' is not an option on poll **'{}'**.", v, poll.name
+                )).await?;
+                return Ok(());
+            }
+        };
 
-                chosen.insert(opt.option.clone());
-                choices.insert(n, opt);
+        if chosen.contains(&choice) {
+            get_logger().info("User chose same option in multiple choice positions.", meta! {
+                "InteractionID" => interaction.id,
+                "PollID" => poll.id,
+                "PollName" => poll.name,
+                "ChoiceN" => n,
+            });
+            command_resp::reply_deferred_result(ctx, interaction, format!(
+                "Duplicate choice selection '{}'. Only choose the same option once; e.g., don't choose option 'A' for both `choice-1` and `choice-3`.",
+                ballots::choice_label(&poll, &choice).unwrap_or_default()
+            )).await?;
+            return Ok(());
+        }
 
-                break;
-            }
+        if choices.contains_key(&n) {
+            // Should never happen
+            get_logger().info("Duplicate choice-n argument passed to /vote cast.", meta! {
+                "InteractionID" => interaction.id,
+                "PollID" => poll.id,
+                "PollName" => poll.name,
+                "ChoiceN" => n,
+            });
+            command_resp::reply_deferred_result(ctx, interaction, format!("Duplicate choice number '{}'.", n)).await?;
+            return Ok(());
         }
+
+        chosen.insert(choice.clone());
+        choices.insert(n, choice);
     }
 
     //
 
-    let existed = match db::model::get_valid_ballot(data.db_client.conn(), poll.id, id_user).await {
+    let ballot = choices.into_iter().map(|(i, v)| (v, i)).collect::<Vec<(Choice, u8)>>();
+    let (cast, existed) = match ballots::cast_ballot(&ctx.http, data.db_client.conn(), &poll, member, id_voter, proxy_for.is_some(), &ballot).await {
         Ok(v) => v,
         Err(e) => {
-            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to find user's existing ballot for poll.").await?;
+            command_resp::reply_deferred_result(ctx, interaction, "Error occurred upon attempt to cast ballot.").await?;
             return Err(e);
         }
     };
-    match &existed {
-        None => {}
-        Some(v) => match db::model::invalidate_ballot(data.db_client.conn(), v.id).await {
-            Ok(()) => {}
-            Err(e) => {
-                command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to invalidate user's existing ballot for poll.").await?;
-                return Err(e);
-            }
-        }
+
+    if proxy_for.is_some() {
+        get_logger().info("Delegate cast a ballot.", meta! {
+            "PollID" => poll.id,
+            "DelegatorID" => id_voter,
+            "DelegateID" => id_user,
+        });
     }
 
+    interaction.create_followup_message(&ctx.http, |r| {
+        r.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+        if proxy_for.is_some() {
+            r.content(format!("Cast on behalf of <@{}>.", id_voter));
+        }
+        r.create_embed(|e| ballots::ballot_embed(e, &poll, &cast, &existed))
+    }).await?;
+
+    Ok(())
+}
+
+async fn vote_mine(
+    ctx: &Context,
+    interaction: &ApplicationCommandInteraction,
+    sub: &ApplicationCommandInteractionDataOption,
+    data: &BotData,
+    guild_id: &GuildId,
+) -> anyhow::Result<()> {
+    let id_user = *interaction.user.id.as_u64();
+
+    let poll_name = command_opt::find_required(ctx, interaction, &sub.options, command_opt::find_string_opt, "poll").await?.unwrap();
+
     //
 
-    let ballot = choices.iter().map(|(i, v)| (v.id, *i)).collect::<Vec<(i32, u8)>>();
-    match db::model::add_ballot(data.db_client.conn(), poll.id, id_user, &ballot).await {
-        Ok(()) => {}
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &poll_name).await {
+        Ok(v) => match v {
+            None => {
+                get_logger().info("Failed to find a poll with the name passed to /vote mine.", meta! {
+                    "InteractionID" => interaction.id,
+                    "PollName" => poll_name,
+                });
+                command_resp::reply_deferred_result(ctx, interaction, format!("Failed to find poll with name **'{}'**.", poll_name)).await?;
+                return Ok(());
+            }
+            Some(v) => v,
+        }
         Err(e) => {
-            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to add ballot.").await?;
+            command_resp::reply_deferred_result(ctx, interaction, "Error occurred upon attempt to find poll by name.").await?;
             return Err(e);
         }
-    }
+    };
+
+    let current = match db::model::get_valid_ballot(data.db_client.conn(), poll.id, &ballots::voter(&poll, id_user)).await {
+        Ok(v) => match v {
+            None => {
+                command_resp::reply_deferred_result(ctx, interaction, format!("You have not voted on poll **'{}'**.", poll.name)).await?;
+                return Ok(());
+            }
+            Some(v) => v,
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(ctx, interaction, "Error occurred upon attempt to look up your ballot.").await?;
+            return Err(e);
+        }
+    };
 
-    let choice_keys = choices.keys().sorted().collect::<Vec<&u8>>();
+    // Once voting has closed the ballot is final, so only offer to withdraw it while it can still change.
+    let withdrawable = poll.accepting_votes();
+
+    interaction.create_followup_message(&ctx.http, |r| {
+        r.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+        r.create_embed(|e| ballots::current_ballot_embed(e, &poll, &current));
+        if withdrawable {
+            r.components(|c| c.create_action_row(|row| row.create_button(|b| b
+                .custom_id(format!("{}:{}", ballot::VOTE_WITHDRAW, poll.id))
+                .label("Withdraw ballot")
+                .style(ButtonStyle::Danger))));
+        }
+        r
+    }).await?;
 
-    interaction.create_followup_message(&ctx.http, |r| r.create_embed(|e| {
-        e.title("Ballot cast");
-        e.thumbnail("https://i.imgur.com/fWgQ8b6.png");
+    Ok(())
+}
 
-        e.field("Poll", format!("{} ({})", poll.name, poll.id), false);
+// Finds the open poll a delegation is scoped to, `Some(None)` meaning every poll in the server.
+// Replies and returns `None` if the poll named cannot take a delegation.
+async fn delegation_poll(
+    ctx: &Context,
+    interaction: &ApplicationCommandInteraction,
+    sub: &ApplicationCommandInteractionDataOption,
+    data: &BotData,
+    guild_id: &GuildId,
+) -> anyhow::Result<Option<Option<Poll>>> {
+    let poll_name = match command_opt::find_string_opt(&sub.options, "poll") {
+        None => return Ok(Some(None)),
+        Some(v) => v,
+    };
 
-        match &existed {
-            None => {}
-            Some(v) => {
-                e.field("Replaced ballot ID", format!("{} (from {})", v.id, v.time_created), false);
-            }
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &poll_name).await? {
+        None => {
+            command_resp::reply_deferred_result(ctx, interaction, format!("Failed to find poll with name **'{}'**.", poll_name)).await?;
+            return Ok(None);
         }
+        Some(v) => v,
+    };
 
-        let mut opt_string = String::new();
-        for key in &choice_keys {
-            let v = choices[key];
-            opt_string.push_str(&format!("**{}.** {}\n", num_word(**key), v.option));
+    // Delegations only matter while ballots can be cast, and are fixed once voting closes.
+    if !poll.accepting_votes() {
+        command_resp::reply_deferred_result(ctx, interaction, format!("Voting is closed for poll **'{}'**.", poll.name)).await?;
+        return Ok(None);
+    }
+
+    Ok(Some(Some(poll)))
+}
+
+async fn vote_delegate(
+    ctx: &Context,
+    interaction: &ApplicationCommandInteraction,
+    sub: &ApplicationCommandInteractionDataOption,
+    data: &BotData,
+    guild_id: &GuildId,
+) -> anyhow::Result<()> {
+    let id_user = *interaction.user.id.as_u64();
+
+    let to = command_opt::find_required(ctx, interaction, &sub.options, command_opt::find_user_opt, "to").await?.unwrap();
+    let id_delegate = *to.id.as_u64();
+
+    if id_delegate == id_user {
+        command_resp::reply_deferred_result(ctx, interaction, "You cannot delegate your vote to yourself.").await?;
+        return Ok(());
+    }
+
+    let poll = match delegation_poll(ctx, interaction, sub, data, guild_id).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    db::model::add_delegation(data.db_client.conn(), *guild_id.as_u64(), poll.as_ref().map(|v| v.id), id_user, id_delegate).await?;
+
+    get_logger().info("User delegated their vote.", meta! {
+        "InteractionID" => interaction.id,
+        "PollID" => poll.as_ref().map(|v| v.id.to_string()).unwrap_or_else(|| "*".to_owned()),
+        "DelegateID" => id_delegate,
+    });
+
+    let scope = match &poll {
+        None => "every poll in this server".to_owned(),
+        Some(v) => format!("poll **'{}'**", v.name),
+    };
+
+    command_resp::reply_deferred_result(ctx, interaction, format!(
+        "<@{}> may now vote for you on {} with `/vote cast for:`. Your own ballot always takes precedence; use `/vote revoke` to undo this.",
+        id_delegate, scope,
+    )).await?;
+
+    Ok(())
+}
+
+async fn vote_revoke(
+    ctx: &Context,
+    interaction: &ApplicationCommandInteraction,
+    sub: &ApplicationCommandInteractionDataOption,
+    data: &BotData,
+    guild_id: &GuildId,
+) -> anyhow::Result<()> {
+    let id_user = *interaction.user.id.as_u64();
+
+    let poll = match delegation_poll(ctx, interaction, sub, data, guild_id).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let revoked = db::model::revoke_delegation(data.db_client.conn(), *guild_id.as_u64(), poll.as_ref().map(|v| v.id), id_user).await?;
+
+    let scope = match &poll {
+        None => "every poll in this server".to_owned(),
+        Some(v) => format!("poll **'{}'**", v.name),
+    };
+
+    if !revoked {
+        command_resp::reply_deferred_result(ctx, interaction, format!("You have not delegated your vote on {}.", scope)).await?;
+        return Ok(());
+    }
+
+    get_logger().info("User revoked their delegation.", meta! {
+        "InteractionID" => interaction.id,
+        "PollID" => poll.as_ref().map(|v| v.id.to_string()).unwrap_or_else(|| "*".to_owned()),
+    });
+
+    command_resp::reply_deferred_result(ctx, interaction, format!(
+        "Revoked your delegation on {}. Ballots already cast for you stand until you vote or withdraw them with `/vote mine`.", scope
+    )).await?;
+
+    Ok(())
+}
+
+async fn vote_reminders(
+    ctx: &Context,
+    interaction: &ApplicationCommandInteraction,
+    sub: &ApplicationCommandInteractionDataOption,
+    data: &BotData,
+    guild_id: &GuildId,
+) -> anyhow::Result<()> {
+    let enabled = command_opt::find_required(ctx, interaction, &sub.options, command_opt::find_boolean_opt, "enabled").await?.unwrap();
+
+    db::model::set_reminder_opt_out(data.db_client.conn(), *guild_id.as_u64(), *interaction.user.id.as_u64(), !enabled).await?;
+
+    get_logger().info("User changed voting reminders.", meta! {
+        "InteractionID" => interaction.id,
+        "Enabled" => enabled,
+    });
+
+    command_resp::reply_deferred_result(ctx, interaction, match enabled {
+        true => "You will be reminded about polls in this server you have not voted on, when a poll sends reminders.",
+        false => "You will no longer be reminded about polls in this server.",
+    }).await?;
+
+    Ok(())
+}
+
+pub async fn vote(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    command_resp::reply_deferred_ack_ephemeral(&ctx, &interaction).await?;
+
+    let sub = &interaction.data.options[0];
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let guild_id = match interaction.guild_id {
+        None => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Use /vote in a server, or the poll's 'Vote by DM' button to vote privately.").await?;
+            return Ok(());
         }
-        e.field("Choices", opt_string, false);
+        Some(v) => v,
+    };
 
-        e
-    })).await?;
+    match sub.name.as_str() {
+        "cast" => vote_cast(&ctx, &interaction, sub, data, &guild_id).await?,
+        "mine" => vote_mine(&ctx, &interaction, sub, data, &guild_id).await?,
+        "delegate" => vote_delegate(&ctx, &interaction, sub, data, &guild_id).await?,
+        "revoke" => vote_revoke(&ctx, &interaction, sub, data, &guild_id).await?,
+        "reminders" => vote_reminders(&ctx, &interaction, sub, data, &guild_id).await?,
+        _ => {}
+    }
 
     Ok(())
 }
 
 pub async fn poll(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
-    command_resp::reply_deferred_ack(&ctx, &interaction).await?;
+    let sub = &interaction.data.options[0];
+
+    // Results are for everyone; confirmations and errors only for the invoking member.
+    match sub.name.as_str() {
+        "tally" => command_resp::reply_deferred_ack(&ctx, &interaction).await?,
+        _ => command_resp::reply_deferred_ack_ephemeral(&ctx, &interaction).await?,
+    }
 
     let guild_id = interaction.guild_id.as_ref().unwrap();
     let member = interaction.member.as_ref().unwrap();
 
     //
 
-    let sub = &interaction.data.options[0];
-
     println!("{:?}", interaction);
     println!("{:?}", sub);
 
@@ -541,7 +2032,18 @@ pub async fn poll(ctx: Context, interaction: ApplicationCommandInteraction) -> a
 
     match sub.name.as_str() {
         "create" => poll_create(&ctx, &interaction, sub, data, guild_id, member).await?,
-        "close" => poll_close(&ctx, &interaction, sub, data, guild_id, member).await?,
+        "wizard" => wizard::poll_wizard(&ctx, &interaction, sub, data, guild_id).await?,
+        "nominations" => nomination::poll_nominations(&ctx, &interaction, sub, data, guild_id, member).await?,
+        "nominate" => nomination::poll_nominate(&ctx, &interaction, sub, data, guild_id, member).await?,
+        "promote" => nomination::poll_promote(&ctx, &interaction, sub, data, guild_id).await?,
+        "edit" => poll_edit(&ctx, &interaction, sub, data, guild_id).await?,
+        "eligibility" => poll_eligibility(&ctx, &interaction, sub, data, guild_id).await?,
+        "weights" => poll_weights(&ctx, &interaction, sub, data, guild_id).await?,
+        "roll" => poll_roll(&ctx, &interaction, sub, data, guild_id).await?,
+        "info" => poll_info(&ctx, &interaction, sub, data, guild_id).await?,
+        "clone" => poll_clone(&ctx, &interaction, sub, data, guild_id, member).await?,
+        "close" => poll_close(&ctx, &interaction, sub, data, guild_id).await?,
+        "delete" => poll_delete(&ctx, &interaction, sub, data, guild_id).await?,
         "tally" => poll_tally(&ctx, &interaction, sub, data, guild_id, member).await?,
         _ => {}
     }
@@ -0,0 +1,462 @@
+use chrono::Utc;
+use evlog::meta;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::guild::Member;
+use serenity::model::id::GuildId;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandInteractionDataOption, ApplicationCommandOptionType};
+
+use crate::db;
+use crate::db::schema::{OptionDetails, Poll, PollSettings};
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp};
+use crate::runtime::get_logger;
+use crate::support::{poll_status, recurrence, text, validate};
+
+pub const TEMPLATE: &str = "template";
+
+pub fn template_builder(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.name(TEMPLATE)
+        .description("Save polls as templates and create new polls from them")
+        .create_option(|opt| opt
+            .name("save")
+            .description("Save a poll's question, options, and rules as a template")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("poll")
+                .description("The name of the poll to save")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("name")
+                .description("Name for the template; replaces any template with the same name")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|opt| opt
+            .name("use")
+            .description("Create a new poll from a template")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("name")
+                .description("The name of the template")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("poll")
+                .description("Unique identifier for the new poll; no whitespace, may only contain a-z, 0-9, and -")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("question")
+                .description("Ask a different question than the template's")
+                .required(false)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("duration")
+                .description("Hours until voting closes, instead of the template's; 0 to close it manually")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Integer)))
+        .create_option(|opt| opt
+            .name("recur")
+            .description("Create a new poll from a template every period, announced in this channel")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("name")
+                .description("The name of the template")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("every")
+                .description("How often to create a poll: daily, weekly, or e.g. 12h, 3d, 2w")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String))
+            .create_sub_option(|opt| opt
+                .name("start")
+                .description("Hours until the first poll is created; 0 (the default) to create it now")
+                .required(false)
+                .kind(ApplicationCommandOptionType::Integer)))
+        .create_option(|opt| opt
+            .name("stop")
+            .description("Stop creating polls from a template")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("name")
+                .description("The name of the template")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String)))
+        .create_option(|opt| opt
+            .name("list")
+            .description("List this server's templates")
+            .kind(ApplicationCommandOptionType::SubCommand))
+        .create_option(|opt| opt
+            .name("delete")
+            .description("Delete a template")
+            .kind(ApplicationCommandOptionType::SubCommand)
+            .create_sub_option(|opt| opt
+                .name("name")
+                .description("The name of the template")
+                .required(true)
+                .kind(ApplicationCommandOptionType::String)));
+
+    cmd
+}
+
+/// How long a poll was set to stay open, in whole hours, or `None` if it had no deadline.
+#[must_use]
+pub(super) fn duration_hours(poll: &Poll) -> Option<u32> {
+    poll.time_close.map(|v| (v - poll.time_created).num_hours().max(1) as u32)
+}
+
+/// Creates a poll copied from a template or another poll, replying with the reason and returning
+/// `None` if it cannot be created. `duration` is in hours, with 0 or `None` leaving
+/// the poll open until it is closed manually. Callers announce and confirm the poll once they have
+/// finished setting it up.
+pub(super) async fn create_from(
+    ctx: &Context,
+    interaction: &ApplicationCommandInteraction,
+    data: &BotData,
+    guild_id: &GuildId,
+    member: &Member,
+    name: &str,
+    question: &str,
+    settings: &PollSettings,
+    duration: Option<i64>,
+    options: &[(String, OptionDetails)],
+) -> anyhow::Result<Option<Poll>> {
+    let name = match validate::poll_name(name) {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(None);
+        }
+    };
+
+    let time_close = match duration {
+        None | Some(0) => None,
+        Some(v) => match validate::deadline(v) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(None);
+            }
+        }
+    };
+
+    let server_has_poll_name = match db::model::check_server_has_poll_name(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to check for existing poll by name.").await?;
+            return Err(e);
+        }
+    };
+
+    if server_has_poll_name {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "A poll with the name `{}` has already been created in this server.", name
+        )).await?;
+        return Ok(None);
+    }
+
+    let poll = match db::model::add_poll(
+        data.db_client.conn(),
+        *guild_id.as_u64(),
+        *member.user.id.as_u64(),
+        &name,
+        question,
+        settings,
+        time_close,
+        None,
+        options,
+    ).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to add poll to database.").await?;
+            return Err(e);
+        }
+    };
+
+    Ok(Some(poll))
+}
+
+async fn template_save(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId, member: &Member) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/template save").await? {
+        return Ok(());
+    }
+
+    let poll_name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "poll").await?.unwrap();
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+
+    let name = match validate::poll_name(&name) {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    let poll = match db::model::get_server_poll(data.db_client.conn(), *guild_id.as_u64(), &poll_name).await {
+        Ok(v) => match v {
+            None => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("Failed to find poll with name **'{}'**.", poll_name)).await?;
+                return Ok(());
+            }
+            Some(v) => v,
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up poll.").await?;
+            return Err(e);
+        }
+    };
+
+    let options = poll.options.iter()
+        .map(|v| (v.option.clone(), v.details.clone()))
+        .collect::<Vec<(String, OptionDetails)>>();
+
+    if options.len() < 2 {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "Poll **'{}'** has no options to save yet.", poll.name
+        )).await?;
+        return Ok(());
+    }
+
+    if let Err(e) = db::model::save_template(
+        data.db_client.conn(),
+        *guild_id.as_u64(),
+        *member.user.id.as_u64(),
+        &name,
+        &poll.question,
+        &poll.settings,
+        duration_hours(&poll),
+        &options,
+    ).await {
+        command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to save template.").await?;
+        return Err(e);
+    }
+
+    get_logger().info("Saved poll as template.", meta! {
+        "InteractionID" => interaction.id,
+        "PollID" => poll.id,
+        "Template" => name,
+    });
+
+    command_resp::reply_deferred_result(&ctx, &interaction, format!(
+        "Saved poll **'{}'** as template **'{}'**; create polls from it with `/template use`.", poll.name, name
+    )).await?;
+
+    Ok(())
+}
+
+async fn template_use(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId, member: &Member) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/template use").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let poll_name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "poll").await?.unwrap();
+
+    let template = match db::model::get_server_template(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => match v {
+            None => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("No template named **'{}'** was found.", name)).await?;
+                return Ok(());
+            }
+            Some(v) => v,
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up template.").await?;
+            return Err(e);
+        }
+    };
+
+    let question = command_opt::find_string_opt(&opt.options, "question").unwrap_or(template.question);
+    let duration = command_opt::find_integer_opt(&opt.options, "duration")
+        .or_else(|| template.duration_hours.map(|v| v as i64));
+
+    let channel = super::poll::announce_channel(interaction, data, guild_id).await?;
+
+    let poll = match create_from(ctx, interaction, data, guild_id, member, &poll_name, &question, &template.settings, duration, &template.options).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    get_logger().info("Created poll from template.", meta! {
+        "InteractionID" => interaction.id,
+        "PollID" => poll.id,
+        "TemplateID" => template.id,
+    });
+
+    let announced = poll_status::announce(&ctx.http, data.db_client.conn(), &poll, channel).await;
+
+    command_resp::reply_deferred_result(&ctx, &interaction, super::poll::created_message(&poll, channel, announced)).await?;
+
+    Ok(())
+}
+
+async fn template_recur(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId, member: &Member) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/template recur").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let every = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "every").await?.unwrap();
+
+    let period_hours = match validate::period(&every) {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    let time_next = match command_opt::find_integer_opt(&opt.options, "start") {
+        None | Some(0) => Utc::now(),
+        Some(v) => match validate::hours_from_now("start", v) {
+            Ok(v) => v,
+            Err(e) => {
+                command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+                return Ok(());
+            }
+        }
+    };
+
+    let template = match db::model::get_server_template(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => match v {
+            None => {
+                command_resp::reply_deferred_result(&ctx, &interaction, format!("No template named **'{}'** was found.", name)).await?;
+                return Ok(());
+            }
+            Some(v) => v,
+        }
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to look up template.").await?;
+            return Err(e);
+        }
+    };
+
+    let channel = super::poll::announce_channel(interaction, data, guild_id).await?;
+
+    if let Err(e) = db::model::set_recurrence(
+        data.db_client.conn(),
+        *guild_id.as_u64(),
+        *member.user.id.as_u64(),
+        &template.name,
+        period_hours,
+        time_next,
+        *channel.as_u64(),
+    ).await {
+        command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to save recurrence.").await?;
+        return Err(e);
+    }
+
+    get_logger().info("Set template recurrence.", meta! {
+        "InteractionID" => interaction.id,
+        "TemplateID" => template.id,
+        "PeriodHours" => period_hours,
+    });
+
+    // The scheduler creates the first poll, within a minute if it is due now.
+    command_resp::reply_deferred_result(&ctx, &interaction, format!(
+        "A poll will be created from template **'{}'** {}, starting <t:{}:R>, and announced in <#{}>. Each poll closes by the time the next one opens.",
+        template.name, recurrence::describe(period_hours), time_next.timestamp(), channel
+    )).await?;
+
+    Ok(())
+}
+
+async fn template_stop(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/template stop").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+
+    match db::model::delete_recurrence(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(true) => command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "Polls will no longer be created from template **'{}'**; polls already created stay open until their deadline.", name
+        )).await?,
+        Ok(false) => command_resp::reply_deferred_result(&ctx, &interaction, format!("Template **'{}'** does not recur.", name)).await?,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to stop recurrence.").await?;
+            return Err(e);
+        }
+    };
+
+    Ok(())
+}
+
+async fn template_list(ctx: &Context, interaction: &ApplicationCommandInteraction, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    let names = match db::model::list_templates(data.db_client.conn(), *guild_id.as_u64()).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to list templates.").await?;
+            return Err(e);
+        }
+    };
+
+    let recurrences = match db::model::list_recurrences(data.db_client.conn(), *guild_id.as_u64()).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to list recurrences.").await?;
+            return Err(e);
+        }
+    };
+
+    let names = names.into_iter()
+        .map(|name| match recurrences.iter().find(|v| v.template == name) {
+            None => name,
+            Some(v) => format!("{} (recurs {}; next <t:{}:R>)", name, recurrence::describe(v.period_hours), v.time_next.timestamp()),
+        })
+        .collect::<Vec<String>>();
+
+    match names.is_empty() {
+        true => command_resp::reply_deferred_result(&ctx, &interaction, "This server has no templates; save one with `/template save`.").await?,
+        false => command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "**Templates**\n{}", text::numbered_list(&names, 1900)
+        )).await?,
+    };
+
+    Ok(())
+}
+
+async fn template_delete(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/template delete").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+
+    match db::model::delete_template(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(true) => command_resp::reply_deferred_result(&ctx, &interaction, format!("Deleted template **'{}'**.", name)).await?,
+        Ok(false) => command_resp::reply_deferred_result(&ctx, &interaction, format!("No template named **'{}'** was found.", name)).await?,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to delete template.").await?;
+            return Err(e);
+        }
+    };
+
+    Ok(())
+}
+
+pub async fn template(ctx: Context, interaction: ApplicationCommandInteraction) -> anyhow::Result<()> {
+    command_resp::reply_deferred_ack_ephemeral(&ctx, &interaction).await?;
+
+    let sub = &interaction.data.options[0];
+
+    let guild_id = interaction.guild_id.as_ref().unwrap();
+    let member = interaction.member.as_ref().unwrap();
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    match sub.name.as_str() {
+        "save" => template_save(&ctx, &interaction, sub, data, guild_id, member).await?,
+        "use" => template_use(&ctx, &interaction, sub, data, guild_id, member).await?,
+        "recur" => template_recur(&ctx, &interaction, sub, data, guild_id, member).await?,
+        "stop" => template_stop(&ctx, &interaction, sub, data, guild_id).await?,
+        "list" => template_list(&ctx, &interaction, data, guild_id).await?,
+        "delete" => template_delete(&ctx, &interaction, sub, data, guild_id).await?,
+        _ => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,652 @@
+use chrono::{DateTime, Utc};
+use evlog::meta;
+use serenity::builder::CreateComponents;
+use serenity::client::Context;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::interactions::InteractionResponseType;
+use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandInteractionDataOption};
+use serenity::model::interactions::message_component::{ActionRowComponent, ButtonStyle, InputTextStyle, MessageComponentInteraction};
+use serenity::model::interactions::modal::ModalSubmitInteraction;
+
+use crate::db;
+use crate::db::schema::{Method, PollSettings, Quorum};
+use crate::handler::BotData;
+use crate::helpers::{command_opt, command_resp, component_resp};
+use crate::runtime::get_logger;
+use crate::support::{eligibility, poll_status, quorum, text, validate};
+
+pub const WIZARD_METHOD: &str = "wizard-method";
+pub const WIZARD_SEATS: &str = "wizard-seats";
+pub const WIZARD_RANKS: &str = "wizard-ranks";
+pub const WIZARD_DURATION: &str = "wizard-duration";
+pub const WIZARD_SECRET: &str = "wizard-secret";
+pub const WIZARD_OPTIONS: &str = "wizard-options";
+pub const WIZARD_OPTIONS_MODAL: &str = "wizard-options-modal";
+pub const WIZARD_ELIGIBILITY: &str = "wizard-eligibility";
+pub const WIZARD_ROLES: &str = "wizard-roles";
+pub const WIZARD_CONFIRM: &str = "wizard-confirm";
+pub const WIZARD_CANCEL: &str = "wizard-cancel";
+
+// The wizard lives in the ephemeral response to /poll wizard, which can only be edited for 15
+// minutes.
+const WIZARD_LIFETIME_MINUTES: i64 = 14;
+
+// Discord's limit on the length of a modal's text input.
+const MAX_OPTIONS_TEXT_LEN: u64 = 4000;
+// The custom IDs of the modal's quorum and reminders inputs; its options input reuses
+// `WIZARD_OPTIONS`.
+const INPUT_QUORUM: &str = "quorum";
+const INPUT_REMINDERS: &str = "reminders";
+// Discord's limit on the options of a select menu.
+const MAX_ROLES: usize = 25;
+
+const DURATIONS: &[(i64, &str)] = &[
+    (0, "No deadline"),
+    (1, "1 hour"),
+    (6, "6 hours"),
+    (12, "12 hours"),
+    (24, "1 day"),
+    (48, "2 days"),
+    (72, "3 days"),
+    (168, "1 week"),
+    (336, "2 weeks"),
+];
+
+/// A poll being assembled by `/poll wizard`, keyed in `BotData::poll_wizards` by its creator.
+#[derive(Clone)]
+pub struct PollWizard {
+    pub id_server: u64,
+    pub name: String,
+    pub question: String,
+    pub options: Vec<String>,
+    pub settings: PollSettings,
+    /// Hours until the poll closes; 0 for no deadline.
+    pub duration: i64,
+    /// Roles that may be required to vote, as (ID, name), highest first.
+    pub roles: Vec<(u64, String)>,
+    /// Whether the wizard shows the choice of roles rather than the other settings.
+    pub choosing_roles: bool,
+    pub interaction: ApplicationCommandInteraction,
+    pub time_started: DateTime<Utc>,
+}
+
+impl PollWizard {
+    fn expired(&self) -> bool {
+        Utc::now() - self.time_started > chrono::Duration::minutes(WIZARD_LIFETIME_MINUTES)
+    }
+
+    /// Keeps ranks and seats within what the current options allow.
+    fn clamp(&mut self) {
+        let n = self.options.len().max(2);
+
+        self.settings.ranks = self.settings.ranks.max(2).min(n.min(validate::MAX_RANKS as usize) as u8);
+        if self.settings.seats as usize >= n {
+            self.settings.seats = 1;
+        }
+    }
+}
+
+fn wizard_content(w: &PollWizard) -> String {
+    let mut r = format!("**New poll `{}`**\n{}\n\n", w.name, w.question);
+
+    if w.options.is_empty() {
+        r.push_str("Enter the options, one per line, optionally as `Name | description | link | emoji`.");
+        return r;
+    }
+
+    r.push_str(&text::numbered_list(&w.options, 1200));
+
+    r.push_str(&format!("\n\n**Method:** {}", w.settings.method.label()));
+    if w.settings.method == Method::Stv {
+        r.push_str(&format!("\n**Seats:** {}", w.settings.seats));
+    }
+    r.push_str(&format!("\n**Ranks:** {}", w.settings.ranks));
+    r.push_str(&format!("\n**Secret ballot:** {}", if w.settings.secret { "Yes" } else { "No" }));
+
+    let duration = DURATIONS.iter().find(|(h, _)| *h == w.duration).map_or("Unknown", |(_, l)| l);
+    r.push_str(&format!("\n**Deadline:** {}", duration));
+
+    let voters = eligibility::describe(&w.settings.eligibility).unwrap_or_else(|| "Anyone in this server".to_owned());
+    r.push_str(&format!("\n**Who may vote:** {}", voters));
+
+    if let Some(v) = w.settings.quorum {
+        r.push_str(&format!("\n**Quorum:** {}", quorum::describe(v)));
+    }
+    if !w.settings.reminders.hours.is_empty() {
+        r.push_str(&format!("\n**Reminders:** {} hour(s) before the deadline", hours_list(&w.settings.reminders.hours)));
+    }
+
+    r.push_str("\n\nAdjust the settings below, then create the poll. Excluded roles and minimum membership ages can be set with `/poll eligibility` once it is created.");
+
+    r
+}
+
+fn wizard_components<'a>(c: &'a mut CreateComponents, w: &PollWizard) -> &'a mut CreateComponents {
+    if w.choosing_roles && !w.roles.is_empty() {
+        c.create_action_row(|r| r.create_select_menu(|m| m
+            .custom_id(WIZARD_ROLES)
+            .placeholder("Anyone in this server")
+            .min_values(0)
+            .max_values(w.roles.len() as u64)
+            .options(|o| {
+                for (id, name) in &w.roles {
+                    o.create_option(|so| so
+                        .label(format!("Members with @{}", text::truncate(name, 80)))
+                        .value(id)
+                        .default_selection(w.settings.eligibility.required_roles.contains(id)));
+                }
+                o
+            })));
+
+        c.create_action_row(|r| r.create_button(|b| b
+            .custom_id(WIZARD_ELIGIBILITY)
+            .label("Done")
+            .style(ButtonStyle::Primary)));
+
+        return c;
+    }
+
+    if !w.options.is_empty() {
+        c.create_action_row(|r| r.create_select_menu(|m| m
+            .custom_id(WIZARD_METHOD)
+            .options(|o| {
+                for method in Method::ALL {
+                    o.create_option(|so| so
+                        .label(method.label())
+                        .value(method.as_str())
+                        .default_selection(*method == w.settings.method));
+                }
+                o
+            })));
+
+        if w.settings.method == Method::Stv {
+            c.create_action_row(|r| r.create_select_menu(|m| m
+                .custom_id(WIZARD_SEATS)
+                .options(|o| {
+                    for seats in 1..w.options.len().min(26) {
+                        o.create_option(|so| so
+                            .label(format!("{} seat(s)", seats))
+                            .value(seats)
+                            .default_selection(seats == w.settings.seats as usize));
+                    }
+                    o
+                })));
+        }
+
+        c.create_action_row(|r| r.create_select_menu(|m| m
+            .custom_id(WIZARD_RANKS)
+            .options(|o| {
+                for ranks in 2..=w.options.len().min(validate::MAX_RANKS as usize).max(2) {
+                    o.create_option(|so| so
+                        .label(format!("Rank up to {} choices", ranks))
+                        .value(ranks)
+                        .default_selection(ranks == w.settings.ranks as usize));
+                }
+                o
+            })));
+
+        c.create_action_row(|r| r.create_select_menu(|m| m
+            .custom_id(WIZARD_DURATION)
+            .options(|o| {
+                for (hours, label) in DURATIONS {
+                    o.create_option(|so| so
+                        .label(label)
+                        .value(hours)
+                        .default_selection(*hours == w.duration));
+                }
+                o
+            })));
+    }
+
+    c.create_action_row(|r| {
+        if w.options.is_empty() {
+            r.create_button(|b| b
+                .custom_id(WIZARD_OPTIONS)
+                .label("Enter options")
+                .style(ButtonStyle::Primary));
+        } else {
+            r.create_button(|b| b
+                .custom_id(WIZARD_CONFIRM)
+                .label("Create poll")
+                .style(ButtonStyle::Success));
+            r.create_button(|b| b
+                .custom_id(WIZARD_OPTIONS)
+                .label("Change options")
+                .style(ButtonStyle::Secondary));
+            r.create_button(|b| b
+                .custom_id(WIZARD_SECRET)
+                .label(if w.settings.secret { "Make ballots public" } else { "Make ballots secret" })
+                .style(ButtonStyle::Secondary));
+            if !w.roles.is_empty() {
+                r.create_button(|b| b
+                    .custom_id(WIZARD_ELIGIBILITY)
+                    .label("Who may vote")
+                    .style(ButtonStyle::Secondary));
+            }
+        }
+        r.create_button(|b| b
+            .custom_id(WIZARD_CANCEL)
+            .label("Cancel")
+            .style(ButtonStyle::Danger))
+    });
+
+    c
+}
+
+pub async fn poll_wizard(ctx: &Context, interaction: &ApplicationCommandInteraction, opt: &ApplicationCommandInteractionDataOption, data: &BotData, guild_id: &GuildId) -> anyhow::Result<()> {
+    if !super::poll::check_manager(ctx, interaction, data, guild_id, "/poll wizard").await? {
+        return Ok(());
+    }
+
+    let name = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "name").await?.unwrap();
+    let question = command_opt::find_required(&ctx, &interaction, &opt.options, command_opt::find_string_opt, "question").await?.unwrap();
+
+    let name = match validate::poll_name(&name) {
+        Ok(v) => v,
+        Err(e) => {
+            get_logger().info("Invalid name passed to /poll wizard.", meta! {
+                "InteractionID" => interaction.id,
+                "Name" => name,
+            });
+            command_resp::reply_deferred_result(&ctx, &interaction, e).await?;
+            return Ok(());
+        }
+    };
+
+    let server_has_poll_name = match db::model::check_server_has_poll_name(data.db_client.conn(), *guild_id.as_u64(), &name).await {
+        Ok(v) => v,
+        Err(e) => {
+            command_resp::reply_deferred_result(&ctx, &interaction, "Error occurred upon attempt to check for existing poll by name.").await?;
+            return Err(e);
+        }
+    };
+
+    if server_has_poll_name {
+        command_resp::reply_deferred_result(&ctx, &interaction, format!(
+            "A poll with the name `{}` has already been created in this server.", name
+        )).await?;
+        return Ok(());
+    }
+
+    let settings = db::model::get_guild_settings(data.db_client.conn(), *guild_id.as_u64()).await?;
+
+    // Bot and integration roles are left out, as is @everyone, which shares the server's ID.
+    let mut roles = guild_id.roles(&ctx.http).await?
+        .into_values()
+        .filter(|v| v.id.as_u64() != guild_id.as_u64() && !v.managed)
+        .collect::<Vec<_>>();
+    roles.sort_by_key(|v| -v.position);
+    let roles = roles.into_iter()
+        .take(MAX_ROLES)
+        .map(|v| (*v.id.as_u64(), v.name))
+        .collect::<Vec<(u64, String)>>();
+
+    let wizard = PollWizard {
+        id_server: *guild_id.as_u64(),
+        name,
+        question,
+        options: Vec::new(),
+        settings: settings.poll_settings(),
+        duration: 0,
+        roles,
+        choosing_roles: false,
+        interaction: interaction.clone(),
+        time_started: Utc::now(),
+    };
+
+    interaction.edit_original_interaction_response(&ctx.http, |r| {
+        r.content(wizard_content(&wizard));
+        r.components(|c| wizard_components(c, &wizard))
+    }).await?;
+
+    data.poll_wizards.insert(*interaction.user.id.as_u64(), wizard);
+
+    Ok(())
+}
+
+async fn update_wizard<F>(ctx: &Context, interaction: &MessageComponentInteraction, f: F) -> anyhow::Result<()>
+    where F: FnOnce(&mut PollWizard)
+{
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let id_user = *interaction.user.id.as_u64();
+
+    let wizard = match data.poll_wizards.get_mut(&id_user) {
+        Some(mut w) if !w.expired() => {
+            f(&mut w);
+            w.clamp();
+            Some(w.value().clone())
+        }
+        _ => None,
+    };
+
+    match wizard {
+        None => {
+            data.poll_wizards.remove(&id_user);
+            component_resp::update(ctx, interaction, |d| {
+                d.content("This poll wizard has expired; run /poll wizard again.");
+                d.components(|c| c)
+            }).await?;
+        }
+        Some(w) => {
+            component_resp::update(ctx, interaction, |d| {
+                d.content(wizard_content(&w));
+                d.components(|c| wizard_components(c, &w))
+            }).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn selected<T: std::str::FromStr>(interaction: &MessageComponentInteraction) -> Option<T> {
+    interaction.data.values.first().and_then(|v| v.parse::<T>().ok())
+}
+
+pub async fn wizard_method(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let method = interaction.data.values.first().and_then(|v| Method::parse(v));
+
+    update_wizard(&ctx, &interaction, |w| {
+        if let Some(v) = method {
+            w.settings.method = v;
+        }
+    }).await
+}
+
+pub async fn wizard_seats(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let seats = selected::<u8>(&interaction);
+
+    update_wizard(&ctx, &interaction, |w| {
+        if let Some(v) = seats {
+            w.settings.seats = v;
+        }
+    }).await
+}
+
+pub async fn wizard_ranks(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let ranks = selected::<u8>(&interaction);
+
+    update_wizard(&ctx, &interaction, |w| {
+        if let Some(v) = ranks {
+            w.settings.ranks = v;
+        }
+    }).await
+}
+
+pub async fn wizard_duration(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let duration = selected::<i64>(&interaction).filter(|v| DURATIONS.iter().any(|(h, _)| h == v));
+
+    update_wizard(&ctx, &interaction, |w| {
+        if let Some(v) = duration {
+            w.duration = v;
+        }
+    }).await
+}
+
+pub async fn wizard_secret(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    update_wizard(&ctx, &interaction, |w| w.settings.secret = !w.settings.secret).await
+}
+
+fn hours_list(hours: &[u32]) -> String {
+    hours.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(", ")
+}
+
+/// Opens a modal for entering the wizard's options, quorum and reminders, filled in with any
+/// already entered.
+pub async fn wizard_options(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let entered = {
+        let data = ctx.data.read().await;
+        let data = data.get::<BotData>().unwrap();
+
+        data.poll_wizards.get(interaction.user.id.as_u64())
+            .filter(|w| !w.expired())
+            .map(|w| {
+                let quorum = match w.settings.quorum {
+                    None => String::new(),
+                    Some(Quorum::Ballots(v)) => v.to_string(),
+                    Some(Quorum::Percent(v)) => format!("{}%", v),
+                };
+                (w.options.join("\n"), quorum, hours_list(&w.settings.reminders.hours))
+            })
+    };
+
+    let (options, quorum, reminders) = match entered {
+        None => return update_wizard(&ctx, &interaction, |_| {}).await,
+        Some(v) => v,
+    };
+
+    interaction.create_interaction_response(&ctx, |r| {
+        r.kind(InteractionResponseType::Modal);
+        r.interaction_response_data(|d| d
+            .custom_id(WIZARD_OPTIONS_MODAL)
+            .title("Poll options")
+            .components(|c| {
+                c.create_action_row(|row| row.create_input_text(|t| {
+                    t.custom_id(WIZARD_OPTIONS)
+                        .label("One option per line")
+                        .placeholder("Name | description | link | emoji")
+                        .style(InputTextStyle::Paragraph)
+                        .max_length(MAX_OPTIONS_TEXT_LEN)
+                        .required(true);
+                    if !options.is_empty() {
+                        t.value(&options);
+                    }
+                    t
+                }));
+                c.create_action_row(|row| row.create_input_text(|t| {
+                    t.custom_id(INPUT_QUORUM)
+                        .label("Quorum (optional)")
+                        .placeholder("A number of ballots, such as 10, or a percentage, such as 25%")
+                        .style(InputTextStyle::Short)
+                        .max_length(8)
+                        .required(false);
+                    if !quorum.is_empty() {
+                        t.value(&quorum);
+                    }
+                    t
+                }));
+                c.create_action_row(|row| row.create_input_text(|t| {
+                    t.custom_id(INPUT_REMINDERS)
+                        .label("Reminders (optional)")
+                        .placeholder("Hours before the deadline to remind members who have not voted, such as 24, 1")
+                        .style(InputTextStyle::Short)
+                        .max_length(40)
+                        .required(false);
+                    if !reminders.is_empty() {
+                        t.value(&reminders);
+                    }
+                    t
+                }))
+            }));
+        r
+    }).await?;
+
+    Ok(())
+}
+
+// The value entered in a modal's text input.
+fn input_value(interaction: &ModalSubmitInteraction, custom_id: &str) -> String {
+    interaction.data.components.iter()
+        .flat_map(|row| row.components.iter())
+        .find_map(|v| match v {
+            ActionRowComponent::InputText(v) if v.custom_id == custom_id => Some(v.value.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Takes a wizard's options, quorum and reminders from the modal opened by `wizard_options`.
+pub async fn wizard_options_submit(ctx: Context, interaction: ModalSubmitInteraction) -> anyhow::Result<()> {
+    let lines = input_value(&interaction, WIZARD_OPTIONS).lines()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_owned())
+        .collect::<Vec<String>>();
+
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let id_user = *interaction.user.id.as_u64();
+
+    // The lines are kept as written and parsed again when the poll is created.
+    let checked = validate::options(lines.clone())
+        .and_then(|_| validate::quorum_text(&input_value(&interaction, INPUT_QUORUM)))
+        .and_then(|q| validate::reminder_hours(&input_value(&interaction, INPUT_REMINDERS)).map(|r| (q, r)));
+
+    let ((quorum, reminders), problem) = match checked {
+        Ok(v) => (v, None),
+        Err(e) => ((None, Vec::new()), Some(e)),
+    };
+
+    let wizard = match data.poll_wizards.get_mut(&id_user) {
+        Some(mut w) if !w.expired() => {
+            if problem.is_none() {
+                w.options = lines;
+                w.settings.quorum = quorum;
+                w.settings.reminders.hours = reminders;
+                w.clamp();
+            }
+            Some(w.value().clone())
+        }
+        _ => None,
+    };
+
+    if wizard.is_none() {
+        data.poll_wizards.remove(&id_user);
+    }
+
+    let content = match (&wizard, problem) {
+        (None, _) => "This poll wizard has expired; run /poll wizard again.".to_owned(),
+        (Some(w), None) => wizard_content(w),
+        (Some(w), Some(e)) => format!("{}\n\n{}", e, wizard_content(w)),
+    };
+
+    interaction.create_interaction_response(&ctx, |r| {
+        r.kind(InteractionResponseType::UpdateMessage);
+        r.interaction_response_data(|d| {
+            d.content(content);
+            match &wizard {
+                None => d.components(|c| c),
+                Some(w) => d.components(|c| wizard_components(c, w)),
+            }
+        });
+        r
+    }).await?;
+
+    Ok(())
+}
+
+/// Switches between choosing who may vote and the other settings.
+pub async fn wizard_eligibility(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    update_wizard(&ctx, &interaction, |w| w.choosing_roles = !w.choosing_roles).await
+}
+
+pub async fn wizard_roles(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let roles = interaction.data.values.iter()
+        .filter_map(|v| v.parse::<u64>().ok())
+        .collect::<Vec<u64>>();
+
+    update_wizard(&ctx, &interaction, |w| {
+        w.settings.eligibility.required_roles = roles.into_iter()
+            .filter(|v| w.roles.iter().any(|(id, _)| id == v))
+            .collect();
+    }).await
+}
+
+pub async fn wizard_cancel(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    data.poll_wizards.remove(interaction.user.id.as_u64());
+
+    component_resp::update(&ctx, &interaction, |d| {
+        d.content("Poll wizard cancelled.");
+        d.components(|c| c)
+    }).await?;
+
+    Ok(())
+}
+
+pub async fn wizard_confirm(ctx: Context, interaction: MessageComponentInteraction) -> anyhow::Result<()> {
+    let data = ctx.data.read().await;
+    let data = data.get::<BotData>().unwrap();
+
+    let id_user = *interaction.user.id.as_u64();
+
+    let wizard = match data.poll_wizards.get(&id_user) {
+        Some(w) if !w.expired() => w.value().clone(),
+        _ => {
+            data.poll_wizards.remove(&id_user);
+            component_resp::update(&ctx, &interaction, |d| {
+                d.content("This poll wizard has expired; run /poll wizard again.");
+                d.components(|c| c)
+            }).await?;
+            return Ok(());
+        }
+    };
+
+    // Options and settings were validated as they were entered, but the name may have been taken
+    // since the wizard started.
+    let checked = validate::options(wizard.options.clone())
+        .and_then(|opts| validate::seats(wizard.settings.method, wizard.settings.seats as i64, opts.len()).map(|_| opts));
+
+    let opts = match checked {
+        Ok(v) => v,
+        Err(e) => {
+            component_resp::reply_ephemeral(&ctx, &interaction, |d| d.content(e)).await?;
+            return Ok(());
+        }
+    };
+
+    let server_has_poll_name = match db::model::check_server_has_poll_name(data.db_client.conn(), wizard.id_server, &wizard.name).await {
+        Ok(v) => v,
+        Err(e) => {
+            component_resp::reply_ephemeral(&ctx, &interaction, |d| d.content("Error occurred upon attempt to check for existing poll by name.")).await?;
+            return Err(e);
+        }
+    };
+
+    if server_has_poll_name {
+        component_resp::reply_ephemeral(&ctx, &interaction, |d| d.content(format!(
+            "A poll with the name `{}` has already been created in this server.", wizard.name
+        ))).await?;
+        return Ok(());
+    }
+
+    let time_close = match wizard.duration {
+        0 => None,
+        v => validate::deadline(v).ok(),
+    };
+
+    let poll = match db::model::add_poll(
+        data.db_client.conn(),
+        wizard.id_server,
+        id_user,
+        &wizard.name,
+        &wizard.question,
+        &wizard.settings,
+        time_close,
+        None,
+        &opts,
+    ).await {
+        Ok(v) => v,
+        Err(e) => {
+            component_resp::reply_ephemeral(&ctx, &interaction, |d| d.content("Error occurred upon attempt to add poll to database.")).await?;
+            return Err(e);
+        }
+    };
+
+    data.poll_wizards.remove(&id_user);
+
+    let settings = db::model::get_guild_settings(data.db_client.conn(), poll.id_server).await?;
+    let channel = settings.id_announce_channel.map_or(interaction.channel_id, ChannelId);
+    let announced = poll_status::announce(&ctx.http, data.db_client.conn(), &poll, channel).await;
+
+    component_resp::update(&ctx, &interaction, |d| {
+        d.content(super::poll::created_message(&poll, channel, announced));
+        d.components(|c| c)
+    }).await?;
+
+    Ok(())
+}
@@ -1,41 +1,57 @@
-use sqlx::{PgPool, query};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction, query};
 use tokio_stream::StreamExt;
 
-use crate::db::schema::{Ballot, BallotChoice, Poll, PollOption};
+use crate::db::schema::{Ballot, BallotChoice, Choice, Eligibility, GuildSettings, Method, Nomination, Nominations, OptionDetails, Poll, PollGroup, PollOption, PollRecurrence, PollSettings, PollTemplate, Quorum, Reminders, Runoff, Visibility, Voter};
+
+/// Loads a server's settings, or the defaults if they were never changed.
+pub async fn get_guild_settings(conn: &PgPool, id_server: u64) -> anyhow::Result<GuildSettings> {
+    let r = query!("SELECT * FROM guild_settings WHERE id_server=$1", id_server.to_string())
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(match r {
+        None => GuildSettings::default(),
+        Some(r) => GuildSettings {
+            id_manager_role: r.id_manager_role.map(|v| v.parse::<u64>().unwrap()),
+            results: Visibility::parse(&r.results).unwrap(),
+            method: Method::parse(&r.method).unwrap(),
+            ranks: r.ranks as u8,
+            secret: r.secret,
+            id_announce_channel: r.id_announce_channel.map(|v| v.parse::<u64>().unwrap()),
+            locale: r.locale,
+        },
+    })
+}
+
+pub async fn set_guild_settings(conn: &PgPool, id_server: u64, settings: &GuildSettings) -> anyhow::Result<()> {
+    query!(
+        "INSERT INTO guild_settings (id_server, id_manager_role, results, method, ranks, secret, id_announce_channel, locale)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         ON CONFLICT ON CONSTRAINT guild_settings_pk
+         DO UPDATE SET id_manager_role=$2, results=$3, method=$4, ranks=$5, secret=$6, id_announce_channel=$7, locale=$8;",
+        id_server.to_string(), settings.id_manager_role.map(|v| v.to_string()), settings.results.as_str(),
+        settings.method.as_str(), settings.ranks as i32, settings.secret,
+        settings.id_announce_channel.map(|v| v.to_string()), settings.locale)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
 
 pub async fn list_open_polls(
     conn: &PgPool,
     id_server: u64,
 ) -> anyhow::Result<Vec<Poll>> {
-    let mut stream = query!("SELECT * FROM poll WHERE id_server=$1 AND open=TRUE;", id_server.to_string())
-        .map(|r| Poll {
-            id: r.id,
-            time_created: r.time_created,
-            id_server: r.id_server.parse::<u64>().unwrap(),
-            id_created_by: r.id_created_by.parse::<u64>().unwrap(),
-            open: r.open,
-            name: r.name,
-            question: r.question,
-            ranks: r.ranks as u8,
-            options: Vec::new(),
-        })
-        .fetch(conn);
+    let ids = query!("SELECT id FROM poll WHERE id_server=$1 AND open=TRUE ORDER BY id;", id_server.to_string())
+        .fetch_all(conn)
+        .await?;
 
     let mut result = Vec::new();
-    while let Some(mut row) = stream.try_next().await? {
-        let mut stream = query!("SELECT * FROM poll_option WHERE id_poll=$1", row.id)
-            .map(|r| PollOption {
-                id_poll: r.id_poll,
-                id: r.id,
-                option: r.option.to_owned(),
-            })
-            .fetch(conn);
-
-        while let Some(opt) = stream.try_next().await? {
-            row.options.push(opt);
+    for r in ids {
+        if let Some(poll) = get_poll(conn, r.id).await? {
+            result.push(poll);
         }
-
-        result.push(row);
     }
 
     Ok(result)
@@ -49,8 +65,8 @@ pub async fn check_server_has_poll_name(conn: &PgPool, id_server: u64, name: &st
     Ok(r.known.unwrap())
 }
 
-pub async fn get_server_poll(conn: &PgPool, id_server: u64, name: &str) -> anyhow::Result<Option<Poll>> {
-    let r = query!("SELECT * FROM poll WHERE id_server=$1 AND name=$2", id_server.to_string(), name)
+pub async fn get_poll(conn: &PgPool, id: i32) -> anyhow::Result<Option<Poll>> {
+    let r = query!("SELECT * FROM poll WHERE id=$1", id)
         .fetch_optional(conn)
         .await?;
 
@@ -59,12 +75,17 @@ pub async fn get_server_poll(conn: &PgPool, id_server: u64, name: &str) -> anyho
         Some(v) => v,
     };
 
-    let mut options = query!("SELECT * FROM poll_option WHERE id_poll = $1", r.id)
+    let mut options = query!("SELECT * FROM poll_option WHERE id_poll = $1 ORDER BY id", r.id)
         .map(|row| {
             PollOption {
                 id_poll: row.id_poll,
                 id: row.id,
                 option: row.option,
+                details: OptionDetails {
+                    description: row.description,
+                    url: row.url,
+                    emoji: row.emoji,
+                },
             }
         })
         .fetch(conn);
@@ -74,53 +95,135 @@ pub async fn get_server_poll(conn: &PgPool, id_server: u64, name: &str) -> anyho
         opt_result.push(row);
     }
 
+    let roles = query!("SELECT id_role, excluded FROM poll_role WHERE id_poll = $1", r.id)
+        .fetch_all(conn)
+        .await?;
+
+    let mut eligibility = Eligibility {
+        min_member_days: r.min_member_days.map(|v| v as u32),
+        min_account_days: r.min_account_days.map(|v| v as u32),
+        ..Eligibility::default()
+    };
+    for role in roles {
+        match role.excluded {
+            true => eligibility.excluded_roles.push(role.id_role.parse::<u64>().unwrap()),
+            false => eligibility.required_roles.push(role.id_role.parse::<u64>().unwrap()),
+        }
+    }
+
+    let role_weights = query!("SELECT id_role, weight FROM poll_weight_role WHERE id_poll = $1 ORDER BY weight DESC", r.id)
+        .fetch_all(conn)
+        .await?
+        .into_iter()
+        .map(|v| (v.id_role.parse::<u64>().unwrap(), v.weight as u32))
+        .collect();
+
     Ok(Some(Poll {
         id: r.id,
         time_created: r.time_created,
-        id_server: id_server,
+        id_server: r.id_server.parse::<u64>().unwrap(),
         id_created_by: r.id_created_by.parse::<u64>().unwrap(),
         open: r.open,
         name: r.name,
         question: r.question,
-        ranks: r.ranks as u8,
+        settings: PollSettings {
+            ranks: r.ranks as u8,
+            method: Method::parse(&r.method).unwrap(),
+            seats: r.seats as u8,
+            runoff: Runoff {
+                candidates: r.runoff_candidates as u8,
+                hours: r.runoff_hours.map(|v| v as u32),
+            },
+            secret: r.secret,
+            write_ins: r.write_ins,
+            reminders: Reminders {
+                hours: r.reminder_hours.iter().map(|v| *v as u32).collect(),
+                dm: r.reminder_dm,
+            },
+            results: Visibility::parse(&r.results).unwrap(),
+            eligibility,
+            role_weights,
+            quorum: match (r.quorum_ballots, r.quorum_percent) {
+                (Some(v), _) => Some(Quorum::Ballots(v as u32)),
+                (None, Some(v)) => Some(Quorum::Percent(v as u8)),
+                (None, None) => None,
+            },
+        },
+        time_close: r.time_close,
+        nominations: match r.nominating {
+            false => None,
+            true => Some(Nominations {
+                seconds: r.nomination_seconds as u32,
+                time_close: r.time_nominations_close,
+                voting_hours: r.voting_hours.map(|v| v as u32),
+            }),
+        },
+        time_roll_cutoff: r.time_roll_cutoff,
+        time_roll_frozen: r.time_roll_frozen,
+        id_channel: r.id_channel.map(|v| v.parse::<u64>().unwrap()),
+        id_message: r.id_message.map(|v| v.parse::<u64>().unwrap()),
+        id_group: r.id_group,
+        id_runoff_of: r.id_runoff_of,
         options: opt_result,
     }))
 }
 
+pub async fn get_server_poll(conn: &PgPool, id_server: u64, name: &str) -> anyhow::Result<Option<Poll>> {
+    let r = query!("SELECT id FROM poll WHERE id_server=$1 AND name=$2", id_server.to_string(), name)
+        .fetch_optional(conn)
+        .await?;
+
+    match r {
+        None => Ok(None),
+        Some(v) => get_poll(conn, v.id).await,
+    }
+}
+
 pub async fn add_poll(
     conn: &PgPool,
     id_server: u64,
     id_created_by: u64,
     name: &str,
     question: &str,
-    ranks: u8,
-    options: &[String],
+    settings: &PollSettings,
+    time_close: Option<DateTime<Utc>>,
+    nominations: Option<&Nominations>,
+    options: &[(String, OptionDetails)],
 ) -> anyhow::Result<Poll> {
     let mut tx = conn.begin().await?;
 
+    let (quorum_ballots, quorum_percent) = quorum_columns(settings.quorum);
+
     let r = query!(
-        "INSERT INTO poll (time_created, id_server, id_created_by, open, name, question, ranks)
-         VALUES (NOW(), $1, $2, TRUE, $3, $4, $5)
+        "INSERT INTO poll (time_created, id_server, id_created_by, open, name, question, ranks, method, seats, secret,
+                           min_member_days, min_account_days, quorum_ballots, quorum_percent, time_close,
+                           nominating, nomination_seconds, time_nominations_close, voting_hours, write_ins,
+                           runoff_candidates, runoff_hours, reminder_hours, reminder_dm, results)
+         VALUES (NOW(), $1, $2, TRUE, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23)
          RETURNING id, time_created;",
-        id_server.to_string(), id_created_by.to_string(), name, question, ranks as i32)
+        id_server.to_string(), id_created_by.to_string(), name, question,
+        settings.ranks as i32, settings.method.as_str(), settings.seats as i32, settings.secret,
+        settings.eligibility.min_member_days.map(|v| v as i32), settings.eligibility.min_account_days.map(|v| v as i32),
+        quorum_ballots, quorum_percent, time_close,
+        nominations.is_some(), nominations.map_or(0, |v| v.seconds as i32),
+        nominations.and_then(|v| v.time_close), nominations.and_then(|v| v.voting_hours.map(|v| v as i32)),
+        settings.write_ins, settings.runoff.candidates as i32, settings.runoff.hours.map(|v| v as i32),
+        &reminder_hours(&settings.reminders), settings.reminders.dm, settings.results.as_str())
         .fetch_one(&mut tx)
         .await?;
 
+    add_poll_roles(&mut tx, r.id, settings).await?;
+
     let mut opt_result = Vec::new();
 
-    for option in options {
-        let option_r = query!(
-            "INSERT INTO poll_option (id_poll, option)
-             VALUES ($1, $2)
-             RETURNING id;",
-            r.id, option)
-            .fetch_one(&mut tx)
-            .await?;
+    for (option, details) in options {
+        let id = add_poll_option(&mut tx, r.id, option, details).await?;
 
         opt_result.push(PollOption {
             id_poll: r.id,
-            id: option_r.id,
+            id,
             option: option.to_string(),
+            details: details.clone(),
         });
     }
 
@@ -134,141 +237,1181 @@ pub async fn add_poll(
         open: true,
         name: name.to_owned(),
         question: question.to_owned(),
-        ranks: ranks,
+        settings: settings.clone(),
+        time_close: time_close,
+        nominations: nominations.cloned(),
+        time_roll_cutoff: None,
+        time_roll_frozen: None,
+        id_channel: None,
+        id_message: None,
+        id_group: None,
+        id_runoff_of: None,
         options: opt_result,
     })
 }
 
-pub async fn close_poll(
-    conn: &PgPool,
-    id_server: u64,
-    id_created_by: u64,
-    name: &str,
-) -> anyhow::Result<bool> {
+async fn add_poll_option(tx: &mut Transaction<'_, Postgres>, id_poll: i32, option: &str, details: &OptionDetails) -> anyhow::Result<i32> {
     let r = query!(
-        "UPDATE poll SET open=FALSE WHERE id_server=$1 AND id_created_by=$2 AND name=$3 AND open=TRUE;",
-        id_server.to_string(), id_created_by.to_string(), name)
-        .execute(conn)
+        "INSERT INTO poll_option (id_poll, option, description, url, emoji)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id;",
+        id_poll, option, details.description, details.url, details.emoji)
+        .fetch_one(&mut *tx)
         .await?;
 
-    Ok(r.rows_affected() > 0)
+    Ok(r.id)
 }
 
-pub async fn add_ballot(
-    conn: &PgPool,
-    id_poll: i32,
-    id_user: u64,
-    choices: &[(i32, u8)],
-) -> anyhow::Result<()> {
-    let tx = conn.begin().await?;
+// Splits a quorum into the (quorum_ballots, quorum_percent) columns of the poll table.
+fn quorum_columns(quorum: Option<Quorum>) -> (Option<i32>, Option<i32>) {
+    match quorum {
+        None => (None, None),
+        Some(Quorum::Ballots(v)) => (Some(v as i32), None),
+        Some(Quorum::Percent(v)) => (None, Some(v as i32)),
+    }
+}
 
-    let ballot = query!(
-        "INSERT INTO ballot (id_poll, id_user, time_created, invalidated)
-         VALUES ($1, $2, NOW(), FALSE)
-         RETURNING id;",
-        id_poll, id_user.to_string())
-        .fetch_one(conn)
-        .await?;
+// Converts reminder lead times to the reminder_hours column of the poll table.
+fn reminder_hours(reminders: &Reminders) -> Vec<i32> {
+    reminders.hours.iter().map(|v| *v as i32).collect()
+}
 
-    for choice in choices {
-        query!(
-            "INSERT INTO ballot_choice (id_ballot, id_option, rank)
-             VALUES ($1, $2, $3);",
-            ballot.id, choice.0, choice.1 as i32)
-            .execute(conn)
+// Inserts the role-based eligibility rules and weights of a poll's settings.
+async fn add_poll_roles(tx: &mut Transaction<'_, Postgres>, id_poll: i32, settings: &PollSettings) -> anyhow::Result<()> {
+    let roles = settings.eligibility.required_roles.iter().map(|v| (v, false))
+        .chain(settings.eligibility.excluded_roles.iter().map(|v| (v, true)));
+
+    for (id_role, excluded) in roles {
+        query!("INSERT INTO poll_role (id_poll, id_role, excluded) VALUES ($1, $2, $3);", id_poll, id_role.to_string(), excluded)
+            .execute(&mut *tx)
             .await?;
     }
 
+    for (id_role, weight) in &settings.role_weights {
+        query!("INSERT INTO poll_weight_role (id_poll, id_role, weight) VALUES ($1, $2, $3);", id_poll, id_role.to_string(), *weight as i32)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn update_poll_settings(conn: &PgPool, id_poll: i32, settings: &PollSettings) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    let (quorum_ballots, quorum_percent) = quorum_columns(settings.quorum);
+
+    query!(
+        "UPDATE poll SET ranks=$2, method=$3, seats=$4, secret=$5, min_member_days=$6, min_account_days=$7,
+                         quorum_ballots=$8, quorum_percent=$9, write_ins=$10, runoff_candidates=$11, runoff_hours=$12,
+                         reminder_hours=$13, reminder_dm=$14, results=$15
+         WHERE id=$1;",
+        id_poll, settings.ranks as i32, settings.method.as_str(), settings.seats as i32, settings.secret,
+        settings.eligibility.min_member_days.map(|v| v as i32), settings.eligibility.min_account_days.map(|v| v as i32),
+        quorum_ballots, quorum_percent, settings.write_ins,
+        settings.runoff.candidates as i32, settings.runoff.hours.map(|v| v as i32),
+        &reminder_hours(&settings.reminders), settings.reminders.dm, settings.results.as_str())
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM poll_role WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM poll_weight_role WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    add_poll_roles(&mut tx, id_poll, settings).await?;
+
     tx.commit().await?;
 
     Ok(())
 }
 
-pub async fn get_valid_ballot(
-    conn: &PgPool,
-    id_poll: i32,
-    id_user: u64,
-) -> anyhow::Result<Option<Ballot>> {
-    let tx = conn.begin().await?;
+/// Sets a poll's deadline and forgets which reminders were sent for the old one.
+pub async fn set_poll_deadline(conn: &PgPool, id_poll: i32, time_close: Option<DateTime<Utc>>) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
 
-    let ballot = query!("SELECT * FROM ballot WHERE id_poll=$1 AND id_user=$2 AND invalidated=FALSE;", id_poll, id_user.to_string())
-        .fetch_optional(conn)
+    query!("UPDATE poll SET time_close=$2 WHERE id=$1;", id_poll, time_close)
+        .execute(&mut tx)
         .await?;
 
-    let ballot = match ballot {
-        None => return Ok(None),
-        Some(v) => v,
-    };
+    query!("DELETE FROM reminder_sent WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
 
-    let mut r = Ballot {
-        id: ballot.id,
-        id_poll: ballot.id_poll,
-        id_user: ballot.id_user.parse::<u64>().unwrap(),
-        time_created: ballot.time_created,
-        invalidated: ballot.invalidated,
-        choices: Vec::new(),
-    };
+    tx.commit().await?;
 
-    let mut choices = query!("SELECT * FROM ballot_choice WHERE id_ballot=$1;", ballot.id)
-        .map(|row| BallotChoice {
-            id_ballot: row.id_ballot,
-            id_option: row.id_option,
-            rank: row.rank as u8,
-        })
-        .fetch(conn);
+    Ok(())
+}
 
-    for choice in choices.try_next().await? {
-        r.choices.push(choice);
+pub async fn set_poll_message(conn: &PgPool, id_poll: i32, id_channel: u64, id_message: u64) -> anyhow::Result<()> {
+    query!("UPDATE poll SET id_channel=$2, id_message=$3 WHERE id=$1;", id_poll, id_channel.to_string(), id_message.to_string())
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Closes every open poll whose deadline has passed and returns the (poll ID, server ID) of each.
+pub async fn close_expired_polls(conn: &PgPool) -> anyhow::Result<Vec<(i32, u64)>> {
+    let r = query!("UPDATE poll SET open=FALSE WHERE open=TRUE AND time_close <= NOW() RETURNING id, id_server;")
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|v| (v.id, v.id_server.parse::<u64>().unwrap())).collect())
+}
+
+/// Records a member's support for an option on a poll in its nomination phase; returns false if
+/// they already supported it.
+pub async fn add_nomination(conn: &PgPool, id_poll: i32, option: &str, id_user: u64) -> anyhow::Result<bool> {
+    let r = query!(
+        "INSERT INTO nomination (id_poll, option, id_user, time_created)
+         VALUES ($1, $2, $3, NOW())
+         ON CONFLICT DO NOTHING;",
+        id_poll, option, id_user.to_string())
+        .execute(conn)
+        .await?;
+
+    Ok(r.rows_affected() > 0)
+}
+
+/// Lists a poll's nominations in the order they were first made.
+pub async fn get_nominations(conn: &PgPool, id_poll: i32) -> anyhow::Result<Vec<Nomination>> {
+    let rows = query!("SELECT option, id_user FROM nomination WHERE id_poll=$1 ORDER BY time_created;", id_poll)
+        .fetch_all(conn)
+        .await?;
+
+    let mut result: Vec<Nomination> = Vec::new();
+    for row in rows {
+        let id_user = row.id_user.parse::<u64>().unwrap();
+
+        match result.iter_mut().find(|v| v.option == row.option) {
+            Some(v) => v.id_seconded_by.push(id_user),
+            None => result.push(Nomination {
+                option: row.option,
+                id_nominated_by: id_user,
+                id_seconded_by: Vec::new(),
+            }),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Ends a poll's nomination phase: adds `options` to it and opens voting until `time_close`.
+pub async fn start_voting(conn: &PgPool, id_poll: i32, options: &[(String, OptionDetails)], time_close: Option<DateTime<Utc>>) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    for (option, details) in options {
+        add_poll_option(&mut tx, id_poll, option, details).await?;
     }
 
+    query!(
+        "UPDATE poll SET nominating=FALSE, time_nominations_close=NULL, time_close=$2 WHERE id=$1;",
+        id_poll, time_close)
+        .execute(&mut tx)
+        .await?;
+
     tx.commit().await?;
 
-    Ok(Some(r))
+    Ok(())
 }
 
-pub async fn get_valid_ballots(
+/// Lists the open polls whose nomination phase has reached its scheduled close.
+pub async fn list_due_nominations(conn: &PgPool) -> anyhow::Result<Vec<i32>> {
+    let r = query!("SELECT id FROM poll WHERE open=TRUE AND nominating=TRUE AND time_nominations_close <= NOW();")
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|v| v.id).collect())
+}
+
+/// Closes a poll by ID, whoever created it; returns false if it was already closed.
+pub async fn close_poll_by_id(conn: &PgPool, id_poll: i32) -> anyhow::Result<bool> {
+    let r = query!("UPDATE poll SET open=FALSE WHERE id=$1 AND open=TRUE;", id_poll)
+        .execute(conn)
+        .await?;
+
+    Ok(r.rows_affected() > 0)
+}
+
+/// Deletes a poll along with its options, ballots and everything else recorded for it. Runoffs and
+/// recurrences that refer to the poll are kept but no longer point at it.
+pub async fn delete_poll(conn: &PgPool, id_poll: i32) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    query!("DELETE FROM ballot_choice WHERE id_ballot IN (SELECT id FROM ballot WHERE id_poll=$1);", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM ballot WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM voter WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM voter_roll WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM delegation WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM reminder_sent WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM nomination WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM poll_role WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM poll_weight_role WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM poll_weight_user WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM poll_option WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("UPDATE poll SET id_runoff_of=NULL WHERE id_runoff_of=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("UPDATE poll_recurrence SET id_last_poll=NULL WHERE id_last_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM poll WHERE id=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Marks a poll as the runoff of poll `id_runoff_of`.
+pub async fn set_runoff_of(conn: &PgPool, id_poll: i32, id_runoff_of: i32) -> anyhow::Result<()> {
+    query!("UPDATE poll SET id_runoff_of=$2 WHERE id=$1;", id_poll, id_runoff_of)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Finds the runoff held for a poll that had no majority, if there is one.
+pub async fn get_runoff(conn: &PgPool, id_poll: i32) -> anyhow::Result<Option<Poll>> {
+    let r = query!("SELECT id FROM poll WHERE id_runoff_of=$1", id_poll)
+        .fetch_optional(conn)
+        .await?;
+
+    match r {
+        None => Ok(None),
+        Some(v) => get_poll(conn, v.id).await,
+    }
+}
+
+/// Saves a poll definition as a template, replacing any template of the same name in the server.
+pub async fn save_template(
     conn: &PgPool,
-    id_poll: i32,
-) -> anyhow::Result<Vec<Ballot>> {
-    let tx = conn.begin().await?;
+    id_server: u64,
+    id_created_by: u64,
+    name: &str,
+    question: &str,
+    settings: &PollSettings,
+    duration_hours: Option<u32>,
+    options: &[(String, OptionDetails)],
+) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
 
-    let mut ballots = query!("SELECT * FROM ballot WHERE id_poll=$1 AND invalidated=FALSE;", id_poll)
-        .map(|row| {
-            Ballot {
-                id: row.id,
-                id_poll: row.id_poll,
-                id_user: row.id_user.parse::<u64>().unwrap(),
-                time_created: row.time_created,
-                invalidated: row.invalidated,
-                choices: Vec::new(),
-            }
-        })
-        .fetch(conn);
+    query!("DELETE FROM poll_template WHERE id_server=$1 AND name=$2;", id_server.to_string(), name)
+        .execute(&mut tx)
+        .await?;
 
-    let mut ballots_r = Vec::new();
-    while let Some(mut row) = ballots.try_next().await? {
-        let mut choices = query!("SELECT * FROM ballot_choice WHERE id_ballot=$1;", row.id)
-            .map(|row| BallotChoice {
-                id_ballot: row.id_ballot,
-                id_option: row.id_option,
-                rank: row.rank as u8,
-            })
-            .fetch(conn);
+    let (quorum_ballots, quorum_percent) = quorum_columns(settings.quorum);
 
-        while let Some(choice) = choices.try_next().await? {
-            row.choices.push(choice);
-        }
+    let r = query!(
+        "INSERT INTO poll_template (time_created, id_server, id_created_by, name, question, ranks, method, seats, secret, write_ins,
+                                    min_member_days, min_account_days, quorum_ballots, quorum_percent,
+                                    runoff_candidates, runoff_hours, reminder_hours, reminder_dm, results, duration_hours)
+         VALUES (NOW(), $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
+         RETURNING id;",
+        id_server.to_string(), id_created_by.to_string(), name, question,
+        settings.ranks as i32, settings.method.as_str(), settings.seats as i32, settings.secret, settings.write_ins,
+        settings.eligibility.min_member_days.map(|v| v as i32), settings.eligibility.min_account_days.map(|v| v as i32),
+        quorum_ballots, quorum_percent,
+        settings.runoff.candidates as i32, settings.runoff.hours.map(|v| v as i32),
+        &reminder_hours(&settings.reminders), settings.reminders.dm, settings.results.as_str(),
+        duration_hours.map(|v| v as i32))
+        .fetch_one(&mut tx)
+        .await?;
 
-        ballots_r.push(row);
+    for (option, details) in options {
+        query!(
+            "INSERT INTO poll_template_option (id_template, option, description, url, emoji)
+             VALUES ($1, $2, $3, $4, $5);",
+            r.id, option, details.description, details.url, details.emoji)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    let roles = settings.eligibility.required_roles.iter().map(|v| (v, false))
+        .chain(settings.eligibility.excluded_roles.iter().map(|v| (v, true)));
+
+    for (id_role, excluded) in roles {
+        query!("INSERT INTO poll_template_role (id_template, id_role, excluded) VALUES ($1, $2, $3);", r.id, id_role.to_string(), excluded)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    for (id_role, weight) in &settings.role_weights {
+        query!("INSERT INTO poll_template_weight_role (id_template, id_role, weight) VALUES ($1, $2, $3);", r.id, id_role.to_string(), *weight as i32)
+            .execute(&mut tx)
+            .await?;
     }
 
     tx.commit().await?;
 
-    Ok(ballots_r)
+    Ok(())
 }
 
-pub async fn invalidate_ballot(conn: &PgPool, id_ballot: i32) -> anyhow::Result<()> {
-    query!("UPDATE ballot SET invalidated=TRUE WHERE id=$1;", id_ballot).execute(conn).await?;
+pub async fn get_server_template(conn: &PgPool, id_server: u64, name: &str) -> anyhow::Result<Option<PollTemplate>> {
+    let r = query!("SELECT * FROM poll_template WHERE id_server=$1 AND name=$2", id_server.to_string(), name)
+        .fetch_optional(conn)
+        .await?;
 
-    Ok(())
+    let r = match r {
+        None => return Ok(None),
+        Some(v) => v,
+    };
+
+    let options = query!("SELECT * FROM poll_template_option WHERE id_template=$1 ORDER BY id", r.id)
+        .fetch_all(conn)
+        .await?
+        .into_iter()
+        .map(|row| (row.option, OptionDetails {
+            description: row.description,
+            url: row.url,
+            emoji: row.emoji,
+        }))
+        .collect();
+
+    let roles = query!("SELECT id_role, excluded FROM poll_template_role WHERE id_template=$1", r.id)
+        .fetch_all(conn)
+        .await?;
+
+    let mut eligibility = Eligibility {
+        min_member_days: r.min_member_days.map(|v| v as u32),
+        min_account_days: r.min_account_days.map(|v| v as u32),
+        ..Eligibility::default()
+    };
+    for role in roles {
+        match role.excluded {
+            true => eligibility.excluded_roles.push(role.id_role.parse::<u64>().unwrap()),
+            false => eligibility.required_roles.push(role.id_role.parse::<u64>().unwrap()),
+        }
+    }
+
+    let role_weights = query!("SELECT id_role, weight FROM poll_template_weight_role WHERE id_template=$1 ORDER BY weight DESC", r.id)
+        .fetch_all(conn)
+        .await?
+        .into_iter()
+        .map(|v| (v.id_role.parse::<u64>().unwrap(), v.weight as u32))
+        .collect();
+
+    Ok(Some(PollTemplate {
+        id: r.id,
+        id_server: r.id_server.parse::<u64>().unwrap(),
+        id_created_by: r.id_created_by.parse::<u64>().unwrap(),
+        name: r.name,
+        question: r.question,
+        settings: PollSettings {
+            ranks: r.ranks as u8,
+            method: Method::parse(&r.method).unwrap(),
+            seats: r.seats as u8,
+            runoff: Runoff {
+                candidates: r.runoff_candidates as u8,
+                hours: r.runoff_hours.map(|v| v as u32),
+            },
+            secret: r.secret,
+            write_ins: r.write_ins,
+            reminders: Reminders {
+                hours: r.reminder_hours.iter().map(|v| *v as u32).collect(),
+                dm: r.reminder_dm,
+            },
+            results: Visibility::parse(&r.results).unwrap(),
+            eligibility,
+            role_weights,
+            quorum: match (r.quorum_ballots, r.quorum_percent) {
+                (Some(v), _) => Some(Quorum::Ballots(v as u32)),
+                (None, Some(v)) => Some(Quorum::Percent(v as u8)),
+                (None, None) => None,
+            },
+        },
+        duration_hours: r.duration_hours.map(|v| v as u32),
+        options,
+    }))
+}
+
+/// Lists the names of a server's templates, alphabetically.
+pub async fn list_templates(conn: &PgPool, id_server: u64) -> anyhow::Result<Vec<String>> {
+    let r = query!("SELECT name FROM poll_template WHERE id_server=$1 ORDER BY name;", id_server.to_string())
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|v| v.name).collect())
+}
+
+/// Deletes a server's template by name, along with its recurrence; returns false if there was none.
+pub async fn delete_template(conn: &PgPool, id_server: u64, name: &str) -> anyhow::Result<bool> {
+    let mut tx = conn.begin().await?;
+
+    query!("DELETE FROM poll_recurrence WHERE id_server=$1 AND template=$2;", id_server.to_string(), name)
+        .execute(&mut tx)
+        .await?;
+
+    let r = query!("DELETE FROM poll_template WHERE id_server=$1 AND name=$2;", id_server.to_string(), name)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(r.rows_affected() > 0)
+}
+
+/// Makes a template recur, replacing its existing recurrence, if any.
+pub async fn set_recurrence(
+    conn: &PgPool,
+    id_server: u64,
+    id_created_by: u64,
+    template: &str,
+    period_hours: u32,
+    time_next: DateTime<Utc>,
+    id_channel: u64,
+) -> anyhow::Result<()> {
+    query!(
+        "INSERT INTO poll_recurrence (time_created, id_server, id_created_by, template, period_hours, time_next, id_channel)
+         VALUES (NOW(), $1, $2, $3, $4, $5, $6)
+         ON CONFLICT ON CONSTRAINT poll_recurrence_id_server_template_uniq
+         DO UPDATE SET id_created_by=$2, period_hours=$4, time_next=$5, id_channel=$6;",
+        id_server.to_string(), id_created_by.to_string(), template, period_hours as i32, time_next, id_channel.to_string())
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Stops a template from recurring; returns false if it was not.
+pub async fn delete_recurrence(conn: &PgPool, id_server: u64, template: &str) -> anyhow::Result<bool> {
+    let r = query!("DELETE FROM poll_recurrence WHERE id_server=$1 AND template=$2;", id_server.to_string(), template)
+        .execute(conn)
+        .await?;
+
+    Ok(r.rows_affected() > 0)
+}
+
+/// Lists a server's recurrences by template name.
+pub async fn list_recurrences(conn: &PgPool, id_server: u64) -> anyhow::Result<Vec<PollRecurrence>> {
+    let r = query!("SELECT * FROM poll_recurrence WHERE id_server=$1 ORDER BY template;", id_server.to_string())
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|r| PollRecurrence {
+        id: r.id,
+        id_server: r.id_server.parse::<u64>().unwrap(),
+        id_created_by: r.id_created_by.parse::<u64>().unwrap(),
+        template: r.template,
+        period_hours: r.period_hours as u32,
+        time_next: r.time_next,
+        id_channel: r.id_channel.parse::<u64>().unwrap(),
+        id_last_poll: r.id_last_poll,
+    }).collect())
+}
+
+/// Lists the recurrences whose next poll is due.
+pub async fn list_due_recurrences(conn: &PgPool) -> anyhow::Result<Vec<PollRecurrence>> {
+    let r = query!("SELECT * FROM poll_recurrence WHERE time_next <= NOW() ORDER BY time_next;")
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|r| PollRecurrence {
+        id: r.id,
+        id_server: r.id_server.parse::<u64>().unwrap(),
+        id_created_by: r.id_created_by.parse::<u64>().unwrap(),
+        template: r.template,
+        period_hours: r.period_hours as u32,
+        time_next: r.time_next,
+        id_channel: r.id_channel.parse::<u64>().unwrap(),
+        id_last_poll: r.id_last_poll,
+    }).collect())
+}
+
+/// Records the poll a recurrence just created and when it creates the next one.
+pub async fn advance_recurrence(conn: &PgPool, id: i32, time_next: DateTime<Utc>, id_last_poll: i32) -> anyhow::Result<()> {
+    query!("UPDATE poll_recurrence SET time_next=$2, id_last_poll=$3 WHERE id=$1;", id, time_next, id_last_poll)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn add_poll_group(
+    conn: &PgPool,
+    id_server: u64,
+    id_created_by: u64,
+    name: &str,
+    title: &str,
+    time_close: Option<DateTime<Utc>>,
+) -> anyhow::Result<PollGroup> {
+    let r = query!(
+        "INSERT INTO poll_group (time_created, id_server, id_created_by, open, name, title, time_close)
+         VALUES (NOW(), $1, $2, TRUE, $3, $4, $5)
+         RETURNING id, time_created;",
+        id_server.to_string(), id_created_by.to_string(), name, title, time_close)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(PollGroup {
+        id: r.id,
+        time_created: r.time_created,
+        id_server,
+        id_created_by,
+        open: true,
+        name: name.to_owned(),
+        title: title.to_owned(),
+        time_close,
+        id_channel: None,
+        id_message: None,
+    })
+}
+
+pub async fn get_poll_group(conn: &PgPool, id: i32) -> anyhow::Result<Option<PollGroup>> {
+    let r = query!("SELECT * FROM poll_group WHERE id=$1", id)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(r.map(|r| PollGroup {
+        id: r.id,
+        time_created: r.time_created,
+        id_server: r.id_server.parse::<u64>().unwrap(),
+        id_created_by: r.id_created_by.parse::<u64>().unwrap(),
+        open: r.open,
+        name: r.name,
+        title: r.title,
+        time_close: r.time_close,
+        id_channel: r.id_channel.map(|v| v.parse::<u64>().unwrap()),
+        id_message: r.id_message.map(|v| v.parse::<u64>().unwrap()),
+    }))
+}
+
+pub async fn get_server_poll_group(conn: &PgPool, id_server: u64, name: &str) -> anyhow::Result<Option<PollGroup>> {
+    let r = query!("SELECT id FROM poll_group WHERE id_server=$1 AND name=$2", id_server.to_string(), name)
+        .fetch_optional(conn)
+        .await?;
+
+    match r {
+        None => Ok(None),
+        Some(v) => get_poll_group(conn, v.id).await,
+    }
+}
+
+/// Lists the polls in a group in the order they were created, which is the order members vote on
+/// them in.
+pub async fn list_group_polls(conn: &PgPool, id_group: i32) -> anyhow::Result<Vec<Poll>> {
+    let ids = query!("SELECT id FROM poll WHERE id_group=$1 ORDER BY id;", id_group)
+        .fetch_all(conn)
+        .await?;
+
+    let mut result = Vec::new();
+    for r in ids {
+        if let Some(poll) = get_poll(conn, r.id).await? {
+            result.push(poll);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Adds a poll to a group, or removes it from its group if `id_group` is `None`, and sets the
+/// poll's deadline to `time_close`.
+pub async fn set_poll_group(conn: &PgPool, id_poll: i32, id_group: Option<i32>, time_close: Option<DateTime<Utc>>) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    query!("UPDATE poll SET id_group=$2, time_close=$3 WHERE id=$1;", id_poll, id_group, time_close)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM reminder_sent WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Sets the deadline of a group and of every open poll in it.
+pub async fn set_group_deadline(conn: &PgPool, id_group: i32, time_close: Option<DateTime<Utc>>) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    query!("UPDATE poll_group SET time_close=$2 WHERE id=$1;", id_group, time_close)
+        .execute(&mut tx)
+        .await?;
+
+    query!("UPDATE poll SET time_close=$2 WHERE id_group=$1 AND open=TRUE;", id_group, time_close)
+        .execute(&mut tx)
+        .await?;
+
+    query!("DELETE FROM reminder_sent WHERE id_poll IN (SELECT id FROM poll WHERE id_group=$1 AND open=TRUE);", id_group)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn set_group_message(conn: &PgPool, id_group: i32, id_channel: u64, id_message: u64) -> anyhow::Result<()> {
+    query!("UPDATE poll_group SET id_channel=$2, id_message=$3 WHERE id=$1;", id_group, id_channel.to_string(), id_message.to_string())
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Closes a group and every open poll in it; returns the IDs of the polls closed.
+pub async fn close_poll_group(conn: &PgPool, id_group: i32) -> anyhow::Result<Vec<i32>> {
+    let mut tx = conn.begin().await?;
+
+    query!("UPDATE poll_group SET open=FALSE WHERE id=$1;", id_group)
+        .execute(&mut tx)
+        .await?;
+
+    let r = query!("UPDATE poll SET open=FALSE WHERE id_group=$1 AND open=TRUE RETURNING id;", id_group)
+        .fetch_all(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(r.into_iter().map(|v| v.id).collect())
+}
+
+/// Lists the open groups whose deadline has passed.
+pub async fn list_expired_poll_groups(conn: &PgPool) -> anyhow::Result<Vec<i32>> {
+    let r = query!("SELECT id FROM poll_group WHERE open=TRUE AND time_close <= NOW();")
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|v| v.id).collect())
+}
+
+// Splits a voter into the (id_user, voter_token) columns of the ballot table.
+fn voter_columns(voter: &Voter) -> (Option<String>, Option<String>) {
+    match voter {
+        Voter::User(id) => (Some(id.to_string()), None),
+        Voter::Token(token) => (None, Some(token.clone())),
+    }
+}
+
+// Splits a ballot choice into the (id_option, write_in) columns of the ballot_choice table.
+fn choice_columns(choice: &Choice) -> (Option<i32>, Option<String>) {
+    match choice {
+        Choice::Option(id) => (Some(*id), None),
+        Choice::WriteIn(v) => (None, Some(v.clone())),
+    }
+}
+
+fn choice_from_columns(id_option: Option<i32>, write_in: Option<String>) -> Choice {
+    match id_option {
+        Some(id) => Choice::Option(id),
+        None => Choice::WriteIn(write_in.unwrap()),
+    }
+}
+
+fn voter_from_columns(id_user: Option<String>, voter_token: Option<String>) -> Voter {
+    match id_user {
+        Some(id) => Voter::User(id.parse::<u64>().unwrap()),
+        None => Voter::Token(voter_token.unwrap()),
+    }
+}
+
+/// Adds `voter`'s ballot on a poll in one transaction with invalidating the ballot it `replaces`,
+/// if any, and, when `voter` is a token, recording that `id_member` has voted. Token ballots store
+/// `by_proxy` on the voter row and an hour-rounded time, so the returned ballot's time is rounded
+/// too.
+pub async fn add_ballot(
+    conn: &PgPool,
+    id_poll: i32,
+    voter: &Voter,
+    id_member: u64,
+    replaces: Option<i32>,
+    weight: u32,
+    by_proxy: bool,
+    choices: &[(Choice, u8)],
+) -> anyhow::Result<Ballot> {
+    let mut tx = conn.begin().await?;
+
+    if let Some(id_ballot) = replaces {
+        query!("UPDATE ballot SET invalidated=TRUE WHERE id=$1;", id_ballot)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    let (id_user, voter_token) = voter_columns(voter);
+    let secret = voter_token.is_some();
+
+    let ballot = query!(
+        "INSERT INTO ballot (id_poll, id_user, voter_token, weight, by_proxy, time_created, invalidated)
+         VALUES ($1, $2, $3, $4, $5, CASE WHEN $6 THEN date_trunc('hour', NOW()) ELSE NOW() END, FALSE)
+         RETURNING id, time_created;",
+        id_poll, id_user, voter_token, weight as i32, by_proxy && !secret, secret)
+        .fetch_one(&mut tx)
+        .await?;
+
+    let mut r = Ballot {
+        id: ballot.id,
+        id_poll,
+        voter: voter.clone(),
+        weight,
+        by_proxy,
+        time_created: ballot.time_created,
+        invalidated: false,
+        choices: Vec::new(),
+    };
+
+    for (choice, rank) in choices {
+        let (id_option, write_in) = choice_columns(choice);
+
+        query!(
+            "INSERT INTO ballot_choice (id_ballot, id_option, write_in, rank)
+             VALUES ($1, $2, $3, $4);",
+            ballot.id, id_option, write_in, *rank as i32)
+            .execute(&mut tx)
+            .await?;
+
+        r.choices.push(BallotChoice {
+            id_ballot: ballot.id,
+            choice: choice.clone(),
+            rank: *rank,
+        });
+    }
+
+    if secret {
+        query!(
+            "INSERT INTO voter (id_poll, id_user, by_proxy) VALUES ($1, $2, $3)
+             ON CONFLICT ON CONSTRAINT voter_pk DO UPDATE SET by_proxy=$3;",
+            id_poll, id_member.to_string(), by_proxy)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(r)
+}
+
+pub async fn get_valid_ballot(
+    conn: &PgPool,
+    id_poll: i32,
+    voter: &Voter,
+) -> anyhow::Result<Option<Ballot>> {
+    let tx = conn.begin().await?;
+
+    let (id_user, voter_token) = voter_columns(voter);
+
+    let ballot = query!(
+        "SELECT * FROM ballot
+         WHERE id_poll=$1 AND id_user IS NOT DISTINCT FROM $2 AND voter_token IS NOT DISTINCT FROM $3 AND invalidated=FALSE;",
+        id_poll, id_user, voter_token)
+        .fetch_optional(conn)
+        .await?;
+
+    let ballot = match ballot {
+        None => return Ok(None),
+        Some(v) => v,
+    };
+
+    let mut r = Ballot {
+        id: ballot.id,
+        id_poll: ballot.id_poll,
+        voter: voter_from_columns(ballot.id_user, ballot.voter_token),
+        weight: ballot.weight as u32,
+        by_proxy: ballot.by_proxy,
+        time_created: ballot.time_created,
+        invalidated: ballot.invalidated,
+        choices: Vec::new(),
+    };
+
+    let mut choices = query!("SELECT * FROM ballot_choice WHERE id_ballot=$1;", ballot.id)
+        .map(|row| BallotChoice {
+            id_ballot: row.id_ballot,
+            choice: choice_from_columns(row.id_option, row.write_in),
+            rank: row.rank as u8,
+        })
+        .fetch(conn);
+
+    while let Some(choice) = choices.try_next().await? {
+        r.choices.push(choice);
+    }
+
+    tx.commit().await?;
+
+    Ok(Some(r))
+}
+
+pub async fn get_valid_ballots(
+    conn: &PgPool,
+    id_poll: i32,
+) -> anyhow::Result<Vec<Ballot>> {
+    let tx = conn.begin().await?;
+
+    let mut ballots = query!("SELECT * FROM ballot WHERE id_poll=$1 AND invalidated=FALSE;", id_poll)
+        .map(|row| {
+            Ballot {
+                id: row.id,
+                id_poll: row.id_poll,
+                voter: voter_from_columns(row.id_user, row.voter_token),
+                weight: row.weight as u32,
+                by_proxy: row.by_proxy,
+                time_created: row.time_created,
+                invalidated: row.invalidated,
+                choices: Vec::new(),
+            }
+        })
+        .fetch(conn);
+
+    let mut ballots_r = Vec::new();
+    while let Some(mut row) = ballots.try_next().await? {
+        let mut choices = query!("SELECT * FROM ballot_choice WHERE id_ballot=$1;", row.id)
+            .map(|row| BallotChoice {
+                id_ballot: row.id_ballot,
+                choice: choice_from_columns(row.id_option, row.write_in),
+                rank: row.rank as u8,
+            })
+            .fetch(conn);
+
+        while let Some(choice) = choices.try_next().await? {
+            row.choices.push(choice);
+        }
+
+        ballots_r.push(row);
+    }
+
+    tx.commit().await?;
+
+    Ok(ballots_r)
+}
+
+pub async fn count_valid_ballots(conn: &PgPool, id_poll: i32) -> anyhow::Result<i64> {
+    let r = query!("SELECT COUNT(*) AS count FROM ballot WHERE id_poll=$1 AND invalidated=FALSE;", id_poll)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(r.count.unwrap_or(0))
+}
+
+/// Lists the distinct write-in candidates on a poll's valid ballots, in the order they were first
+/// written in.
+pub async fn get_write_ins(conn: &PgPool, id_poll: i32) -> anyhow::Result<Vec<String>> {
+    let r = query!(
+        "SELECT c.write_in AS \"write_in!\"
+         FROM ballot_choice c
+         JOIN ballot b ON b.id = c.id_ballot
+         WHERE b.id_poll=$1 AND b.invalidated=FALSE AND c.write_in IS NOT NULL
+         GROUP BY c.write_in
+         ORDER BY MIN(b.time_created);",
+        id_poll)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|v| v.write_in).collect())
+}
+
+pub async fn count_proxy_ballots(conn: &PgPool, id_poll: i32) -> anyhow::Result<i64> {
+    // Proxy ballots on secret polls are only marked on their voter rows.
+    let r = query!(
+        "SELECT (SELECT COUNT(*) FROM ballot WHERE id_poll=$1 AND invalidated=FALSE AND by_proxy=TRUE)
+              + (SELECT COUNT(*) FROM voter WHERE id_poll=$1 AND by_proxy=TRUE) AS count;",
+        id_poll)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(r.count.unwrap_or(0))
+}
+
+/// Whether a secret poll's voter's counted ballot was cast by their delegate, or `None` if they
+/// have no counted ballot.
+pub async fn get_voter_by_proxy(conn: &PgPool, id_poll: i32, id_user: u64) -> anyhow::Result<Option<bool>> {
+    let r = query!("SELECT by_proxy FROM voter WHERE id_poll=$1 AND id_user=$2;", id_poll, id_user.to_string())
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(r.map(|v| v.by_proxy))
+}
+
+/// Invalidates a ballot on a poll and, on secret polls, removes `id_member` from its voters, in one
+/// transaction.
+pub async fn invalidate_ballot(conn: &PgPool, id_poll: i32, id_ballot: i32, id_member: Option<u64>) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    query!("UPDATE ballot SET invalidated=TRUE WHERE id=$1;", id_ballot)
+        .execute(&mut tx)
+        .await?;
+
+    if let Some(id_member) = id_member {
+        query!("DELETE FROM voter WHERE id_poll=$1 AND id_user=$2;", id_poll, id_member.to_string())
+            .execute(&mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Replaces a poll's voter roll with `id_users` and marks it frozen.
+pub async fn set_voter_roll(conn: &PgPool, id_poll: i32, id_users: &[u64]) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    query!("DELETE FROM voter_roll WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    let id_users = id_users.iter().map(|v| v.to_string()).collect::<Vec<String>>();
+    query!("INSERT INTO voter_roll (id_poll, id_user) SELECT $1, UNNEST($2::VARCHAR[]);", id_poll, &id_users)
+        .execute(&mut tx)
+        .await?;
+
+    query!("UPDATE poll SET time_roll_frozen=NOW(), time_roll_cutoff=NULL WHERE id=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn clear_voter_roll(conn: &PgPool, id_poll: i32) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    query!("DELETE FROM voter_roll WHERE id_poll=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    query!("UPDATE poll SET time_roll_frozen=NULL, time_roll_cutoff=NULL WHERE id=$1;", id_poll)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn set_roll_cutoff(conn: &PgPool, id_poll: i32, time_roll_cutoff: Option<DateTime<Utc>>) -> anyhow::Result<()> {
+    query!("UPDATE poll SET time_roll_cutoff=$2 WHERE id=$1;", id_poll, time_roll_cutoff)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_voter_roll(conn: &PgPool, id_poll: i32) -> anyhow::Result<Vec<u64>> {
+    let r = query!("SELECT id_user FROM voter_roll WHERE id_poll=$1 ORDER BY id_user;", id_poll)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|v| v.id_user.parse::<u64>().unwrap()).collect())
+}
+
+pub async fn check_on_voter_roll(conn: &PgPool, id_poll: i32, id_user: u64) -> anyhow::Result<bool> {
+    let r = query!("SELECT EXISTS(SELECT 1 FROM voter_roll WHERE id_poll=$1 AND id_user=$2) AS on_roll;", id_poll, id_user.to_string())
+        .fetch_one(conn)
+        .await?;
+
+    Ok(r.on_roll.unwrap())
+}
+
+pub async fn count_voter_roll(conn: &PgPool, id_poll: i32) -> anyhow::Result<i64> {
+    let r = query!("SELECT COUNT(*) AS count FROM voter_roll WHERE id_poll=$1;", id_poll)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(r.count.unwrap_or(0))
+}
+
+/// Lists open polls whose scheduled voter roll cutoff has passed.
+pub async fn list_due_voter_rolls(conn: &PgPool) -> anyhow::Result<Vec<i32>> {
+    let r = query!("SELECT id FROM poll WHERE open=TRUE AND time_roll_frozen IS NULL AND time_roll_cutoff <= NOW();")
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|v| v.id).collect())
+}
+
+pub async fn get_user_weight(conn: &PgPool, id_poll: i32, id_user: u64) -> anyhow::Result<Option<u32>> {
+    let r = query!("SELECT weight FROM poll_weight_user WHERE id_poll=$1 AND id_user=$2;", id_poll, id_user.to_string())
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(r.map(|v| v.weight as u32))
+}
+
+/// Sets the weight of each (user ID, weight) pair; a weight of 0 removes the user's weight.
+pub async fn set_user_weights(conn: &PgPool, id_poll: i32, weights: &[(u64, u32)]) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    for (id_user, weight) in weights {
+        match weight {
+            0 => query!("DELETE FROM poll_weight_user WHERE id_poll=$1 AND id_user=$2;", id_poll, id_user.to_string())
+                .execute(&mut tx)
+                .await?,
+            w => query!(
+                "INSERT INTO poll_weight_user (id_poll, id_user, weight) VALUES ($1, $2, $3)
+                 ON CONFLICT (id_poll, id_user) DO UPDATE SET weight=EXCLUDED.weight;",
+                id_poll, id_user.to_string(), *w as i32)
+                .execute(&mut tx)
+                .await?,
+        };
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn get_user_weights(conn: &PgPool, id_poll: i32) -> anyhow::Result<Vec<(u64, u32)>> {
+    let r = query!("SELECT id_user, weight FROM poll_weight_user WHERE id_poll=$1;", id_poll)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|v| (v.id_user.parse::<u64>().unwrap(), v.weight as u32)).collect())
+}
+
+pub async fn count_user_weights(conn: &PgPool, id_poll: i32) -> anyhow::Result<i64> {
+    let r = query!("SELECT COUNT(*) AS count FROM poll_weight_user WHERE id_poll=$1;", id_poll)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(r.count.unwrap_or(0))
+}
+
+/// Records that `id_delegator` lets `id_delegate` vote for them on a poll, or on every poll in the
+/// server if `id_poll` is `None`, replacing any delegation they had made with the same scope.
+pub async fn add_delegation(conn: &PgPool, id_server: u64, id_poll: Option<i32>, id_delegator: u64, id_delegate: u64) -> anyhow::Result<()> {
+    let mut tx = conn.begin().await?;
+
+    query!(
+        "UPDATE delegation SET time_revoked=NOW()
+         WHERE id_server=$1 AND id_poll IS NOT DISTINCT FROM $2 AND id_delegator=$3 AND time_revoked IS NULL;",
+        id_server.to_string(), id_poll, id_delegator.to_string())
+        .execute(&mut tx)
+        .await?;
+
+    query!(
+        "INSERT INTO delegation (id_server, id_poll, id_delegator, id_delegate, time_created)
+         VALUES ($1, $2, $3, $4, NOW());",
+        id_server.to_string(), id_poll, id_delegator.to_string(), id_delegate.to_string())
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Revokes a member's delegation with the given scope; returns whether they had one.
+pub async fn revoke_delegation(conn: &PgPool, id_server: u64, id_poll: Option<i32>, id_delegator: u64) -> anyhow::Result<bool> {
+    let r = query!(
+        "UPDATE delegation SET time_revoked=NOW()
+         WHERE id_server=$1 AND id_poll IS NOT DISTINCT FROM $2 AND id_delegator=$3 AND time_revoked IS NULL;",
+        id_server.to_string(), id_poll, id_delegator.to_string())
+        .execute(conn)
+        .await?;
+
+    Ok(r.rows_affected() > 0)
+}
+
+/// Finds who may vote for `id_delegator` on a poll; a delegation for the poll itself takes
+/// precedence over one covering the whole server.
+pub async fn get_delegate(conn: &PgPool, id_server: u64, id_poll: i32, id_delegator: u64) -> anyhow::Result<Option<u64>> {
+    let r = query!(
+        "SELECT id_delegate FROM delegation
+         WHERE id_server=$1 AND id_delegator=$2 AND time_revoked IS NULL AND (id_poll=$3 OR id_poll IS NULL)
+         ORDER BY id_poll NULLS LAST
+         LIMIT 1;",
+        id_server.to_string(), id_delegator.to_string(), id_poll)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(r.map(|v| v.id_delegate.parse::<u64>().unwrap()))
+}
+
+/// Marks every reminder that has come due on an open poll as sent, and returns the polls they are
+/// for. A poll with several reminders due at once, e.g. after its deadline was brought forward, is
+/// only listed once.
+pub async fn take_due_reminders(conn: &PgPool) -> anyhow::Result<Vec<i32>> {
+    let r = query!(
+        "INSERT INTO reminder_sent (id_poll, hours_before, time_sent)
+         SELECT p.id, h.hours, NOW()
+         FROM poll p, UNNEST(p.reminder_hours) AS h(hours)
+         WHERE p.open=TRUE AND p.nominating=FALSE AND p.time_close > NOW()
+           AND p.time_close - make_interval(hours => h.hours) <= NOW()
+         ON CONFLICT DO NOTHING
+         RETURNING id_poll;")
+        .fetch_all(conn)
+        .await?;
+
+    let mut ids = r.into_iter().map(|v| v.id_poll).collect::<Vec<i32>>();
+    ids.sort_unstable();
+    ids.dedup();
+
+    Ok(ids)
+}
+
+/// Lists the users with a counted ballot on a poll, including secret polls, where ballots are not
+/// stored against the user.
+pub async fn list_voted_users(conn: &PgPool, id_poll: i32) -> anyhow::Result<Vec<u64>> {
+    let r = query!(
+        "SELECT id_user AS \"id_user!\" FROM ballot WHERE id_poll=$1 AND invalidated=FALSE AND id_user IS NOT NULL
+         UNION
+         SELECT id_user FROM voter WHERE id_poll=$1;",
+        id_poll)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|v| v.id_user.parse::<u64>().unwrap()).collect())
+}
+
+pub async fn set_reminder_opt_out(conn: &PgPool, id_server: u64, id_user: u64, opted_out: bool) -> anyhow::Result<()> {
+    match opted_out {
+        true => query!("INSERT INTO reminder_opt_out (id_server, id_user) VALUES ($1, $2) ON CONFLICT DO NOTHING;", id_server.to_string(), id_user.to_string())
+            .execute(conn)
+            .await?,
+        false => query!("DELETE FROM reminder_opt_out WHERE id_server=$1 AND id_user=$2;", id_server.to_string(), id_user.to_string())
+            .execute(conn)
+            .await?,
+    };
+
+    Ok(())
+}
+
+pub async fn list_reminder_opt_outs(conn: &PgPool, id_server: u64) -> anyhow::Result<Vec<u64>> {
+    let r = query!("SELECT id_user FROM reminder_opt_out WHERE id_server=$1;", id_server.to_string())
+        .fetch_all(conn)
+        .await?;
+
+    Ok(r.into_iter().map(|v| v.id_user.parse::<u64>().unwrap()).collect())
 }
@@ -8,20 +8,386 @@ pub struct Poll {
     pub open: bool,
     pub name: String,
     pub question: String,
-    pub ranks: u8,
+    pub settings: PollSettings,
+    pub time_close: Option<DateTime<Utc>>,
+    /// Set while the poll is collecting nominations for its options instead of votes.
+    pub nominations: Option<Nominations>,
+    /// When the voter roll will be frozen, if that is scheduled and has not happened yet.
+    pub time_roll_cutoff: Option<DateTime<Utc>>,
+    /// When the voter roll was frozen; once set, only members on the roll may vote.
+    pub time_roll_frozen: Option<DateTime<Utc>>,
+    pub id_channel: Option<u64>,
+    pub id_message: Option<u64>,
+    /// The election the poll is part of, if any.
+    pub id_group: Option<i32>,
+    /// The poll this is a runoff of, if any.
+    pub id_runoff_of: Option<i32>,
     pub options: Vec<PollOption>,
 }
 
+impl Poll {
+    /// Whether the poll is open, past its nomination phase, and its deadline, if any, has not yet
+    /// passed.
+    #[must_use]
+    pub fn accepting_votes(&self) -> bool {
+        self.open && self.nominations.is_none() && self.time_close.map_or(true, |v| v > Utc::now())
+    }
+
+    /// Whether the poll is open and in a nomination phase that has not yet closed.
+    #[must_use]
+    pub fn accepting_nominations(&self) -> bool {
+        match &self.nominations {
+            None => false,
+            Some(v) => self.open && v.time_close.map_or(true, |v| v > Utc::now()),
+        }
+    }
+
+    /// Finds the option a voter meant by `v`: an exact match, or else the only option whose
+    /// 100-character form (which Discord choice values are truncated to) or case-insensitive form
+    /// matches. Returns `None` if several options match `v` in the same way.
+    #[must_use]
+    pub fn find_option(&self, v: &str) -> Option<&PollOption> {
+        find_option(&self.options, v)
+    }
+}
+
+fn find_option<'a>(options: &'a [PollOption], v: &str) -> Option<&'a PollOption> {
+    let v = v.trim();
+
+    options.iter().find(|opt| opt.option == v)
+        .or_else(|| only(options.iter().filter(|opt| opt.option.chars().take(100).eq(v.chars()))))
+        .or_else(|| only(options.iter().filter(|opt| opt.option.to_lowercase() == v.to_lowercase())))
+}
+
+// The single item of `items`, or `None` if there are none or several.
+fn only<T>(mut items: impl Iterator<Item = T>) -> Option<T> {
+    let first = items.next()?;
+
+    match items.next() {
+        None => Some(first),
+        Some(_) => None,
+    }
+}
+
+/// Per-server configuration, including defaults for new polls.
+#[derive(Clone, Debug)]
+pub struct GuildSettings {
+    /// Members with this role may manage polls, in addition to members with the Administrator
+    /// permission.
+    pub id_manager_role: Option<u64>,
+    pub results: Visibility,
+    pub method: Method,
+    pub ranks: u8,
+    pub secret: bool,
+    /// Where new polls are announced; `None` for the channel they were created in.
+    pub id_announce_channel: Option<u64>,
+    /// The server's language and region, as a tag such as `en-US`. Messages are not yet
+    /// translated, so this is only recorded.
+    pub locale: String,
+}
+
+impl GuildSettings {
+    /// The rules new polls in the server start with.
+    #[must_use]
+    pub fn poll_settings(&self) -> PollSettings {
+        PollSettings {
+            ranks: self.ranks,
+            method: self.method,
+            secret: self.secret,
+            results: self.results,
+            ..PollSettings::default()
+        }
+    }
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        let poll = PollSettings::default();
+
+        Self {
+            id_manager_role: None,
+            results: poll.results,
+            method: poll.method,
+            ranks: poll.ranks,
+            secret: poll.secret,
+            id_announce_channel: None,
+            locale: "en-US".to_owned(),
+        }
+    }
+}
+
+/// Polls voted on together as one election, e.g. for several offices. Members can vote on every
+/// poll in one flow, and the polls share a deadline and are closed together.
+pub struct PollGroup {
+    pub id: i32,
+    pub time_created: DateTime<Utc>,
+    pub id_server: u64,
+    pub id_created_by: u64,
+    pub open: bool,
+    pub name: String,
+    pub title: String,
+    pub time_close: Option<DateTime<Utc>>,
+    pub id_channel: Option<u64>,
+    pub id_message: Option<u64>,
+}
+
+/// A saved poll definition that new polls can be created from, e.g. for a poll held every week.
+pub struct PollTemplate {
+    pub id: i32,
+    pub id_server: u64,
+    pub id_created_by: u64,
+    pub name: String,
+    pub question: String,
+    pub settings: PollSettings,
+    /// How long polls created from the template stay open; `None` to close them manually.
+    pub duration_hours: Option<u32>,
+    pub options: Vec<(String, OptionDetails)>,
+}
+
+/// A template that a new poll is created from every period, e.g. a weekly check-in.
+pub struct PollRecurrence {
+    pub id: i32,
+    pub id_server: u64,
+    pub id_created_by: u64,
+    /// The name of the template; the recurrence stops if it is deleted.
+    pub template: String,
+    pub period_hours: u32,
+    /// When the next poll will be created.
+    pub time_next: DateTime<Utc>,
+    /// Where new polls are announced.
+    pub id_channel: u64,
+    /// The most recently created poll, if any.
+    pub id_last_poll: Option<i32>,
+}
+
+/// The nomination phase of a poll, before its options are fixed and voting opens.
+#[derive(Clone, Debug, Default)]
+pub struct Nominations {
+    /// How many members besides the nominator must second a nomination for it to become an option.
+    pub seconds: u32,
+    /// When nominations close and voting opens, if that is scheduled.
+    pub time_close: Option<DateTime<Utc>>,
+    /// Hours voting stays open once it starts; `None` to close it manually.
+    pub voting_hours: Option<u32>,
+}
+
+/// An option proposed during a poll's nomination phase.
+pub struct Nomination {
+    pub option: String,
+    pub id_nominated_by: u64,
+    pub id_seconded_by: Vec<u64>,
+}
+
+/// How a poll's ballots are counted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    /// Orders every option by support; the original behaviour of `/poll tally`.
+    Ranking,
+    /// Single transferable vote electing `seats` winners.
+    Stv,
+    /// Counts first choices; if no option wins a majority, a runoff poll is held between the
+    /// options with the most.
+    Runoff,
+}
+
+impl Method {
+    pub const ALL: &'static [Method] = &[Method::Ranking, Method::Stv, Method::Runoff];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Ranking => "ranking",
+            Method::Stv => "stv",
+            Method::Runoff => "runoff",
+        }
+    }
+
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Method::Ranking => "Ranking (order all options)",
+            Method::Stv => "Single transferable vote",
+            Method::Runoff => "Majority, with a runoff if no option has one",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(v: &str) -> Option<Method> {
+        Method::ALL.iter().copied().find(|m| m.as_str() == v)
+    }
+}
+
+/// Who may see a poll's results before it closes; anyone may once it has.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Visibility {
+    Always,
+    /// Only the poll's creator and the server's managers.
+    Managers,
+    Closed,
+}
+
+impl Visibility {
+    pub const ALL: &'static [Visibility] = &[Visibility::Always, Visibility::Managers, Visibility::Closed];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Always => "always",
+            Visibility::Managers => "managers",
+            Visibility::Closed => "closed",
+        }
+    }
+
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Visibility::Always => "Anyone, at any time",
+            Visibility::Managers => "Only the poll's creator and managers until it closes",
+            Visibility::Closed => "Nobody until the poll closes",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(v: &str) -> Option<Visibility> {
+        Visibility::ALL.iter().copied().find(|m| m.as_str() == v)
+    }
+}
+
+/// Per-poll voting and counting rules.
+#[derive(Clone, Debug)]
+pub struct PollSettings {
+    pub ranks: u8,
+    pub method: Method,
+    /// Number of winners; only used by [`Method::Stv`].
+    pub seats: u8,
+    /// Only used by [`Method::Runoff`].
+    pub runoff: Runoff,
+    /// Whether ballots are stored against a [`Voter::Token`] instead of the voter's user ID. Each
+    /// ballot still stores its weight, since tallies need it, so a weight only one member has
+    /// identifies that member's ballot.
+    pub secret: bool,
+    pub eligibility: Eligibility,
+    /// Vote weights for members with a role, as (role ID, weight).
+    pub role_weights: Vec<(u64, u32)>,
+    /// Turnout needed for results to be conclusive, if any.
+    pub quorum: Option<Quorum>,
+    /// Whether voters may rank candidates that are not among the poll's options.
+    pub write_ins: bool,
+    pub reminders: Reminders,
+    pub results: Visibility,
+}
+
+impl Default for PollSettings {
+    fn default() -> Self {
+        Self {
+            ranks: 3,
+            method: Method::Ranking,
+            seats: 1,
+            runoff: Runoff::default(),
+            secret: false,
+            eligibility: Eligibility::default(),
+            role_weights: Vec::new(),
+            quorum: None,
+            write_ins: false,
+            reminders: Reminders::default(),
+            results: Visibility::Always,
+        }
+    }
+}
+
+/// How a poll without a majority goes to a runoff.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Runoff {
+    /// How many of the options with the most first choices are on the runoff; options tied with
+    /// the last of them are included too.
+    pub candidates: u8,
+    /// Hours the runoff poll stays open; `None` to close it manually.
+    pub hours: Option<u32>,
+}
+
+impl Default for Runoff {
+    fn default() -> Self {
+        Self {
+            candidates: 2,
+            hours: Some(24),
+        }
+    }
+}
+
+/// When members who have not voted on a poll are reminded to; the default sends no reminders.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Reminders {
+    /// Hours before the deadline to send each reminder, latest first.
+    pub hours: Vec<u32>,
+    /// Whether to remind members by DM instead of mentioning them in the channel of the poll's
+    /// status message.
+    pub dm: bool,
+}
+
+/// Minimum turnout for a poll's results to count.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Quorum {
+    /// At least this many ballots.
+    Ballots(u32),
+    /// Ballots from at least this percentage of eligible voters.
+    Percent(u8),
+}
+
+/// Who may vote on a poll; the default lets every server member vote.
+#[derive(Clone, Debug, Default)]
+pub struct Eligibility {
+    /// Members need at least one of these roles, if any are listed.
+    pub required_roles: Vec<u64>,
+    /// Members with any of these roles may not vote.
+    pub excluded_roles: Vec<u64>,
+    pub min_member_days: Option<u32>,
+    pub min_account_days: Option<u32>,
+}
+
+impl Eligibility {
+    #[must_use]
+    pub fn is_unrestricted(&self) -> bool {
+        self.required_roles.is_empty()
+            && self.excluded_roles.is_empty()
+            && self.min_member_days.is_none()
+            && self.min_account_days.is_none()
+    }
+}
+
 pub struct PollOption {
     pub id_poll: i32,
     pub id: i32,
     pub option: String,
+    pub details: OptionDetails,
+}
+
+/// Optional extras shown alongside an option's name.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OptionDetails {
+    /// A one-line summary, e.g. of a proposal.
+    pub description: Option<String>,
+    pub url: Option<String>,
+    /// A Unicode emoji, or a custom emoji as `<:name:id>`.
+    pub emoji: Option<String>,
+}
+
+/// Whose ballot a ballot is.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Voter {
+    User(u64),
+    /// A one-way token derived from the poll and user IDs, used by secret polls so that ballots
+    /// can be replaced without recording who cast them.
+    Token(String),
 }
 
 pub struct Ballot {
     pub id: i32,
     pub id_poll: i32,
-    pub id_user: u64,
+    pub voter: Voter,
+    /// How many votes the ballot counts as, fixed when it was cast.
+    pub weight: u32,
+    /// Whether the ballot was cast by a delegate on the voter's behalf.
+    pub by_proxy: bool,
     pub time_created: DateTime<Utc>,
     pub invalidated: bool,
     pub choices: Vec<BallotChoice>,
@@ -29,6 +395,47 @@ pub struct Ballot {
 
 pub struct BallotChoice {
     pub id_ballot: i32,
-    pub id_option: i32,
+    pub choice: Choice,
     pub rank: u8,
 }
+
+/// What a voter ranked at one position of their ballot.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Choice {
+    /// One of the poll's options, by ID.
+    Option(i32),
+    /// A candidate not listed on the poll, merged with matching write-ins from other voters.
+    WriteIn(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option(id: i32, option: &str) -> PollOption {
+        PollOption {
+            id_poll: 1,
+            id,
+            option: option.to_owned(),
+            details: OptionDetails::default(),
+        }
+    }
+
+    #[test]
+    fn test_find_option() {
+        let long = "x".repeat(100);
+        let options = vec![
+            option(1, "Apple"),
+            option(2, &format!("{}1", long)),
+            option(3, &format!("{}2", long)),
+            option(4, &format!("{}y", "z".repeat(100))),
+        ];
+
+        assert_eq!(Some(1), find_option(&options, " Apple ").map(|v| v.id));
+        assert_eq!(Some(1), find_option(&options, "apple").map(|v| v.id));
+        assert_eq!(Some(4), find_option(&options, &"z".repeat(100)).map(|v| v.id));
+        // Options 2 and 3 share their first 100 characters, so the truncated form names neither.
+        assert_eq!(None, find_option(&options, &long).map(|v| v.id));
+        assert_eq!(Some(3), find_option(&options, &format!("{}2", long)).map(|v| v.id));
+    }
+}
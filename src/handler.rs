@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use dashmap::DashMap;
 use evlog::meta;
 use serenity::async_trait;
 use serenity::client::{Context, EventHandler};
@@ -10,11 +11,17 @@ use serenity::model::interactions::{Interaction, InteractionResponseType, Intera
 use serenity::prelude::TypeMapKey;
 
 use crate::commands;
+use crate::commands::ballot::BallotDraft;
+use crate::commands::wizard::PollWizard;
 use crate::db::dbclient::DBClient;
 use crate::runtime::get_logger;
 
 pub struct BotData {
     pub db_client: Arc<DBClient>,
+    /// In-progress component ballots, keyed by (user ID, poll ID).
+    pub ballot_drafts: DashMap<(u64, i32), BallotDraft>,
+    /// In-progress `/poll wizard` sessions, keyed by user ID.
+    pub poll_wizards: DashMap<u64, PollWizard>,
 }
 
 impl BotData {
@@ -23,6 +30,8 @@ impl BotData {
 
         Self {
             db_client,
+            ballot_drafts: DashMap::new(),
+            poll_wizards: DashMap::new(),
         }
     }
 }
@@ -31,6 +40,25 @@ impl TypeMapKey for BotData {
     type Value = BotData;
 }
 
+/// Identifies where an interaction came from for logging; direct messages have no guild.
+struct GuildLabel {
+    id: String,
+    name: String,
+}
+
+async fn guild_label(ctx: &Context, guild_id: Option<GuildId>) -> GuildLabel {
+    match guild_id {
+        None => GuildLabel {
+            id: "DM".to_owned(),
+            name: "DM".to_owned(),
+        },
+        Some(id) => GuildLabel {
+            id: id.to_string(),
+            name: ctx.cache.guild_field(id, |g| g.name.clone()).await.unwrap_or_default(),
+        },
+    }
+}
+
 pub struct BotHandler {}
 
 #[async_trait]
@@ -43,9 +71,6 @@ impl EventHandler for BotHandler {
             "Name" => guild.name,
         ]);
 
-        let data = ctx.data.read().await;
-        let data = data.get::<BotData>().unwrap();
-
         let existing_cmds = guild.get_application_commands(&ctx).await.unwrap();
 
         let existing_map = existing_cmds.iter()
@@ -89,58 +114,146 @@ impl EventHandler for BotHandler {
                 "ID" => created.id
             });
         }
-
-        let partial_guild = ctx.http.get_guild(*guild.id.as_u64()).await.unwrap();
-        crate::support::register_polls::register_polls(data.db_client.conn(), &ctx, &partial_guild).await.unwrap();
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::ApplicationCommand(interaction) = interaction {
-            let guild = ctx.cache.guild(interaction.guild_id.unwrap()).await.unwrap();
+        match interaction {
+            Interaction::ApplicationCommand(interaction) => {
+                let guild = guild_label(&ctx, interaction.guild_id).await;
+
+                if interaction.kind == InteractionType::Ping {
+                    get_logger().info("Interaction ping.", meta! {
+                        "GuildID" => guild.id,
+                        "GuildName" => guild.name,
+                        "InteractionID" => interaction.id
+                    });
+
+                    interaction.create_interaction_response(ctx.http.as_ref(), |r| {
+                        r.kind(InteractionResponseType::Pong)
+                    }).await.unwrap();
+                } else if interaction.kind == InteractionType::ApplicationCommand {
+                    get_logger().info("Interaction ping.", meta! {
+                        "GuildID" => guild.id,
+                        "GuildName" => guild.name,
+                        "InteractionID" => interaction.id,
+                        "CommandID" => interaction.data.id,
+                        "CommandName" => interaction.data.name
+                    });
+
+                    let handler = match commands::get_handler(&interaction.data.name) {
+                        None => return,
+                        Some(v) => v,
+                    };
 
-            if interaction.kind == InteractionType::Ping {
-                get_logger().info("Interaction ping.", meta! {
+                    let interaction_id = interaction.id;
+                    let command_id = interaction.data.id.clone();
+                    let command_name = interaction.data.name.clone();
+
+                    let r: anyhow::Result<()> = handler(ctx, interaction).await;
+                    match r {
+                        Ok(()) => {}
+                        Err(e) => {
+                            get_logger().error("Error occurred in interaction processor.", meta! {
+                                "GuildID" => guild.id,
+                                "GuildName" => guild.name,
+                                "InteractionID" => interaction_id,
+                                "CommandID" => command_id,
+                                "CommandName" => command_name,
+                                "Error" => e,
+                            });
+                        }
+                    }
+                }
+            }
+            Interaction::Autocomplete(interaction) => {
+                let handler = match commands::get_autocomplete_handler(&interaction.data.name) {
+                    None => return,
+                    Some(v) => v,
+                };
+
+                let guild = guild_label(&ctx, interaction.guild_id).await;
+                let interaction_id = interaction.id;
+                let command_name = interaction.data.name.clone();
+
+                let r: anyhow::Result<()> = handler(ctx, interaction).await;
+                match r {
+                    Ok(()) => {}
+                    Err(e) => {
+                        get_logger().error("Error occurred in autocomplete processor.", meta! {
+                            "GuildID" => guild.id,
+                            "GuildName" => guild.name,
+                            "InteractionID" => interaction_id,
+                            "CommandName" => command_name,
+                            "Error" => e,
+                        });
+                    }
+                }
+            }
+            Interaction::MessageComponent(interaction) => {
+                let guild = guild_label(&ctx, interaction.guild_id).await;
+
+                get_logger().info("Component interaction.", meta! {
                     "GuildID" => guild.id,
                     "GuildName" => guild.name,
-                    "InteractionID" => interaction.id
+                    "InteractionID" => interaction.id,
+                    "CustomID" => interaction.data.custom_id
                 });
 
-                interaction.create_interaction_response(ctx.http.as_ref(), |r| {
-                    r.kind(InteractionResponseType::Pong)
-                }).await.unwrap();
-            } else if interaction.kind == InteractionType::ApplicationCommand {
-                get_logger().info("Interaction ping.", meta! {
+                let handler = match commands::get_component_handler(&interaction.data.custom_id) {
+                    None => return,
+                    Some(v) => v,
+                };
+
+                let interaction_id = interaction.id;
+                let custom_id = interaction.data.custom_id.clone();
+
+                let r: anyhow::Result<()> = handler(ctx, interaction).await;
+                match r {
+                    Ok(()) => {}
+                    Err(e) => {
+                        get_logger().error("Error occurred in component processor.", meta! {
+                            "GuildID" => guild.id,
+                            "GuildName" => guild.name,
+                            "InteractionID" => interaction_id,
+                            "CustomID" => custom_id,
+                            "Error" => e,
+                        });
+                    }
+                }
+            }
+            Interaction::ModalSubmit(interaction) => {
+                let guild = guild_label(&ctx, interaction.guild_id).await;
+
+                get_logger().info("Modal interaction.", meta! {
                     "GuildID" => guild.id,
                     "GuildName" => guild.name,
                     "InteractionID" => interaction.id,
-                    "CommandID" => interaction.data.id,
-                    "CommandName" => interaction.data.name
+                    "CustomID" => interaction.data.custom_id
                 });
 
-                let handler = match commands::get_handler(&interaction.data.name) {
+                let handler = match commands::get_modal_handler(&interaction.data.custom_id) {
                     None => return,
                     Some(v) => v,
                 };
 
                 let interaction_id = interaction.id;
-                let command_id = interaction.data.id.clone();
-                let command_name = interaction.data.name.clone();
+                let custom_id = interaction.data.custom_id.clone();
 
                 let r: anyhow::Result<()> = handler(ctx, interaction).await;
                 match r {
                     Ok(()) => {}
                     Err(e) => {
-                        get_logger().error("Error occurred in interaction processor.", meta! {
+                        get_logger().error("Error occurred in modal processor.", meta! {
                             "GuildID" => guild.id,
                             "GuildName" => guild.name,
                             "InteractionID" => interaction_id,
-                            "CommandID" => command_id,
-                            "CommandName" => command_name,
+                            "CustomID" => custom_id,
                             "Error" => e,
                         });
                     }
                 }
             }
+            _ => {}
         }
     }
 }
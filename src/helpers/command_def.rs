@@ -1,6 +1,9 @@
 use serenity::builder::CreateApplicationCommand;
 use serenity::futures::future::BoxFuture;
 use serenity::model::interactions::application_command::ApplicationCommandInteraction;
+use serenity::model::interactions::autocomplete::AutocompleteInteraction;
+use serenity::model::interactions::message_component::MessageComponentInteraction;
+use serenity::model::interactions::modal::ModalSubmitInteraction;
 use serenity::model::prelude::*;
 use serenity::prelude::*;
 
@@ -10,11 +13,27 @@ use serenity::prelude::*;
 pub type InteractionResult = BoxFuture<'static, anyhow::Result<()>>;
 pub type CommandBuilder = fn(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand;
 pub type InteractionHandler = fn(Context, ApplicationCommandInteraction) -> InteractionResult;
+pub type AutocompleteHandler = fn(Context, AutocompleteInteraction) -> InteractionResult;
+pub type ComponentHandler = fn(Context, MessageComponentInteraction) -> InteractionResult;
+pub type ModalHandler = fn(Context, ModalSubmitInteraction) -> InteractionResult;
 
 pub struct CommandDef {
     pub name: &'static str,
     pub builder: CommandBuilder,
     pub handler: InteractionHandler,
+    pub autocomplete: Option<AutocompleteHandler>,
     pub re_register: bool,
     pub whitelisted_servers: Option<&'static [GuildId]>,
 }
+
+/// Routes message component interactions whose custom ID is `prefix` or starts with `prefix:`.
+pub struct ComponentDef {
+    pub prefix: &'static str,
+    pub handler: ComponentHandler,
+}
+
+/// Routes modal submissions whose custom ID is `prefix` or starts with `prefix:`.
+pub struct ModalDef {
+    pub prefix: &'static str,
+    pub handler: ModalHandler,
+}
@@ -1,5 +1,5 @@
 use serenity::client::Context;
-use serenity::model::id::UserId;
+use serenity::model::id::{ChannelId, RoleId, UserId};
 use serenity::model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandInteractionDataOption};
 use serenity::model::interactions::application_command::ApplicationCommandInteractionDataOptionValue;
 
@@ -89,3 +89,23 @@ pub fn find_boolean_opt(opts: &[ApplicationCommandInteractionDataOption], name:
         _ => None,
     }
 }
+
+#[must_use]
+pub fn find_role_opt(opts: &[ApplicationCommandInteractionDataOption], name: &str) -> Option<RoleId> {
+    let i = opts.iter().find(|v| v.name == name)?;
+
+    match &i.resolved {
+        Some(ApplicationCommandInteractionDataOptionValue::Role(x)) => Some(x.id),
+        _ => None,
+    }
+}
+
+#[must_use]
+pub fn find_channel_opt(opts: &[ApplicationCommandInteractionDataOption], name: &str) -> Option<ChannelId> {
+    let i = opts.iter().find(|v| v.name == name)?;
+
+    match &i.resolved {
+        Some(ApplicationCommandInteractionDataOptionValue::Channel(x)) => Some(x.id),
+        _ => None,
+    }
+}
@@ -1,7 +1,7 @@
 use serenity::builder::CreateInteractionResponseData;
 use serenity::client::Context;
 use serenity::model::id::MessageId;
-use serenity::model::interactions::InteractionResponseType;
+use serenity::model::interactions::{InteractionApplicationCommandCallbackDataFlags, InteractionResponseType};
 use serenity::model::interactions::application_command::ApplicationCommandInteraction;
 
 pub async fn reply<T>(ctx: &Context, interaction: &ApplicationCommandInteraction, content: T) -> anyhow::Result<()>
@@ -35,6 +35,32 @@ pub async fn reply_deferred_ack(ctx: &Context, interaction: &ApplicationCommandI
     Ok(())
 }
 
+/// Defers the response such that everything sent through the original response, including
+/// errors, is only visible to the invoking member.
+pub async fn reply_deferred_ack_ephemeral(ctx: &Context, interaction: &ApplicationCommandInteraction) -> anyhow::Result<()> {
+    interaction.create_interaction_response(&ctx, |response| {
+        response.kind(InteractionResponseType::DeferredChannelMessageWithSource);
+        response.interaction_response_data(|d| d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL));
+        response
+    }).await?;
+
+    Ok(())
+}
+
+/// Replaces a public deferred response with a message only the invoking member can see.
+pub async fn reply_deferred_private<D>(ctx: &Context, interaction: &ApplicationCommandInteraction, text: D) -> anyhow::Result<()>
+    where D: ToString {
+    interaction.delete_original_interaction_response(&ctx).await?;
+
+    interaction.create_followup_message(&ctx, |response| {
+        response.content(text);
+        response.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+        response
+    }).await?;
+
+    Ok(())
+}
+
 pub async fn reply_deferred_result<D>(ctx: &Context, interaction: &ApplicationCommandInteraction, text: D) -> anyhow::Result<MessageId>
     where D: ToString {
     let resp = interaction.edit_original_interaction_response(&ctx, |response| {
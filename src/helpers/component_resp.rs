@@ -0,0 +1,33 @@
+use serenity::builder::CreateInteractionResponseData;
+use serenity::client::Context;
+use serenity::model::interactions::{InteractionApplicationCommandCallbackDataFlags, InteractionResponseType};
+use serenity::model::interactions::message_component::MessageComponentInteraction;
+
+/// Replies to a component interaction with a new message only the invoking user can see.
+pub async fn reply_ephemeral<T>(ctx: &Context, interaction: &MessageComponentInteraction, content: T) -> anyhow::Result<()>
+    where T: FnOnce(&mut CreateInteractionResponseData) -> &mut CreateInteractionResponseData,
+{
+    interaction.create_interaction_response(&ctx, |response| {
+        response.kind(InteractionResponseType::ChannelMessageWithSource);
+        response.interaction_response_data(|d| {
+            d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+            content(d)
+        });
+        response
+    }).await?;
+
+    Ok(())
+}
+
+/// Replaces the message the component is attached to.
+pub async fn update<T>(ctx: &Context, interaction: &MessageComponentInteraction, content: T) -> anyhow::Result<()>
+    where T: FnOnce(&mut CreateInteractionResponseData) -> &mut CreateInteractionResponseData,
+{
+    interaction.create_interaction_response(&ctx, |response| {
+        response.kind(InteractionResponseType::UpdateMessage);
+        response.interaction_response_data(content);
+        response
+    }).await?;
+
+    Ok(())
+}
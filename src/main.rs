@@ -5,7 +5,8 @@ use evlog::{LogEventConsolePrinter, Logger};
 use serenity::Client;
 use crate::db::dbclient::DBClient;
 use crate::handler::{BotData, BotHandler};
-use crate::runtime::{get_logger, set_logger};
+use crate::runtime::{get_logger, set_logger, set_secret};
+use crate::support::scheduler;
 
 mod handler;
 mod runtime;
@@ -22,10 +23,12 @@ async fn main() {
     let appl: u64 = env::var("EASYVOTE_APPL").expect("expected EASYVOTE_APPL").parse().expect("application ID is invalid");
     let token = env::var("EASYVOTE_TOKEN").expect("expected EASYVOTE_TOKEN");
     let db_url = env::var("EASYVOTE_DATABASE_URL").expect("expected EASYVOTE_DATABASE_URL");
+    let secret = env::var("EASYVOTE_SECRET").expect("expected EASYVOTE_SECRET");
 
     let mut logger = Logger::default();
     logger.register(LogEventConsolePrinter::default());
     set_logger(logger);
+    set_secret(secret.into_bytes());
 
     let db_client = DBClient::new(&db_url).await
         .expect("failed to connect to database");
@@ -40,6 +43,8 @@ async fn main() {
             get_logger().error_with_err("Client initialization error.", &e, None);
             panic!("{}", e)
         });
+    tokio::spawn(scheduler::run(client.cache_and_http.http.clone(), data.db_client.clone()));
+
     client.data.write().await.insert::<BotData>(data);
 
     if let Err(e) = client.start_shards(2).await {
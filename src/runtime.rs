@@ -10,3 +10,14 @@ pub fn set_logger(l: Logger) {
 pub fn get_logger<'a>() -> &'a Logger {
     LOGGER.get().unwrap()
 }
+
+/// Key for values derived from ballots, such as receipt codes; must stay the same across restarts.
+pub static SECRET: OnceCell<Vec<u8>> = OnceCell::new();
+
+pub fn set_secret(s: Vec<u8>) {
+    SECRET.set(s).ok().unwrap();
+}
+
+pub fn get_secret<'a>() -> &'a [u8] {
+    SECRET.get().unwrap()
+}
@@ -18,6 +18,7 @@
 //! Library for applying a single-transferable vote algorithm to an election, as defined in a CSV
 //! file.
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 use anyhow::Error;
@@ -25,8 +26,10 @@ use anyhow::Error;
 use crate::get_logger;
 
 type Candidate = String;
-type CandidateVotesPair = (Candidate, Vec<Vote>);
-type CandidateVotesMap = HashMap<Candidate, Vec<Vote>>;
+/// A vote with the value it carries: its weight, less any part of it spent electing a candidate.
+type WeightedVote = (Vote, f64);
+type CandidateVotesPair = (Candidate, Vec<WeightedVote>);
+type CandidateVotesMap = HashMap<Candidate, Vec<WeightedVote>>;
 
 /// List of candidate names, ordered from first preference to last preference.
 pub type Vote = Vec<String>;
@@ -61,7 +64,16 @@ pub struct Election {
     eliminated: CandidateVotesMap,
     num_spoiled_votes: u64,
     seats: u64,
-    votes: Vec<Vote>,
+    votes: Vec<WeightedVote>,
+}
+
+// Allowance for rounding in fractional transfers, so that a pile that should exactly meet the
+// quota does.
+const QUOTA_TOLERANCE: f64 = 1e-9;
+
+// The number of votes a pile of weighted votes is worth.
+fn pile_value(votes: &[WeightedVote]) -> f64 {
+    votes.iter().map(|(_, weight)| weight).sum()
 }
 
 impl Election {
@@ -69,6 +81,10 @@ impl Election {
     ///
     /// The more common way to construct an `Election` is with [`Election::from_csv_file`].
     pub fn new(candidates: Vec<Candidate>, votes: Vec<Vote>, seats: u64) -> Self {
+        Election::with_values(candidates, votes.into_iter().map(|vote| (vote, 1.0)).collect(), seats)
+    }
+
+    fn with_values(candidates: Vec<Candidate>, votes: Vec<WeightedVote>, seats: u64) -> Self {
         let mut election = Election {
             candidates,
             votes,
@@ -84,9 +100,35 @@ impl Election {
         election
     }
 
+    /// Constructs an `Election` in which each vote is paired with its weight and counts as that
+    /// many votes. Votes are never copied; instead, a winner's surplus is passed on as a fraction
+    /// of the value of every vote for them, as in the Gregory method.
+    pub fn new_weighted(candidates: Vec<Candidate>, votes: Vec<(Vote, u64)>, seats: u64) -> Self {
+        let votes = votes.into_iter()
+            .map(|(vote, weight)| (vote, weight as f64))
+            .collect();
+
+        Election::with_values(candidates, votes, seats)
+    }
+
+    /// Constructs a weighted `Election` that accepts write-in candidates: anyone voted for who is
+    /// not among `candidates` runs as an additional candidate, instead of the vote being spoiled.
+    pub fn new_weighted_with_write_ins(mut candidates: Vec<Candidate>, votes: Vec<(Vote, u64)>, seats: u64) -> Self {
+        for (vote, _) in &votes {
+            for candidate in vote {
+                if !candidates.contains(candidate) {
+                    get_logger().info(format!("Write-in candidate added: {}.", candidate), None);
+                    candidates.push(candidate.clone());
+                }
+            }
+        }
+
+        Election::new_weighted(candidates, votes, seats)
+    }
+
     /// Returns the total number of votes cast in the election.
     pub fn total_votes(&self) -> u64 {
-        self.votes.len() as u64
+        pile_value(&self.votes).round() as u64
     }
 
     /// Returns the number of votes a candidate must reach to get a seat.
@@ -104,9 +146,9 @@ impl Election {
         }
 
         // First-choice votes
-        for vote in &self.votes {
+        for (vote, weight) in &self.votes {
             let candidate = candidate_votes.get_mut(&vote[0]).unwrap();
-            candidate.push(vote.clone());
+            candidate.push((vote.clone(), *weight));
         }
 
         while self.elected.len() < self.seats as usize {
@@ -145,21 +187,22 @@ impl Election {
             elected: self
                 .elected
                 .into_iter()
-                .map(|(k, v): (Candidate, Vec<Vote>)| (k, v.len() as u64))
+                .map(|(k, v): (Candidate, Vec<WeightedVote>)| (k, pile_value(&v).round() as u64))
                 .collect(),
             eliminated: self
                 .eliminated
                 .into_iter()
-                .map(|(k, v): (Candidate, Vec<Vote>)| (k, v.len() as u64))
+                .map(|(k, v): (Candidate, Vec<WeightedVote>)| (k, pile_value(&v).round() as u64))
                 .collect(),
         })
     }
 
-    // A spoiled vote is a vote containing a candidate who doesn't exist.
+    // A spoiled vote is a vote containing a candidate who doesn't exist. Elections accepting
+    // write-ins add those candidates beforehand, so none of their votes are spoiled this way.
     fn purge_spoiled_votes(&mut self) -> u64 {
         let before_length = self.votes.len();
         let candidates = self.candidates.as_slice();
-        self.votes.retain(|vote| {
+        self.votes.retain(|(vote, _)| {
             for candidate in vote {
                 if !candidates.contains(candidate) {
                     get_logger().info(format!("Candidate voted for but not running: {}.", candidate), None);
@@ -174,10 +217,10 @@ impl Election {
     fn get_round_winners(
         &self,
         candidate_votes: &CandidateVotesMap,
-    ) -> HashMap<Candidate, Vec<Vote>> {
+    ) -> HashMap<Candidate, Vec<WeightedVote>> {
         let mut elected = HashMap::new();
         for (candidate, votes) in candidate_votes {
-            if votes.len() >= self.quota() as usize {
+            if pile_value(votes) + QUOTA_TOLERANCE >= self.quota() as f64 {
                 elected.insert(candidate.clone(), votes.clone());
             }
         }
@@ -190,7 +233,7 @@ impl Election {
     ) -> Option<CandidateVotesPair> {
         let loser = candidate_votes
             .iter()
-            .min_by(|a, b| a.1.len().cmp(&b.1.len()));
+            .min_by(|a, b| pile_value(a.1).partial_cmp(&pile_value(b.1)).unwrap_or(Ordering::Equal));
 
         match loser {
             None => None,
@@ -206,16 +249,14 @@ impl Election {
         &self,
         candidate: &CandidateVotesPair,
         candidate_votes: &mut CandidateVotesMap,
-    ) -> u64 {
-        // Calculate how many surplus votes to distribute.
-        let num_surplus = candidate.1.len() - self.quota() as usize;
-        let surplus_votes = rand::seq::IteratorRandom::choose_multiple(
-            candidate.1.clone().into_iter(),
-            &mut rand::thread_rng(),
-            num_surplus,
-        );
-
-        for vote in &surplus_votes {
+    ) -> f64 {
+        // Every vote for the winner passes on the share of its value that the winner did not need.
+        let total = pile_value(&candidate.1);
+        let surplus = total - self.quota() as f64;
+        if surplus <= 0.0 {
+            return 0.0;
+        }
+        for (vote, weight) in &candidate.1 {
             if vote.len() == 1 {
                 continue;
             }
@@ -224,18 +265,18 @@ impl Election {
                 continue;
             }
             let cand = candidate_votes.get_mut(&new_vote[0]).unwrap();
-            cand.push(new_vote.to_vec());
+            cand.push((new_vote, weight * surplus / total));
         }
 
-        num_surplus as u64
+        surplus
     }
 
     fn distribute_loser_votes(
         &self,
         candidate: &CandidateVotesPair,
         candidate_votes: &mut CandidateVotesMap,
-    ) -> u64 {
-        for vote in &candidate.1 {
+    ) -> f64 {
+        for (vote, weight) in &candidate.1 {
             if vote.len() == 1 {
                 continue;
             }
@@ -244,9 +285,9 @@ impl Election {
                 continue;
             }
             let cand = candidate_votes.get_mut(&new_vote[0]).unwrap();
-            cand.push(new_vote.to_vec());
+            cand.push((new_vote, *weight));
         }
-        candidate.1.len() as u64
+        pile_value(&candidate.1)
     }
 
     fn vote_candidate_elected_or_eliminated(&self, candidate: &str) -> bool {
@@ -280,13 +321,13 @@ mod tests {
         );
         assert_eq!(
             election.votes,
-            vec![vec!["cand1".to_owned(), "cand2".to_owned()]]
+            vec![(vec!["cand1".to_owned(), "cand2".to_owned()], 1.0)]
         );
     }
 
     #[test]
     fn test_quota_calculation() {
-        let votes = vec![Vote::default(); 100];
+        let votes = vec![(Vote::default(), 1.0); 100];
         let election = Election {
             votes,
             seats: 2,
@@ -324,6 +365,62 @@ mod tests {
         assert_eq!(expected_results, results);
     }
 
+    #[test]
+    fn test_weighted_votes() {
+        crate::runtime::LOGGER.get_or_init(evlog::Logger::default);
+
+        let candidates = vec!["a".to_owned(), "b".to_owned()];
+        let votes = vec![
+            (vec!["a".to_owned()], 1),
+            (vec!["a".to_owned()], 1),
+            (vec!["b".to_owned()], 3),
+        ];
+        let election = Election::new_weighted(candidates, votes, 1);
+        assert_eq!(5, election.total_votes());
+
+        let results = election.results().unwrap();
+        assert_eq!(Some(&3), results.elected().get("b"));
+        assert!(results.elected().get("a").is_none());
+    }
+
+    #[test]
+    fn test_write_in_votes() {
+        crate::runtime::LOGGER.get_or_init(evlog::Logger::default);
+
+        let candidates = vec!["a".to_owned(), "b".to_owned()];
+        let votes = vec![
+            (vec!["z".to_owned(), "a".to_owned()], 1),
+            (vec!["z".to_owned()], 2),
+            (vec!["a".to_owned()], 1),
+            (vec!["b".to_owned()], 1),
+        ];
+        let election = Election::new_weighted_with_write_ins(candidates, votes, 1);
+        assert_eq!(0, election.num_spoiled_votes);
+        assert_eq!(5, election.total_votes());
+
+        let results = election.results().unwrap();
+        assert_eq!(Some(&3), results.elected().get("z"));
+    }
+
+    #[test]
+    fn test_weighted_surplus_transfer() {
+        crate::runtime::LOGGER.get_or_init(evlog::Logger::default);
+
+        let candidates = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+        let votes = vec![
+            (vec!["a".to_owned(), "b".to_owned()], 6),
+            (vec!["b".to_owned()], 2),
+            (vec!["c".to_owned()], 1),
+        ];
+        let election = Election::new_weighted(candidates, votes, 2);
+        assert_eq!(4, election.quota());
+
+        // a's surplus of 2 passes to b as a third of each of a's 6 votes.
+        let results = election.results().unwrap();
+        assert_eq!(Some(&6), results.elected().get("a"));
+        assert_eq!(Some(&4), results.elected().get("b"));
+    }
+
     #[test]
     fn test_spoiled_vote_removal() {
         let expected_results = ElectionResults {
@@ -0,0 +1,180 @@
+use evlog::meta;
+use itertools::Itertools;
+use serenity::builder::CreateEmbed;
+use serenity::http::Http;
+use serenity::model::guild::Member;
+use sqlx::PgPool;
+
+use crate::db;
+use crate::db::schema::{Ballot, Choice, Poll, Voter};
+use crate::runtime::get_logger;
+use crate::support::numbers::num_word;
+use crate::support::{groups, poll_status, receipts, weights};
+
+/// Identifies a member's ballots on a poll; secret polls only ever see a one-way token.
+#[must_use]
+pub fn voter(poll: &Poll, id_user: u64) -> Voter {
+    match poll.settings.secret {
+        true => Voter::Token(receipts::voter_token(poll.id, id_user)),
+        false => Voter::User(id_user),
+    }
+}
+
+/// Whether a member's counted ballot on a poll was cast by their delegate, or `None` if they have
+/// no counted ballot. Secret polls only record this against the member, not their ballot.
+pub async fn cast_by_proxy(conn: &PgPool, poll: &Poll, id_user: u64) -> anyhow::Result<Option<bool>> {
+    match poll.settings.secret {
+        true => db::model::get_voter_by_proxy(conn, poll.id, id_user).await,
+        false => Ok(db::model::get_valid_ballot(conn, poll.id, &voter(poll, id_user)).await?.map(|v| v.by_proxy)),
+    }
+}
+
+/// Replaces a member's valid ballot on a poll with a new one and refreshes the poll's status
+/// message, and its election's if it is in one. `by_proxy` marks a ballot cast by the member's
+/// delegate. Returns the new ballot and the ballot that was replaced, if any.
+pub async fn cast_ballot(
+    http: &Http,
+    conn: &PgPool,
+    poll: &Poll,
+    member: Option<&Member>,
+    id_user: u64,
+    by_proxy: bool,
+    choices: &[(Choice, u8)],
+) -> anyhow::Result<(Ballot, Option<Ballot>)> {
+    let voter = voter(poll, id_user);
+    let weight = weights::weight(http, conn, poll, member, id_user).await?;
+    let existed = db::model::get_valid_ballot(conn, poll.id, &voter).await?;
+
+    let cast = db::model::add_ballot(conn, poll.id, &voter, id_user, existed.as_ref().map(|v| v.id), weight, by_proxy, choices).await?;
+
+    if let Err(e) = poll_status::update_status_message(http, conn, poll.id).await {
+        get_logger().error("Failed to update poll status message.", meta! {
+            "PollID" => poll.id,
+            "Error" => e,
+        });
+    }
+
+    if let Some(id_group) = poll.id_group {
+        if let Err(e) = groups::update_status_message(http, conn, id_group).await {
+            get_logger().error("Failed to update election status message.", meta! {
+                "GroupID" => id_group,
+                "Error" => e,
+            });
+        }
+    }
+
+    Ok((cast, existed))
+}
+
+/// Invalidates a member's valid ballot on a poll without casting a new one and refreshes the
+/// poll's status message, and its election's if it is in one. Returns the withdrawn ballot, or
+/// `None` if the member had not voted.
+pub async fn withdraw_ballot(http: &Http, conn: &PgPool, poll: &Poll, id_user: u64) -> anyhow::Result<Option<Ballot>> {
+    let existed = match db::model::get_valid_ballot(conn, poll.id, &voter(poll, id_user)).await? {
+        None => return Ok(None),
+        Some(v) => v,
+    };
+
+    db::model::invalidate_ballot(conn, poll.id, existed.id, poll.settings.secret.then(|| id_user)).await?;
+
+    if let Err(e) = poll_status::update_status_message(http, conn, poll.id).await {
+        get_logger().error("Failed to update poll status message.", meta! {
+            "PollID" => poll.id,
+            "Error" => e,
+        });
+    }
+
+    if let Some(id_group) = poll.id_group {
+        if let Err(e) = groups::update_status_message(http, conn, id_group).await {
+            get_logger().error("Failed to update election status message.", meta! {
+                "GroupID" => id_group,
+                "Error" => e,
+            });
+        }
+    }
+
+    Ok(Some(existed))
+}
+
+/// Spells a write-in like a matching one already written in, ignoring case, so that voters who
+/// write in the same candidate are counted together.
+#[must_use]
+pub fn merge_write_in(known: &[String], v: String) -> String {
+    let lower = v.to_lowercase();
+
+    known.iter()
+        .find(|known| known.to_lowercase() == lower)
+        .cloned()
+        .unwrap_or(v)
+}
+
+/// Names a choice as voters see it, or `None` if it refers to an option no longer on the poll.
+#[must_use]
+pub fn choice_label(poll: &Poll, choice: &Choice) -> Option<String> {
+    match choice {
+        Choice::Option(id) => poll.options.iter().find(|v| v.id == *id).map(|v| v.option.clone()),
+        Choice::WriteIn(v) => Some(format!("{} (write-in)", v)),
+    }
+}
+
+/// Builds the "Ballot cast" confirmation.
+pub fn ballot_embed<'a>(e: &'a mut CreateEmbed, poll: &Poll, cast: &Ballot, replaced: &Option<Ballot>) -> &'a mut CreateEmbed {
+    e.title("Ballot cast");
+    e.thumbnail("https://i.imgur.com/fWgQ8b6.png");
+
+    e.field("Poll", format!("{} ({})", poll.name, poll.id), false);
+
+    match replaced {
+        None => {}
+        Some(v) => {
+            e.field("Replaced ballot ID", format!("{} (from {})", v.id, v.time_created), false);
+        }
+    }
+
+    e.field("Choices", choices_list(poll, cast), false);
+    weight_field(e, cast);
+    receipt_field(e, cast);
+
+    e
+}
+
+/// Shows a member the ballot they currently have counted on a poll.
+pub fn current_ballot_embed<'a>(e: &'a mut CreateEmbed, poll: &Poll, ballot: &Ballot) -> &'a mut CreateEmbed {
+    e.title("Your ballot");
+    e.thumbnail("https://i.imgur.com/fWgQ8b6.png");
+
+    e.field("Poll", format!("{} ({})", poll.name, poll.id), false);
+    e.field("Ballot ID", format!("{} (from {})", ballot.id, ballot.time_created), false);
+
+    e.field("Choices", choices_list(poll, ballot), false);
+    weight_field(e, ballot);
+    if ballot.by_proxy {
+        e.field("Cast by", "Your delegate", false);
+    }
+    receipt_field(e, ballot);
+
+    e
+}
+
+fn weight_field(e: &mut CreateEmbed, ballot: &Ballot) {
+    if ballot.weight != 1 {
+        e.field("Weight", format!("Counts as {} votes", ballot.weight), false);
+    }
+}
+
+fn receipt_field(e: &mut CreateEmbed, ballot: &Ballot) {
+    e.field("Receipt", format!(
+        "`{}`\nKeep this code; it is published with the poll's results if your ballot is counted unchanged.",
+        receipts::receipt(ballot)
+    ), false);
+}
+
+fn choices_list(poll: &Poll, ballot: &Ballot) -> String {
+    let mut opt_string = String::new();
+    for choice in ballot.choices.iter().sorted_by_key(|v| v.rank) {
+        if let Some(label) = choice_label(poll, &choice.choice) {
+            opt_string.push_str(&format!("**{}.** {}\n", num_word(choice.rank), label));
+        }
+    }
+    opt_string
+}
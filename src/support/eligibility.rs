@@ -0,0 +1,92 @@
+use chrono::{Duration, Utc};
+use serenity::http::Http;
+use serenity::model::guild::Member;
+use sqlx::PgPool;
+
+use crate::db;
+use crate::db::schema::{Eligibility, Poll};
+
+fn role_mentions(roles: &[u64]) -> String {
+    roles.iter().map(|v| format!("<@&{}>", v)).collect::<Vec<String>>().join(", ")
+}
+
+/// Describes a poll's eligibility rules, one per line, or `None` if anyone in the server may vote.
+#[must_use]
+pub fn describe(rules: &Eligibility) -> Option<String> {
+    if rules.is_unrestricted() {
+        return None;
+    }
+
+    let mut r = Vec::new();
+
+    if !rules.required_roles.is_empty() {
+        r.push(format!("Requires one of: {}", role_mentions(&rules.required_roles)));
+    }
+    if !rules.excluded_roles.is_empty() {
+        r.push(format!("Excludes: {}", role_mentions(&rules.excluded_roles)));
+    }
+    if let Some(days) = rules.min_member_days {
+        r.push(format!("Server member for at least {} day(s)", days));
+    }
+    if let Some(days) = rules.min_account_days {
+        r.push(format!("Discord account at least {} day(s) old", days));
+    }
+
+    Some(r.join("\n"))
+}
+
+/// Explains why `member` may not vote under `rules`, or returns `None` if they may.
+#[must_use]
+pub fn ineligibility(rules: &Eligibility, member: &Member) -> Option<String> {
+    if !rules.required_roles.is_empty() && !member.roles.iter().any(|v| rules.required_roles.contains(v.as_u64())) {
+        return Some(format!("You need one of these roles to vote on this poll: {}.", role_mentions(&rules.required_roles)));
+    }
+
+    if let Some(role) = member.roles.iter().find(|v| rules.excluded_roles.contains(v.as_u64())) {
+        return Some(format!("Members with the <@&{}> role may not vote on this poll.", role));
+    }
+
+    if let Some(days) = rules.min_member_days {
+        // Members whose join time is unknown are treated as having just joined.
+        let joined = member.joined_at.unwrap_or_else(Utc::now);
+        if Utc::now() - joined < Duration::days(days as i64) {
+            return Some(format!("You must have been a member of this server for at least {} day(s) to vote on this poll.", days));
+        }
+    }
+
+    if let Some(days) = rules.min_account_days {
+        if Utc::now() - member.user.id.created_at() < Duration::days(days as i64) {
+            return Some(format!("Your Discord account must be at least {} day(s) old to vote on this poll.", days));
+        }
+    }
+
+    None
+}
+
+/// Checks whether a user may vote on a poll. Once a poll's voter roll is frozen, only the roll
+/// counts. Otherwise the rules are checked against `member`, the member attached to the
+/// interaction; interactions from direct messages have none, so it is fetched from the poll's server.
+pub async fn check(http: &Http, conn: &PgPool, poll: &Poll, member: Option<&Member>, id_user: u64) -> anyhow::Result<Option<String>> {
+    if let Some(frozen) = poll.time_roll_frozen {
+        return match db::model::check_on_voter_roll(conn, poll.id, id_user).await? {
+            true => Ok(None),
+            false => Ok(Some(format!(
+                "You are not on the voter roll for this poll, which was fixed <t:{}:R>.", frozen.timestamp()
+            ))),
+        };
+    }
+
+    let rules = &poll.settings.eligibility;
+
+    if rules.is_unrestricted() {
+        return Ok(None);
+    }
+
+    match member {
+        Some(v) => Ok(ineligibility(rules, v)),
+        None => match http.get_member(poll.id_server, id_user).await {
+            Ok(v) => Ok(ineligibility(rules, &v)),
+            Err(_) => Ok(Some("Only members of the poll's server may vote on it.".to_owned())),
+        },
+    }
+}
@@ -0,0 +1,191 @@
+use evlog::meta;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::model::interactions::message_component::ButtonStyle;
+use sqlx::PgPool;
+
+use crate::commands::ballot;
+use crate::db;
+use crate::db::schema::{Poll, PollGroup};
+use crate::runtime::get_logger;
+use crate::support::{poll_status, quorum, receipts, runoff, tally::Tally, text};
+
+fn questions_list(polls: &[(Poll, i64)]) -> String {
+    if polls.is_empty() {
+        return "None yet; add polls with `/election add`".to_owned();
+    }
+
+    let lines = polls.iter()
+        .map(|(poll, n_ballots)| match poll.open {
+            true => format!("{} (`{}`; {} ballot(s))", poll.question, poll.name, n_ballots),
+            false => format!("{} (`{}`; {} ballot(s); closed)", poll.question, poll.name, n_ballots),
+        })
+        .collect::<Vec<String>>();
+
+    text::numbered_list(&lines, 1024)
+}
+
+fn status_embed<'a>(e: &'a mut CreateEmbed, group: &PollGroup, polls: &[(Poll, i64)]) -> &'a mut CreateEmbed {
+    e.title(&group.title);
+    e.thumbnail("https://i.imgur.com/fWgQ8b6.png");
+
+    e.field("Election", format!("{} ({})", group.name, group.id), false);
+    e.field("Questions", questions_list(polls), false);
+
+    match (group.open, group.time_close) {
+        (true, None) => e.field("Deadline", "None; closed manually", true),
+        (true, Some(v)) => e.field("Deadline", format!("<t:{}:f> (<t:{}:R>)", v.timestamp(), v.timestamp()), true),
+        (false, _) => e.field("Status", "**Closed**", true),
+    };
+
+    e
+}
+
+fn status_components<'a>(c: &'a mut CreateComponents, group: &PollGroup, polls: &[(Poll, i64)]) -> &'a mut CreateComponents {
+    if group.open && polls.iter().any(|(poll, _)| poll.accepting_votes()) {
+        c.create_action_row(|r| r.create_button(|b| b
+            .custom_id(format!("{}:{}", ballot::VOTE_GROUP, group.id))
+            .label("Vote on all questions")
+            .style(ButtonStyle::Primary)));
+    }
+
+    c
+}
+
+// Loads a group's polls along with how many ballots each has.
+async fn load_polls(conn: &PgPool, id_group: i32) -> anyhow::Result<Vec<(Poll, i64)>> {
+    let mut r = Vec::new();
+
+    for poll in db::model::list_group_polls(conn, id_group).await? {
+        let n_ballots = db::model::count_valid_ballots(conn, poll.id).await?;
+        r.push((poll, n_ballots));
+    }
+
+    Ok(r)
+}
+
+/// Posts the persistent status message for a newly created group and records its location.
+pub async fn post_status_message(http: &Http, conn: &PgPool, group: &PollGroup, channel_id: ChannelId) -> anyhow::Result<()> {
+    let polls = load_polls(conn, group.id).await?;
+
+    let msg = channel_id.send_message(http, |m| {
+        m.embed(|e| status_embed(e, group, &polls));
+        m.components(|c| status_components(c, group, &polls))
+    }).await?;
+
+    db::model::set_group_message(conn, group.id, *channel_id.as_u64(), *msg.id.as_u64()).await?;
+
+    Ok(())
+}
+
+/// Re-renders a group's status message with its current state; does nothing if it never had one.
+pub async fn update_status_message(http: &Http, conn: &PgPool, id_group: i32) -> anyhow::Result<()> {
+    let group = match db::model::get_poll_group(conn, id_group).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let (id_channel, id_message) = match (group.id_channel, group.id_message) {
+        (Some(c), Some(m)) => (c, m),
+        _ => return Ok(()),
+    };
+
+    let polls = load_polls(conn, group.id).await?;
+
+    ChannelId(id_channel).edit_message(http, MessageId(id_message), |m| {
+        m.embed(|e| status_embed(e, &group, &polls));
+        m.components(|c| status_components(c, &group, &polls))
+    }).await?;
+
+    Ok(())
+}
+
+/// Closes a group and every open poll in it, refreshing their status messages and holding any
+/// runoffs the polls need. Returns the closing announcement, which covers the quorum of each poll
+/// that was closed.
+pub async fn close(http: &Http, conn: &PgPool, group: &PollGroup) -> anyhow::Result<String> {
+    let closed = db::model::close_poll_group(conn, group.id).await?;
+
+    let mut r = format!("Voting closed for election **'{}'**; see the results with `/election results`.", group.title);
+
+    for id_poll in closed {
+        get_logger().info("Closed poll with its election.", meta! {
+            "PollID" => id_poll,
+            "GroupID" => group.id,
+            "GuildID" => group.id_server,
+        });
+
+        match quorum::closed_announcement(http, conn, id_poll).await {
+            Ok(v) => {
+                r.push('\n');
+                r.push_str(&v);
+            }
+            Err(e) => get_logger().error("Failed to check quorum of closed poll.", meta! {
+                "PollID" => id_poll,
+                "Error" => e,
+            }),
+        }
+
+        if let Err(e) = poll_status::update_status_message(http, conn, id_poll).await {
+            get_logger().error("Failed to update poll status message.", meta! {
+                "PollID" => id_poll,
+                "Error" => e,
+            });
+        }
+
+        if let Err(e) = runoff::start_if_needed(http, conn, id_poll, group.id_channel.map(ChannelId)).await {
+            get_logger().error("Failed to start runoff poll.", meta! {
+                "PollID" => id_poll,
+                "Error" => e,
+            });
+        }
+    }
+
+    if let Err(e) = update_status_message(http, conn, group.id).await {
+        get_logger().error("Failed to update election status message.", meta! {
+            "GroupID" => group.id,
+            "Error" => e,
+        });
+    }
+
+    Ok(r)
+}
+
+/// Builds the combined `/election results` report, with the winners of each poll in the group.
+pub fn results_embed<'a>(e: &'a mut CreateEmbed, group: &PollGroup, results: &[(Poll, Tally)]) -> &'a mut CreateEmbed {
+    let conclusive = results.iter().all(|(_, tally)| tally.conclusive());
+
+    e.title(if conclusive { "Election results" } else { "Election results (inconclusive)" });
+    e.thumbnail("https://i.imgur.com/fWgQ8b6.png");
+
+    e.field("Election", format!("{} ({})", group.title, group.name), false);
+
+    for (poll, tally) in results {
+        let mut v = match tally.winners.is_empty() {
+            true => "No votes counted".to_owned(),
+            false => tally.winners_list(),
+        };
+
+        if poll.open {
+            v.push_str("\n*Voting is still open.*");
+        }
+        if let Some(q) = &tally.quorum {
+            v.push_str(&format!("\nQuorum: {}", q.describe()));
+        }
+        v.push_str(&format!("\n{} ballot(s) counted", tally.ballots.len()));
+
+        e.field(text::truncate(&poll.question, 256), text::truncate(&v, 1024), false);
+    }
+
+    e
+}
+
+/// Lists the receipt codes of every poll in a group, under a heading for each poll.
+#[must_use]
+pub fn receipt_list(results: &[(Poll, Tally)]) -> String {
+    results.iter()
+        .map(|(poll, tally)| format!("# {}\n{}\n", poll.name, receipts::receipt_list(&tally.ballots)))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
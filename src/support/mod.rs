@@ -1,2 +1,19 @@
-pub mod register_polls;
 pub mod numbers;
+pub mod poll_status;
+pub mod scheduler;
+pub mod ballots;
+pub mod text;
+pub mod validate;
+pub mod receipts;
+pub mod eligibility;
+pub mod voter_roll;
+pub mod weights;
+pub mod quorum;
+pub mod nominations;
+pub mod tally;
+pub mod groups;
+pub mod runoff;
+pub mod names;
+pub mod recurrence;
+pub mod reminders;
+pub mod permissions;
@@ -0,0 +1,47 @@
+use sqlx::PgPool;
+
+use crate::db;
+use crate::support::text;
+
+/// Derives an unused poll name from `stem` and `suffix`, such as `budget-runoff` or
+/// `budget-runoff-2`, shortening `stem` as needed to stay within the 24-character limit on poll
+/// names. Polls the bot creates on its own, like runoffs and recurring polls, are named this way.
+pub async fn unused_poll_name(conn: &PgPool, id_server: u64, stem: &str, suffix: &str) -> anyhow::Result<String> {
+    for i in 1.. {
+        let candidate = candidate_name(stem, suffix, i);
+
+        if !db::model::check_server_has_poll_name(conn, id_server, &candidate).await? {
+            return Ok(candidate);
+        }
+    }
+
+    unreachable!()
+}
+
+// The `n`th name tried for `unused_poll_name`.
+fn candidate_name(stem: &str, suffix: &str, n: u32) -> String {
+    let suffix = match n {
+        1 => format!("-{}", suffix),
+        n => format!("-{}-{}", suffix, n),
+    };
+
+    format!("{}{}", text::truncate(stem, 24 - suffix.len()).trim_end_matches('-'), suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_name() {
+        assert_eq!("budget-runoff", candidate_name("budget", "runoff", 1));
+        assert_eq!("budget-runoff-2", candidate_name("budget", "runoff", 2));
+        assert_eq!("a-very-long-poll-runoff", candidate_name("a-very-long-poll-name", "runoff", 1));
+        assert_eq!("a-very-long-pol-runoff-2", candidate_name("a-very-long-poll-name", "runoff", 2));
+    }
+
+    #[test]
+    fn test_candidate_name_trims_dashes() {
+        assert_eq!("abcdefghijklm-runoff-10", candidate_name("abcdefghijklm-nopq", "runoff", 10));
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+
+use chrono::Utc;
+use evlog::meta;
+use sqlx::PgPool;
+
+use crate::db;
+use crate::db::schema::{Nomination, Nominations, Poll};
+use crate::runtime::get_logger;
+use crate::support::validate;
+
+/// Whether a nomination has been seconded enough to become an option once voting opens.
+#[must_use]
+pub fn qualifies(rules: &Nominations, nomination: &Nomination) -> bool {
+    nomination.id_seconded_by.len() as u32 >= rules.seconds
+}
+
+#[must_use]
+pub fn describe(rules: &Nominations) -> String {
+    let mut r = match rules.seconds {
+        0 => "Every nomination becomes an option".to_owned(),
+        v => format!("Nominations need {} second(s) to become an option", v),
+    };
+
+    match rules.time_close {
+        None => r.push_str("; voting opens when an administrator uses `/poll promote`"),
+        Some(v) => r.push_str(&format!("; voting opens <t:{}:R>", v.timestamp())),
+    }
+
+    r
+}
+
+/// Lists a poll's nominations for its status message, marking those not yet seconded enough.
+#[must_use]
+pub fn list(rules: &Nominations, nominations: &[Nomination]) -> Vec<String> {
+    nominations.iter()
+        .map(|v| match qualifies(rules, v) {
+            true => format!("{} ({} second(s))", v.option, v.id_seconded_by.len()),
+            false => format!("{} ({} second(s); *needs {}*)", v.option, v.id_seconded_by.len(), rules.seconds),
+        })
+        .collect()
+}
+
+/// Finds the nomination of the option named `name`, ignoring case and any description, link, or
+/// emoji, so that nominating an option someone else already proposed seconds it.
+#[must_use]
+pub fn find<'a>(nominations: &'a [Nomination], name: &str) -> Option<&'a Nomination> {
+    let name = name.trim().to_lowercase();
+
+    nominations.iter().find(|opt| option_name(&opt.option).map_or(false, |v| v == name))
+}
+
+// The lowercased name of a nominated option, or `None` if it is not a valid option.
+fn option_name(option: &str) -> Option<String> {
+    validate::option(option).ok().map(|(name, _)| name.to_lowercase())
+}
+
+#[must_use]
+pub fn voting_announcement(name: &str, n_options: usize) -> String {
+    format!("Nominations for poll **'{}'** have closed; voting is now open on {} option(s).", name, n_options)
+}
+
+/// Ends a poll's nomination phase, making every qualifying nomination an option and opening voting
+/// for the poll's voting period. Nominations that are not valid options, or that repeat the name of
+/// an earlier one, are left off. Returns the number of options, or a message explaining why voting
+/// could not start, in which case the poll is left unchanged.
+pub async fn start_voting(conn: &PgPool, poll: &Poll) -> anyhow::Result<Result<usize, String>> {
+    let rules = match &poll.nominations {
+        None => return Ok(Err(format!("Poll **'{}'** is not collecting nominations.", poll.name))),
+        Some(v) => v,
+    };
+
+    let qualifying = db::model::get_nominations(conn, poll.id).await?
+        .into_iter()
+        .filter(|v| qualifies(rules, v))
+        .collect::<Vec<Nomination>>();
+    let n_qualifying = qualifying.len();
+
+    let mut seen = HashSet::new();
+    let options = qualifying.into_iter()
+        .filter(|v| option_name(&v.option).map_or(false, |name| seen.insert(name)))
+        .map(|v| v.option)
+        .collect::<Vec<String>>();
+
+    let options = match validate::options(options) {
+        Ok(v) => v,
+        Err(e) => return Ok(Err(format!("Voting on poll **'{}'** cannot start: {}", poll.name, e))),
+    };
+
+    let time_close = rules.voting_hours.map(|v| Utc::now() + chrono::Duration::hours(v as i64));
+
+    db::model::start_voting(conn, poll.id, &options, time_close).await?;
+
+    get_logger().info("Opened voting on nominated options.", meta! {
+        "PollID" => poll.id,
+        "GuildID" => poll.id_server,
+        "Options" => options.len(),
+        "Skipped" => n_qualifying - options.len(),
+    });
+
+    Ok(Ok(options.len()))
+}
@@ -0,0 +1,49 @@
+use serenity::model::guild::Member;
+use serenity::model::Permissions;
+
+use crate::db::schema::{GuildSettings, Poll, Visibility};
+
+/// Whether a member may manage polls in a server: members with the Administrator permission, and
+/// members with the server's manager role, if it has one.
+#[must_use]
+pub fn is_manager(settings: &GuildSettings, member: &Member) -> bool {
+    if member.permissions.map_or(false, |v| v.contains(Permissions::ADMINISTRATOR)) {
+        return true;
+    }
+
+    match settings.id_manager_role {
+        None => false,
+        Some(id_role) => member.roles.iter().any(|v| *v.as_u64() == id_role),
+    }
+}
+
+/// Whether a member may close or change a poll: its creator, and the server's managers.
+#[must_use]
+pub fn may_manage_poll(settings: &GuildSettings, poll: &Poll, member: &Member) -> bool {
+    poll.id_created_by == *member.user.id.as_u64() || is_manager(settings, member)
+}
+
+/// Whether anyone may see a poll's results: once it has closed, or while it is open if its results
+/// are always visible.
+#[must_use]
+pub fn results_public(poll: &Poll) -> bool {
+    !poll.open || poll.settings.results == Visibility::Always
+}
+
+/// Whether a member may see a poll's results, following its [`Visibility`].
+#[must_use]
+pub fn may_tally(settings: &GuildSettings, poll: &Poll, member: &Member) -> bool {
+    match poll.settings.results {
+        Visibility::Managers => results_public(poll) || may_manage_poll(settings, poll, member),
+        _ => results_public(poll),
+    }
+}
+
+/// Names who may manage polls, for messages refusing someone who may not.
+#[must_use]
+pub fn describe_managers(settings: &GuildSettings) -> String {
+    match settings.id_manager_role {
+        None => "members with the 'Administrator' permission".to_owned(),
+        Some(v) => format!("members with the 'Administrator' permission or the <@&{}> role", v),
+    }
+}
@@ -0,0 +1,273 @@
+use evlog::meta;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::model::interactions::message_component::ButtonStyle;
+use sqlx::PgPool;
+
+use crate::commands::ballot;
+use crate::db;
+use crate::db::schema::{Method, Nomination, OptionDetails, Poll, Visibility};
+use crate::runtime::get_logger;
+use crate::support::{eligibility, nominations, quorum, reminders, runoff, text, weights};
+
+fn method_summary(poll: &Poll) -> String {
+    let mut r = match poll.settings.method {
+        Method::Ranking => format!("{}; up to {} choices", poll.settings.method.label(), poll.settings.ranks),
+        Method::Stv => format!("{} for {} seat(s); up to {} choices", poll.settings.method.label(), poll.settings.seats, poll.settings.ranks),
+        Method::Runoff => format!("{}; only first choices count; {}", poll.settings.method.label(), runoff::describe(poll.settings.runoff)),
+    };
+
+    if poll.settings.secret {
+        r.push_str("; secret ballot");
+    }
+    if poll.settings.write_ins {
+        r.push_str("; write-ins allowed");
+    }
+
+    r
+}
+
+/// Renders an option for embeds, with its emoji, description, and link if it has them.
+#[must_use]
+pub fn option_line(option: &str, details: &OptionDetails) -> String {
+    let mut r = String::new();
+
+    if let Some(v) = &details.emoji {
+        r.push_str(&format!("{} ", v));
+    }
+    r.push_str(option);
+    if let Some(v) = &details.description {
+        r.push_str(&format!(" — *{}*", v));
+    }
+    if let Some(v) = &details.url {
+        r.push_str(&format!(" ([link]({}))", v));
+    }
+
+    r
+}
+
+fn options_list(poll: &Poll) -> String {
+    let opts = poll.options.iter().map(|v| option_line(&v.option, &v.details)).collect::<Vec<String>>();
+    text::numbered_list(&opts, 1024)
+}
+
+/// Builds the "New poll created" announcement.
+pub fn created_embed<'a>(e: &'a mut CreateEmbed, poll: &Poll) -> &'a mut CreateEmbed {
+    e.title("New poll created");
+    e.thumbnail("https://i.imgur.com/fWgQ8b6.png");
+
+    e.field("Poll", format!("{} ({})", poll.name, poll.id), false);
+
+    e.field("Options", options_list(poll), false);
+
+    e.field("Method", method_summary(poll), false);
+
+    e
+}
+
+/// Ballots cast on a poll, how many of them delegates cast, and the size of its voter roll if it
+/// has been frozen.
+pub struct Turnout {
+    pub ballots: i64,
+    pub by_proxy: i64,
+    pub roll: Option<i64>,
+}
+
+impl Turnout {
+    pub async fn count(conn: &PgPool, poll: &Poll) -> anyhow::Result<Turnout> {
+        let ballots = db::model::count_valid_ballots(conn, poll.id).await?;
+        let by_proxy = db::model::count_proxy_ballots(conn, poll.id).await?;
+        let roll = match poll.time_roll_frozen {
+            None => None,
+            Some(_) => Some(db::model::count_voter_roll(conn, poll.id).await?),
+        };
+
+        Ok(Turnout { ballots, by_proxy, roll })
+    }
+
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let mut r = match self.roll {
+            Some(roll) if roll > 0 => format!(
+                "{} of {} on the roll ({:.1}%)", self.ballots, roll, self.ballots as f64 * 100.0 / roll as f64
+            ),
+            _ => format!("{} ballot(s)", self.ballots),
+        };
+
+        if self.by_proxy > 0 {
+            r.push_str(&format!("; {} by proxy", self.by_proxy));
+        }
+
+        r
+    }
+}
+
+/// Loads a poll's nominations if it is in its nomination phase, for its status and info embeds.
+pub async fn load_nominations(conn: &PgPool, poll: &Poll) -> anyhow::Result<Vec<Nomination>> {
+    match poll.nominations {
+        None => Ok(Vec::new()),
+        Some(_) => db::model::get_nominations(conn, poll.id).await,
+    }
+}
+
+fn status_embed<'a>(e: &'a mut CreateEmbed, poll: &Poll, turnout: &Turnout, nominated: &[Nomination], n_user_weights: i64) -> &'a mut CreateEmbed {
+    e.title(&poll.question);
+    e.thumbnail("https://i.imgur.com/fWgQ8b6.png");
+
+    e.field("Poll", format!("{} ({})", poll.name, poll.id), false);
+
+    if let Some(v) = poll.id_runoff_of {
+        e.field("Runoff", format!("Held because poll {} had no majority", v), false);
+    }
+
+    match &poll.nominations {
+        None => {
+            e.field("Options", options_list(poll), false);
+        }
+        Some(rules) => {
+            let list = match nominated.is_empty() {
+                true => "None yet; nominate an option with `/poll nominate`".to_owned(),
+                false => text::numbered_list(&nominations::list(rules, nominated), 1024),
+            };
+            e.field("Nominations", list, false);
+            e.field("Nomination phase", nominations::describe(rules), false);
+        }
+    }
+
+    e.field("Method", method_summary(poll), true);
+
+    match (poll.open, poll.nominations.is_some(), poll.time_close) {
+        (true, true, _) => e.field("Status", "Collecting nominations", true),
+        (true, false, None) => e.field("Deadline", "None; closed manually", true),
+        (true, false, Some(v)) => e.field("Deadline", format!("<t:{}:f> (<t:{}:R>)", v.timestamp(), v.timestamp()), true),
+        (false, _, _) => e.field("Status", "**Closed**", true),
+    };
+
+    e.field("Turnout", turnout.describe(), true);
+
+    if let Some(v) = poll.settings.quorum {
+        e.field("Quorum", quorum::describe(v), true);
+    }
+
+    match poll.time_roll_frozen {
+        Some(v) => {
+            e.field("Eligible voters", format!("Members on the voter roll fixed <t:{}:f>", v.timestamp()), false);
+        }
+        None => {
+            if let Some(v) = eligibility::describe(&poll.settings.eligibility) {
+                e.field("Eligible voters", v, false);
+            }
+            if let Some(v) = poll.time_roll_cutoff {
+                e.field("Voter roll", format!("Fixed <t:{}:R>", v.timestamp()), false);
+            }
+        }
+    }
+
+    if let Some(v) = weights::describe(poll, n_user_weights) {
+        e.field("Vote weights", v, false);
+    }
+
+    e
+}
+
+/// Builds the `/poll info` summary: everything on the status message, plus who created the poll.
+pub fn info_embed<'a>(e: &'a mut CreateEmbed, poll: &Poll, turnout: &Turnout, nominated: &[Nomination], n_user_weights: i64) -> &'a mut CreateEmbed {
+    status_embed(e, poll, turnout, nominated, n_user_weights);
+
+    if poll.time_roll_frozen.is_none() && poll.settings.eligibility.is_unrestricted() {
+        e.field("Eligible voters", "Anyone in this server", false);
+    }
+
+    if let Some(v) = reminders::describe(poll) {
+        e.field("Reminders", v, false);
+    }
+
+    if poll.open && poll.settings.results != Visibility::Always {
+        e.field("Results visible to", poll.settings.results.label(), false);
+    }
+
+    e.field("Created by", format!("<@{}>", poll.id_created_by), true);
+    e.field("Created", format!("<t:{}:f>", poll.time_created.timestamp()), true);
+
+    e
+}
+
+fn status_components<'a>(c: &'a mut CreateComponents, poll: &Poll) -> &'a mut CreateComponents {
+    if poll.accepting_votes() {
+        c.create_action_row(|r| {
+            r.create_button(|b| b
+                .custom_id(format!("{}:{}", ballot::VOTE, poll.id))
+                .label("Vote")
+                .style(ButtonStyle::Primary));
+            r.create_button(|b| b
+                .custom_id(format!("{}:{}", ballot::VOTE_DM, poll.id))
+                .label("Vote by DM")
+                .style(ButtonStyle::Secondary))
+        });
+    }
+
+    c
+}
+
+/// Posts the persistent status message for a newly created poll and records its location.
+pub async fn post_status_message(http: &Http, conn: &PgPool, poll: &Poll, channel_id: ChannelId) -> anyhow::Result<()> {
+    let turnout = Turnout::count(conn, poll).await?;
+    let nominated = load_nominations(conn, poll).await?;
+    let n_user_weights = db::model::count_user_weights(conn, poll.id).await?;
+
+    let msg = channel_id.send_message(http, |m| {
+        m.embed(|e| status_embed(e, poll, &turnout, &nominated, n_user_weights));
+        m.components(|c| status_components(c, poll))
+    }).await?;
+
+    db::model::set_poll_message(conn, poll.id, *channel_id.as_u64(), *msg.id.as_u64()).await?;
+
+    Ok(())
+}
+
+/// Announces a newly created poll in `channel` and posts its status message there. The poll exists
+/// by then, so a failure is logged rather than returned, and `false` lets the caller say so when
+/// confirming the poll.
+pub async fn announce(http: &Http, conn: &PgPool, poll: &Poll, channel_id: ChannelId) -> bool {
+    let result = match channel_id.send_message(http, |m| m.embed(|e| created_embed(e, poll))).await {
+        Ok(_) => post_status_message(http, conn, poll, channel_id).await,
+        Err(e) => Err(e.into()),
+    };
+
+    match result {
+        Ok(_) => true,
+        Err(e) => {
+            get_logger().error("Failed to announce poll.", meta! {
+                "PollID" => poll.id,
+                "ChannelID" => channel_id,
+                "Error" => e,
+            });
+            false
+        }
+    }
+}
+
+/// Re-renders a poll's status message with its current state; does nothing if it never had one.
+pub async fn update_status_message(http: &Http, conn: &PgPool, id_poll: i32) -> anyhow::Result<()> {
+    let poll = match db::model::get_poll(conn, id_poll).await? {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    let (id_channel, id_message) = match (poll.id_channel, poll.id_message) {
+        (Some(c), Some(m)) => (c, m),
+        _ => return Ok(()),
+    };
+
+    let turnout = Turnout::count(conn, &poll).await?;
+    let nominated = load_nominations(conn, &poll).await?;
+    let n_user_weights = db::model::count_user_weights(conn, poll.id).await?;
+
+    ChannelId(id_channel).edit_message(http, MessageId(id_message), |m| {
+        m.embed(|e| status_embed(e, &poll, &turnout, &nominated, n_user_weights));
+        m.components(|c| status_components(c, &poll))
+    }).await?;
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use serenity::http::Http;
+use sqlx::PgPool;
+
+use crate::db;
+use crate::db::schema::{Method, Poll, Quorum};
+use crate::support::voter_roll;
+
+/// A poll's turnout measured against its quorum.
+pub struct QuorumCheck {
+    pub ballots: i64,
+    pub required: i64,
+}
+
+impl QuorumCheck {
+    #[must_use]
+    pub fn met(&self) -> bool {
+        self.ballots >= self.required
+    }
+
+    #[must_use]
+    pub fn describe(&self) -> String {
+        match self.met() {
+            true => format!("Met: {} ballot(s) of {} required", self.ballots, self.required),
+            false => format!("**Not met**: {} ballot(s) of {} required; the results are inconclusive", self.ballots, self.required),
+        }
+    }
+}
+
+#[must_use]
+pub fn describe(quorum: Quorum) -> String {
+    match quorum {
+        Quorum::Ballots(v) => format!("{} ballot(s)", v),
+        Quorum::Percent(v) => format!("{}% of eligible voters", v),
+    }
+}
+
+/// Checks a poll's turnout against its quorum, or returns `None` if it has none. Percentages are
+/// of the voter roll if it has been frozen, and otherwise of the members currently eligible.
+pub async fn check(http: &Http, conn: &PgPool, poll: &Poll) -> anyhow::Result<Option<QuorumCheck>> {
+    let quorum = match poll.settings.quorum {
+        None => return Ok(None),
+        Some(v) => v,
+    };
+
+    let ballots = db::model::count_valid_ballots(conn, poll.id).await?;
+
+    let required = match quorum {
+        Quorum::Ballots(v) => v as i64,
+        Quorum::Percent(v) => {
+            let eligible = match poll.time_roll_frozen {
+                Some(_) => db::model::count_voter_roll(conn, poll.id).await?,
+                None => voter_roll::eligible_count(http, poll).await?,
+            };
+            percent_of(eligible, v)
+        }
+    };
+
+    Ok(Some(QuorumCheck { ballots, required }))
+}
+
+/// The ballots needed to reach `percent` of `eligible` voters, rounded up so that e.g. 50% of 5
+/// voters needs 3 ballots.
+fn percent_of(eligible: i64, percent: u8) -> i64 {
+    (eligible * percent as i64 + 99) / 100
+}
+
+/// Announces that voting on a poll has closed, along with whether it reached its quorum.
+pub async fn closed_announcement(http: &Http, conn: &PgPool, id_poll: i32) -> anyhow::Result<String> {
+    let poll = match db::model::get_poll(conn, id_poll).await? {
+        None => return Err(anyhow::anyhow!("poll {} does not exist", id_poll)),
+        Some(v) => v,
+    };
+
+    let mut r = format!("Voting closed for poll **'{}'**.", poll.name);
+    match check(http, conn, &poll).await {
+        Ok(None) => {}
+        Ok(Some(v)) => {
+            r.push_str(&format!("\nQuorum: {}.", v.describe()));
+            if !v.met() && poll.settings.method == Method::Runoff {
+                r.push_str(" No runoff will be held.");
+            }
+        }
+        Err(e) if e.is::<voter_roll::MissingMembersIntent>() => {
+            r.push_str(&format!("\nQuorum: could not be checked, since {}.", e));
+        }
+        Err(e) => return Err(e),
+    }
+
+    Ok(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_of() {
+        assert_eq!(3, percent_of(5, 50));
+        assert_eq!(2, percent_of(4, 50));
+        assert_eq!(1, percent_of(1, 1));
+        assert_eq!(0, percent_of(0, 50));
+        assert_eq!(7, percent_of(7, 100));
+    }
+
+    #[test]
+    fn test_quorum_met() {
+        assert!(QuorumCheck { ballots: 3, required: 3 }.met());
+        assert!(!QuorumCheck { ballots: 2, required: 3 }.met());
+        assert!(QuorumCheck { ballots: 0, required: 0 }.met());
+    }
+}
@@ -0,0 +1,59 @@
+use hmac::{Hmac, Mac};
+use itertools::Itertools;
+use sha2::Sha256;
+
+use crate::db::schema::{Ballot, Choice};
+use crate::runtime::get_secret;
+
+// Hex digits of the HMAC shown to voters; enough that two ballots on a poll never share a code,
+// few enough to compare by eye.
+const RECEIPT_LEN: usize = 20;
+
+/// Computes a ballot's receipt code from its poll, ID, weight, cast time and choices, keyed with the bot's
+/// secret. The code is recomputed from the stored ballot when results are published, so it only
+/// matches the voter's copy if the ballot was counted unchanged.
+#[must_use]
+pub fn receipt(ballot: &Ballot) -> String {
+    let mut msg = format!("receipt:{}:{}:{}:{}", ballot.id_poll, ballot.id, ballot.weight, ballot.time_created.timestamp_nanos());
+    for choice in ballot.choices.iter().sorted_by_key(|v| v.rank) {
+        match &choice.choice {
+            Choice::Option(id) => msg.push_str(&format!(":{}={}", id, choice.rank)),
+            Choice::WriteIn(v) => msg.push_str(&format!(":write-in:{}={}", v, choice.rank)),
+        }
+    }
+
+    keyed_digest(&msg).chars()
+        .take(RECEIPT_LEN)
+        .chunks(4)
+        .into_iter()
+        .map(|v| v.collect::<String>())
+        .join("-")
+}
+
+/// Derives the token a secret poll stores in place of a voter's user ID. The same member always
+/// gets the same token on a poll, so they can replace their ballot, but the user ID cannot be
+/// recovered from it without the bot's secret.
+#[must_use]
+pub fn voter_token(id_poll: i32, id_user: u64) -> String {
+    keyed_digest(&format!("voter:{}:{}", id_poll, id_user))
+}
+
+/// Lists the receipt code of every counted ballot, one per line. The list is sorted so it reveals
+/// nothing about the order ballots were cast in.
+#[must_use]
+pub fn receipt_list(ballots: &[Ballot]) -> String {
+    ballots.iter()
+        .map(receipt)
+        .sorted()
+        .join("\n")
+}
+
+// Hex-encoded HMAC-SHA256 of `msg` keyed with the bot's secret.
+fn keyed_digest(msg: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(get_secret()).expect("HMAC accepts keys of any length");
+    mac.update(msg.as_bytes());
+
+    mac.finalize().into_bytes().iter()
+        .map(|v| format!("{:02x}", v))
+        .collect::<String>()
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use evlog::meta;
+use serenity::http::Http;
+use serenity::model::id::ChannelId;
+use sqlx::PgPool;
+
+use crate::db;
+use crate::db::schema::{Poll, PollRecurrence};
+use crate::runtime::get_logger;
+use crate::support::{names, poll_status};
+
+#[must_use]
+pub fn describe(period_hours: u32) -> String {
+    match period_hours {
+        24 => "every day".to_owned(),
+        168 => "every week".to_owned(),
+        v if v % 168 == 0 => format!("every {} weeks", v / 168),
+        v if v % 24 == 0 => format!("every {} days", v / 24),
+        1 => "every hour".to_owned(),
+        v => format!("every {} hours", v),
+    }
+}
+
+/// The first time after `now` that falls a whole number of periods after `time_next`, so that a
+/// recurrence missed while the bot was offline resumes on its schedule instead of catching up.
+#[must_use]
+pub fn next_after(time_next: DateTime<Utc>, period_hours: u32, now: DateTime<Utc>) -> DateTime<Utc> {
+    let periods = (now - time_next).num_hours().max(0) / period_hours as i64 + 1;
+
+    time_next + chrono::Duration::hours(periods * period_hours as i64)
+}
+
+// Names a recurring poll after the day it opens, or the hour for polls recurring more than once a
+// day, e.g. `standup-2026-10-18`.
+fn instance_suffix(period_hours: u32, time: DateTime<Utc>) -> String {
+    match period_hours % 24 {
+        0 => time.format("%Y-%m-%d").to_string(),
+        _ => time.format("%Y-%m-%d-%Hh").to_string(),
+    }
+}
+
+/// Creates and announces the next poll of a recurrence from its template. Each poll closes when its
+/// template says to, but no later than when the next one opens. If the template has been deleted,
+/// the recurrence is stopped instead and `None` returned.
+pub async fn start_instance(http: &Http, conn: &PgPool, recurrence: &PollRecurrence) -> anyhow::Result<Option<Poll>> {
+    let channel = ChannelId(recurrence.id_channel);
+
+    let template = match db::model::get_server_template(conn, recurrence.id_server, &recurrence.template).await? {
+        Some(v) => v,
+        None => {
+            db::model::delete_recurrence(conn, recurrence.id_server, &recurrence.template).await?;
+            get_logger().info("Stopped recurrence of deleted template.", meta! {
+                "RecurrenceID" => recurrence.id,
+                "GuildID" => recurrence.id_server,
+            });
+            channel.say(http, format!(
+                "Template **'{}'** no longer exists, so polls are no longer created from it.", recurrence.template
+            )).await?;
+            return Ok(None);
+        }
+    };
+
+    let now = Utc::now();
+    let hours = template.duration_hours.map_or(recurrence.period_hours, |v| v.min(recurrence.period_hours));
+    let time_close = now + chrono::Duration::hours(hours as i64);
+
+    let name = names::unused_poll_name(conn, recurrence.id_server, &template.name, &instance_suffix(recurrence.period_hours, now)).await?;
+
+    let poll = db::model::add_poll(
+        conn,
+        recurrence.id_server,
+        recurrence.id_created_by,
+        &name,
+        &template.question,
+        &template.settings,
+        Some(time_close),
+        None,
+        &template.options,
+    ).await?;
+
+    // Advanced before announcing, so that a failure to post is not retried as another poll.
+    db::model::advance_recurrence(conn, recurrence.id, next_after(recurrence.time_next, recurrence.period_hours, now), poll.id).await?;
+
+    get_logger().info("Created recurring poll.", meta! {
+        "PollID" => poll.id,
+        "RecurrenceID" => recurrence.id,
+        "GuildID" => recurrence.id_server,
+    });
+
+    channel.send_message(http, |m| m.embed(|e| poll_status::created_embed(e, &poll))).await?;
+    poll_status::post_status_message(http, conn, &poll, channel).await?;
+
+    Ok(Some(poll))
+}
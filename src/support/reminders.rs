@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+
+use evlog::meta;
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, UserId};
+use sqlx::PgPool;
+
+use crate::db;
+use crate::db::schema::Poll;
+use crate::runtime::get_logger;
+use crate::support::{text, voter_roll};
+
+// Above this many members, a reminder is posted in the poll's channel without mentioning anyone,
+// rather than sending that many DMs or mentions.
+const MAX_REMINDED: usize = 500;
+
+/// Whether a poll reminds members by DM. Secret polls always do, since mentioning who has not
+/// voted in the channel would tell everyone who has.
+#[must_use]
+pub fn by_dm(poll: &Poll) -> bool {
+    poll.settings.reminders.dm || poll.settings.secret
+}
+
+/// Describes when and how a poll sends reminders, or returns `None` if it sends none.
+#[must_use]
+pub fn describe(poll: &Poll) -> Option<String> {
+    let rules = &poll.settings.reminders;
+    if rules.hours.is_empty() {
+        return None;
+    }
+
+    let hours = rules.hours.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(", ");
+
+    Some(match by_dm(poll) {
+        true => format!("By DM, {} hour(s) before the deadline", hours),
+        false => format!("In the poll's channel, {} hour(s) before the deadline", hours),
+    })
+}
+
+/// Lists the members who may vote on a poll but have no counted ballot on it, leaving out those
+/// who opted out of reminders in its server.
+pub async fn non_voters(http: &Http, conn: &PgPool, poll: &Poll) -> anyhow::Result<Vec<u64>> {
+    let eligible = match poll.time_roll_frozen {
+        Some(_) => db::model::get_voter_roll(conn, poll.id).await?,
+        None => voter_roll::eligible_members(http, poll).await?,
+    };
+
+    let skipped = db::model::list_voted_users(conn, poll.id).await?.into_iter()
+        .chain(db::model::list_reminder_opt_outs(conn, poll.id_server).await?)
+        .collect::<HashSet<u64>>();
+
+    Ok(eligible.into_iter().filter(|v| !skipped.contains(v)).collect())
+}
+
+fn reminder_text(poll: &Poll) -> String {
+    let deadline = poll.time_close.map_or_else(String::new, |v| format!(" closes <t:{}:R>", v.timestamp()));
+
+    let link = match (poll.id_channel, poll.id_message) {
+        (Some(c), Some(m)) => format!(" Vote from the poll's message: https://discord.com/channels/{}/{}/{}", poll.id_server, c, m),
+        _ => format!(" Vote with `/vote cast poll:{}`.", poll.name),
+    };
+
+    format!("Voting on poll **'{}'** ({}){}, and you have not voted yet.{}", poll.name, text::truncate(&poll.question, 200), deadline, link)
+}
+
+/// Reminds the members who have not voted on a poll, by DM or with mentions in the channel of its
+/// status message, depending on the poll's rules. Secret polls and polls without a status message
+/// always use DMs.
+pub async fn send(http: &Http, conn: &PgPool, poll: &Poll) -> anyhow::Result<()> {
+    let users = non_voters(http, conn, poll).await?;
+    if users.is_empty() {
+        return Ok(());
+    }
+
+    let text = reminder_text(poll);
+    let opt_out = "Stop these reminders with `/vote reminders enabled:False`.";
+    let channel = poll.id_channel.map(ChannelId);
+
+    if users.len() > MAX_REMINDED {
+        if let Some(channel) = channel {
+            channel.say(http, format!("**Reminder:** {}", text)).await?;
+        }
+        return Ok(());
+    }
+
+    match (by_dm(poll), channel) {
+        (false, Some(channel)) => {
+            let mentions = users.iter().map(|v| format!("<@{}>", v)).collect::<Vec<String>>();
+
+            // Mentions are split across messages to stay within Discord's 2000-character limit.
+            let mut header = format!("**Reminder:** {} {}\n", text, opt_out);
+            for chunk in mentions.chunks(50) {
+                channel.say(http, format!("{}{}", header, chunk.join(" "))).await?;
+                header = String::new();
+            }
+        }
+        _ => {
+            let mut failed = 0;
+            for id_user in &users {
+                // Members who do not accept DMs from the bot are skipped.
+                let sent = match UserId(*id_user).create_dm_channel(http).await {
+                    Ok(dm) => dm.say(http, format!("**Reminder:** {}\n{}", text, opt_out)).await.is_ok(),
+                    Err(_) => false,
+                };
+                if !sent {
+                    failed += 1;
+                }
+            }
+
+            if failed > 0 {
+                get_logger().info("Failed to DM some voting reminders.", meta! {
+                    "PollID" => poll.id,
+                    "Failed" => failed,
+                });
+            }
+        }
+    }
+
+    get_logger().info("Sent voting reminders.", meta! {
+        "PollID" => poll.id,
+        "GuildID" => poll.id_server,
+        "Reminded" => users.len(),
+    });
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use chrono::Utc;
+use evlog::meta;
+use serenity::http::Http;
+use serenity::model::id::ChannelId;
+use sqlx::PgPool;
+
+use crate::db;
+use crate::db::schema::{Method, OptionDetails, Poll, Runoff};
+use crate::runtime::get_logger;
+use crate::support::{names, poll_status, quorum, tally};
+
+#[must_use]
+pub fn describe(rules: Runoff) -> String {
+    match rules.hours {
+        None => format!("without a majority, the top {} go to a runoff closed manually", rules.candidates),
+        Some(v) => format!("without a majority, the top {} go to a runoff open for {} hour(s)", rules.candidates, v),
+    }
+}
+
+/// Picks the options for a runoff from first choices sorted by votes: the top `n`, plus any tied
+/// with the last of them.
+#[must_use]
+pub fn candidates(first_preferences: &[(String, u64)], n: usize) -> Vec<String> {
+    let cutoff = match first_preferences.get(n.saturating_sub(1)) {
+        None => return first_preferences.iter().map(|(opt, _)| opt.clone()).collect(),
+        Some((_, votes)) => *votes,
+    };
+
+    first_preferences.iter()
+        .filter(|(_, votes)| *votes >= cutoff)
+        .map(|(opt, _)| opt.clone())
+        .collect()
+}
+
+/// Holds a runoff for a poll that has just closed, if it counts first choices, met its quorum, and
+/// none of its options won a majority. The runoff copies the poll's rules and electorate, and is
+/// announced in the channel of the poll's status message, or else in `fallback`. Returns the
+/// runoff, if one was created.
+pub async fn start_if_needed(http: &Http, conn: &PgPool, id_poll: i32, fallback: Option<ChannelId>) -> anyhow::Result<Option<Poll>> {
+    let poll = match db::model::get_poll(conn, id_poll).await? {
+        None => return Ok(None),
+        Some(v) => v,
+    };
+
+    if poll.settings.method != Method::Runoff || db::model::get_runoff(conn, poll.id).await?.is_some() {
+        return Ok(None);
+    }
+
+    // Results that missed the quorum are inconclusive, so they cannot send options to a runoff
+    // either; the closing announcement already says the quorum was not met.
+    if quorum::check(http, conn, &poll).await?.map_or(false, |v| !v.met()) {
+        get_logger().info("Skipped runoff for poll that missed its quorum.", meta! {
+            "PollID" => poll.id,
+            "GuildID" => poll.id_server,
+        });
+        return Ok(None);
+    }
+
+    let ballots = db::model::get_valid_ballots(conn, poll.id).await?;
+    let total_weight = ballots.iter().map(|v| v.weight as u64).sum::<u64>();
+    let first_preferences = tally::first_preferences(&poll, &ballots);
+
+    match first_preferences.first() {
+        None => return Ok(None),
+        Some((_, votes)) if votes * 2 > total_weight => return Ok(None),
+        Some(_) => {}
+    }
+
+    // A runoff between all of the poll's options, e.g. after a tie for first, would only repeat it.
+    let options = candidates(&first_preferences, poll.settings.runoff.candidates as usize);
+    if options.len() < 2 || poll.options.iter().all(|v| options.contains(&v.option)) {
+        return Ok(None);
+    }
+
+    let options = options.into_iter()
+        .map(|opt| {
+            let details = poll.options.iter()
+                .find(|v| v.option == opt)
+                .map_or_else(OptionDetails::default, |v| v.details.clone());
+            (opt, details)
+        })
+        .collect::<Vec<(String, OptionDetails)>>();
+
+    // The runoff itself picks a winner outright, so that a tie cannot lead to another runoff, and
+    // is limited to the options that made it.
+    let mut settings = poll.settings.clone();
+    settings.method = Method::Ranking;
+    settings.write_ins = false;
+    settings.ranks = settings.ranks.min(options.len() as u8).max(2);
+
+    let name = names::unused_poll_name(conn, poll.id_server, &poll.name, "runoff").await?;
+    let time_close = poll.settings.runoff.hours.map(|v| Utc::now() + chrono::Duration::hours(v as i64));
+
+    let mut runoff = db::model::add_poll(
+        conn,
+        poll.id_server,
+        poll.id_created_by,
+        &name,
+        &poll.question,
+        &settings,
+        time_close,
+        None,
+        &options,
+    ).await?;
+
+    db::model::set_runoff_of(conn, runoff.id, poll.id).await?;
+    runoff.id_runoff_of = Some(poll.id);
+
+    let user_weights = db::model::get_user_weights(conn, poll.id).await?;
+    if !user_weights.is_empty() {
+        db::model::set_user_weights(conn, runoff.id, &user_weights).await?;
+    }
+
+    if poll.time_roll_frozen.is_some() {
+        let roll = db::model::get_voter_roll(conn, poll.id).await?;
+        db::model::set_voter_roll(conn, runoff.id, &roll).await?;
+        runoff.time_roll_frozen = Some(Utc::now());
+    }
+
+    get_logger().info("Created runoff poll.", meta! {
+        "PollID" => runoff.id,
+        "RunoffOf" => poll.id,
+        "GuildID" => poll.id_server,
+    });
+
+    let channel = match poll.id_channel.map(ChannelId).or(fallback) {
+        None => return Ok(Some(runoff)),
+        Some(v) => v,
+    };
+
+    let names = options.iter().map(|(opt, _)| format!("**{}**", opt)).collect::<Vec<String>>().join(", ");
+    channel.say(http, format!(
+        "No option won a majority of first choices on poll **'{}'**; a runoff between {} is open as poll **'{}'**.",
+        poll.name, names, runoff.name
+    )).await?;
+    channel.send_message(http, |m| m.embed(|e| poll_status::created_embed(e, &runoff))).await?;
+    poll_status::post_status_message(http, conn, &runoff, channel).await?;
+
+    Ok(Some(runoff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn votes(v: &[(&str, u64)]) -> Vec<(String, u64)> {
+        v.iter().map(|(opt, votes)| (opt.to_string(), *votes)).collect()
+    }
+
+    #[test]
+    fn test_candidates() {
+        let first_preferences = votes(&[("a", 5), ("b", 4), ("c", 2), ("d", 1)]);
+        assert_eq!(vec!["a", "b"], candidates(&first_preferences, 2));
+        assert_eq!(vec!["a", "b", "c", "d"], candidates(&first_preferences, 10));
+    }
+
+    #[test]
+    fn test_candidates_ties() {
+        let first_preferences = votes(&[("a", 5), ("b", 3), ("c", 3), ("d", 1)]);
+        assert_eq!(vec!["a", "b", "c"], candidates(&first_preferences, 2));
+    }
+}
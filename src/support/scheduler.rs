@@ -0,0 +1,245 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use evlog::meta;
+use serenity::http::Http;
+use serenity::model::id::ChannelId;
+use sqlx::PgPool;
+
+use crate::db;
+use crate::db::dbclient::DBClient;
+use crate::runtime::get_logger;
+use crate::support::{groups, nominations, poll_status, quorum, recurrence, reminders, runoff, voter_roll};
+
+const TICK: Duration = Duration::from_secs(30);
+
+/// Periodically performs time-based poll work, such as opening voting when nominations close,
+/// freezing voter rolls at their cutoff, closing polls and elections whose deadline has passed and
+/// holding any runoffs they need, reminding members who have not voted, and creating recurring
+/// polls.
+pub async fn run(http: Arc<Http>, db_client: Arc<DBClient>) {
+    let mut interval = tokio::time::interval(TICK);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = promote_due_nominations(&http, db_client.conn()).await {
+            get_logger().error("Error occurred upon attempt to open voting on due nominations.", meta! {
+                "Error" => e,
+            });
+        }
+
+        if let Err(e) = freeze_due_voter_rolls(&http, db_client.conn()).await {
+            get_logger().error("Error occurred upon attempt to freeze due voter rolls.", meta! {
+                "Error" => e,
+            });
+        }
+
+        // Before closing, so that a reminder due at the deadline is taken before the poll closes.
+        if let Err(e) = send_due_reminders(&http, db_client.conn()).await {
+            get_logger().error("Error occurred upon attempt to send voting reminders.", meta! {
+                "Error" => e,
+            });
+        }
+
+        if let Err(e) = close_expired_polls(&http, db_client.conn()).await {
+            get_logger().error("Error occurred upon attempt to close expired polls.", meta! {
+                "Error" => e,
+            });
+        }
+
+        if let Err(e) = close_expired_groups(&http, db_client.conn()).await {
+            get_logger().error("Error occurred upon attempt to close expired elections.", meta! {
+                "Error" => e,
+            });
+        }
+
+        // After closing, so that the previous poll of a recurrence closes before the next opens.
+        if let Err(e) = start_due_recurrences(&http, db_client.conn()).await {
+            get_logger().error("Error occurred upon attempt to create recurring polls.", meta! {
+                "Error" => e,
+            });
+        }
+    }
+}
+
+async fn promote_due_nominations(http: &Http, conn: &PgPool) -> anyhow::Result<()> {
+    for id_poll in db::model::list_due_nominations(conn).await? {
+        let poll = match db::model::get_poll(conn, id_poll).await? {
+            None => continue,
+            Some(v) => v,
+        };
+
+        // Without enough seconded nominations there is nothing to vote on, so the poll is closed
+        // rather than left waiting on a deadline that has passed.
+        let announcement = match nominations::start_voting(conn, &poll).await? {
+            Ok(n_options) => nominations::voting_announcement(&poll.name, n_options),
+            Err(e) => {
+                db::model::close_poll_by_id(conn, poll.id).await?;
+                get_logger().info("Closed poll with too few nominations.", meta! {
+                    "PollID" => id_poll,
+                    "GuildID" => poll.id_server,
+                });
+                format!("{} The poll has been closed.", e)
+            }
+        };
+
+        if let Some(id_channel) = poll.id_channel {
+            if let Err(e) = ChannelId(id_channel).say(http, announcement).await {
+                get_logger().error("Failed to announce end of nominations.", meta! {
+                    "PollID" => id_poll,
+                    "Error" => e,
+                });
+            }
+        }
+
+        if let Err(e) = poll_status::update_status_message(http, conn, id_poll).await {
+            get_logger().error("Failed to update poll status message.", meta! {
+                "PollID" => id_poll,
+                "Error" => e,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+async fn freeze_due_voter_rolls(http: &Http, conn: &PgPool) -> anyhow::Result<()> {
+    for id_poll in db::model::list_due_voter_rolls(conn).await? {
+        let poll = match db::model::get_poll(conn, id_poll).await? {
+            None => continue,
+            Some(v) => v,
+        };
+
+        // A failure is retried on the next tick, since the cutoff remains set until the roll is frozen.
+        if let Err(e) = voter_roll::freeze(http, conn, &poll).await {
+            get_logger().error("Failed to freeze poll voter roll.", meta! {
+                "PollID" => id_poll,
+                "Error" => e,
+            });
+            continue;
+        }
+
+        if let Err(e) = poll_status::update_status_message(http, conn, id_poll).await {
+            get_logger().error("Failed to update poll status message.", meta! {
+                "PollID" => id_poll,
+                "Error" => e,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+async fn send_due_reminders(http: &Arc<Http>, conn: &PgPool) -> anyhow::Result<()> {
+    for id_poll in db::model::take_due_reminders(conn).await? {
+        let poll = match db::model::get_poll(conn, id_poll).await? {
+            None => continue,
+            Some(v) => v,
+        };
+
+        // Sending hundreds of DMs takes a while under Discord's rate limits, so reminders are sent
+        // from their own task rather than holding up the rest of the tick. They are marked sent
+        // before sending, so a failure is not retried as a second reminder.
+        let http = http.clone();
+        let conn = conn.clone();
+        tokio::spawn(async move {
+            if let Err(e) = reminders::send(&http, &conn, &poll).await {
+                get_logger().error("Failed to send voting reminders.", meta! {
+                    "PollID" => id_poll,
+                    "Error" => e,
+                });
+            }
+        });
+    }
+
+    Ok(())
+}
+
+async fn close_expired_polls(http: &Http, conn: &PgPool) -> anyhow::Result<()> {
+    let closed = db::model::close_expired_polls(conn).await?;
+
+    for (id_poll, id_server) in closed {
+        get_logger().info("Closed poll at deadline.", meta! {
+            "PollID" => id_poll,
+            "GuildID" => id_server,
+        });
+
+        if let Err(e) = announce_closed(http, conn, id_poll).await {
+            get_logger().error("Failed to announce poll closure.", meta! {
+                "PollID" => id_poll,
+                "Error" => e,
+            });
+        }
+
+        if let Err(e) = poll_status::update_status_message(http, conn, id_poll).await {
+            get_logger().error("Failed to update poll status message.", meta! {
+                "PollID" => id_poll,
+                "Error" => e,
+            });
+        }
+
+        if let Err(e) = runoff::start_if_needed(http, conn, id_poll, None).await {
+            get_logger().error("Failed to start runoff poll.", meta! {
+                "PollID" => id_poll,
+                "Error" => e,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// A group's polls share its deadline, so they have normally been closed by `close_expired_polls`
+// already; this closes any that were not and the group itself.
+async fn close_expired_groups(http: &Http, conn: &PgPool) -> anyhow::Result<()> {
+    for id_group in db::model::list_expired_poll_groups(conn).await? {
+        let group = match db::model::get_poll_group(conn, id_group).await? {
+            None => continue,
+            Some(v) => v,
+        };
+
+        let announcement = groups::close(http, conn, &group).await?;
+
+        get_logger().info("Closed election at deadline.", meta! {
+            "GroupID" => id_group,
+            "GuildID" => group.id_server,
+        });
+
+        if let Some(id_channel) = group.id_channel {
+            if let Err(e) = ChannelId(id_channel).say(http, announcement).await {
+                get_logger().error("Failed to announce election closure.", meta! {
+                    "GroupID" => id_group,
+                    "Error" => e,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn start_due_recurrences(http: &Http, conn: &PgPool) -> anyhow::Result<()> {
+    for recurrence in db::model::list_due_recurrences(conn).await? {
+        if let Err(e) = recurrence::start_instance(http, conn, &recurrence).await {
+            get_logger().error("Failed to create recurring poll.", meta! {
+                "RecurrenceID" => recurrence.id,
+                "Error" => e,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// Posts the closing announcement in the channel of the poll's status message, if it has one.
+async fn announce_closed(http: &Http, conn: &PgPool, id_poll: i32) -> anyhow::Result<()> {
+    let id_channel = match db::model::get_poll(conn, id_poll).await?.and_then(|v| v.id_channel) {
+        None => return Ok(()),
+        Some(v) => v,
+    };
+
+    ChannelId(id_channel).say(http, quorum::closed_announcement(http, conn, id_poll).await?).await?;
+
+    Ok(())
+}
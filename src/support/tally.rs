@@ -0,0 +1,188 @@
+use itertools::Itertools;
+use serenity::http::Http;
+use sqlx::PgPool;
+
+use crate::{db, stv};
+use crate::db::schema::{Ballot, Choice, Method, Poll};
+use crate::support::quorum::{self, QuorumCheck};
+
+/// The counted result of a poll.
+pub struct Tally {
+    /// Elected options, or every option with support when ranking or counting first choices, as
+    /// (label, votes) with the most votes first.
+    pub winners: Vec<(String, u64)>,
+    pub ballots: Vec<Ballot>,
+    pub total_weight: u64,
+    pub quorum: Option<QuorumCheck>,
+    pub method: Method,
+}
+
+impl Tally {
+    /// Whether the poll met its quorum, if it has one.
+    #[must_use]
+    pub fn conclusive(&self) -> bool {
+        self.quorum.as_ref().map_or(true, |v| v.met())
+    }
+
+    /// The option with more than half of all first choices, if the poll counts them and one does.
+    #[must_use]
+    pub fn majority(&self) -> Option<&str> {
+        match (self.method, self.winners.first()) {
+            (Method::Runoff, Some((opt, votes))) if votes * 2 > self.total_weight => Some(opt.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Lists the winners by place; options with the same number of votes share a place.
+    #[must_use]
+    pub fn winners_list(&self) -> String {
+        let mut res_string = String::new();
+
+        let mut last = u64::MAX;
+        let mut curr = 0;
+        for (opt, votes) in &self.winners {
+            if *votes < last {
+                curr += 1;
+                last = *votes;
+            }
+            match self.method {
+                Method::Runoff => res_string.push_str(&format!("**{}**. **{}** (first choices: {})\n", curr, opt, votes)),
+                _ => res_string.push_str(&format!("**{}**. **{}** (cumulative votes: {})\n", curr, opt, votes)),
+            }
+        }
+
+        res_string
+    }
+}
+
+/// Sums the weight of the ballots ranking each option or write-in first, skipping choices of
+/// options no longer on the poll, with the most first choices first.
+#[must_use]
+pub fn first_preferences(poll: &Poll, ballots: &[Ballot]) -> Vec<(String, u64)> {
+    let mut r: Vec<(String, u64)> = Vec::new();
+
+    for ballot in ballots {
+        let first = ballot.choices.iter()
+            .sorted_by_key(|v| v.rank)
+            .find_map(|v| match &v.choice {
+                Choice::Option(id) => poll.options.iter().find(|opt| opt.id == *id).map(|opt| opt.option.clone()),
+                Choice::WriteIn(v) => Some(v.clone()),
+            });
+
+        if let Some(first) = first {
+            match r.iter_mut().find(|(opt, _)| *opt == first) {
+                Some((_, votes)) => *votes += ballot.weight as u64,
+                None => r.push((first, ballot.weight as u64)),
+            }
+        }
+    }
+
+    r.sort_by_key(|(_, votes)| -(*votes as i64));
+    r
+}
+
+/// Counts a poll's valid ballots with its counting method and checks its quorum.
+pub async fn tally(http: &Http, conn: &PgPool, poll: &Poll) -> anyhow::Result<Tally> {
+    let ballots = db::model::get_valid_ballots(conn, poll.id).await?;
+    let total_weight = ballots.iter().map(|v| v.weight as u64).sum();
+    let quorum = quorum::check(http, conn, poll).await?;
+
+    let elected = match poll.settings.method {
+        Method::Runoff => first_preferences(poll, &ballots),
+        _ => rank(poll, &ballots)?,
+    };
+
+    let mut winners = Vec::new();
+    for (opt, votes) in elected {
+        match poll.options.iter().any(|v| v.option == opt) {
+            true => winners.push((opt, votes)),
+            false => winners.push((format!("{} (write-in)", opt), votes)),
+        }
+    }
+    winners.sort_by_key(|(_, votes)| -(*votes as i64));
+
+    Ok(Tally { winners, ballots, total_weight, quorum, method: poll.settings.method })
+}
+
+// Counts ballots by single transferable vote, returning each elected option or write-in with its
+// cumulative votes.
+fn rank(poll: &Poll, ballots: &[Ballot]) -> anyhow::Result<Vec<(String, u64)>> {
+    let mut stv_candidates = Vec::new();
+    for opt in &poll.options {
+        stv_candidates.push(opt.option.clone());
+    }
+
+    let mut stv_votes = Vec::new();
+    for ballot in ballots {
+        let mut stv_vote = Vec::new();
+
+        for choice in ballot.choices.iter().sorted_by_key(|v| v.rank) {
+            match &choice.choice {
+                Choice::Option(id) => {
+                    if let Some(opt) = poll.options.iter().find(|v| v.id == *id) {
+                        stv_vote.push(opt.option.clone());
+                    }
+                }
+                Choice::WriteIn(v) => stv_vote.push(v.clone()),
+            }
+        }
+
+        stv_votes.push((stv_vote, ballot.weight as u64));
+    }
+
+    let seats = match poll.settings.method {
+        // More seats than any poll has options, so every option with support is ranked.
+        Method::Ranking => 150,
+        Method::Stv | Method::Runoff => poll.settings.seats as u64,
+    };
+
+    let stv_election = match poll.settings.write_ins {
+        true => stv::Election::new_weighted_with_write_ins(stv_candidates, stv_votes, seats),
+        false => stv::Election::new_weighted(stv_candidates, stv_votes, seats),
+    };
+
+    let stv_results = stv_election.results()?;
+
+    Ok(stv_results.elected().iter().map(|(opt, votes)| (opt.clone(), *votes)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tally(method: Method, winners: &[(&str, u64)]) -> Tally {
+        let winners = winners.iter().map(|(opt, votes)| (opt.to_string(), *votes)).collect::<Vec<(String, u64)>>();
+        let total_weight = winners.iter().map(|(_, votes)| votes).sum();
+
+        Tally {
+            winners,
+            ballots: Vec::new(),
+            total_weight,
+            quorum: None,
+            method,
+        }
+    }
+
+    #[test]
+    fn test_majority() {
+        assert_eq!(Some("a"), tally(Method::Runoff, &[("a", 3), ("b", 2)]).majority());
+        assert_eq!(None, tally(Method::Runoff, &[("a", 2), ("b", 2)]).majority());
+        assert_eq!(None, tally(Method::Ranking, &[("a", 3), ("b", 2)]).majority());
+        assert_eq!(None, tally(Method::Runoff, &[]).majority());
+    }
+
+    #[test]
+    fn test_winners_list_shares_places() {
+        let list = tally(Method::Ranking, &[("a", 5), ("b", 5), ("c", 2)]).winners_list();
+        assert_eq!("**1**. **a** (cumulative votes: 5)\n**1**. **b** (cumulative votes: 5)\n**2**. **c** (cumulative votes: 2)\n", list);
+    }
+
+    #[test]
+    fn test_conclusive() {
+        let mut t = tally(Method::Ranking, &[("a", 1)]);
+        assert!(t.conclusive());
+
+        t.quorum = Some(QuorumCheck { ballots: 1, required: 2 });
+        assert!(!t.conclusive());
+    }
+}
@@ -0,0 +1,23 @@
+pub fn truncate(s: &str, n: usize) -> String {
+    s.chars().take(n).collect()
+}
+
+/// Renders `items` as a numbered list, cut short with a count of the rest if it would exceed
+/// `limit` bytes (e.g. the 1024-character limit on embed fields).
+pub fn numbered_list<T: AsRef<str>>(items: &[T], limit: usize) -> String {
+    let mut r = String::new();
+
+    for (i, item) in items.iter().enumerate() {
+        let line = format!("**{}.** {}\n", i + 1, item.as_ref());
+
+        // Always leave room for the "and N more" line.
+        if r.len() + line.len() + 32 > limit {
+            r.push_str(&format!("...and {} more", items.len() - i));
+            break;
+        }
+
+        r.push_str(&line);
+    }
+
+    r
+}
@@ -0,0 +1,528 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::db::schema::{Method, OptionDetails, Quorum};
+
+// Validators shared by every way of creating or editing a poll. Each returns the normalized value,
+// or a message suitable for showing to the member who supplied it.
+
+pub const MAX_RANKS: i64 = 20;
+pub const MAX_DURATION_HOURS: i64 = 2160;
+pub const MAX_OPTIONS: usize = 100;
+pub const MAX_DAYS: i64 = 3650;
+pub const MAX_WEIGHT: i64 = 1000;
+pub const MAX_QUORUM: i64 = 100000;
+pub const MAX_SECONDS: i64 = 100;
+pub const MAX_RUNOFF_CANDIDATES: i64 = 10;
+pub const MAX_REMINDERS: usize = 5;
+// Each poll in an election gets a field in its results embed, which allows at most 25.
+pub const MAX_GROUP_POLLS: usize = 20;
+// The longest text Discord shows in an autocomplete suggestion.
+pub const MAX_WRITE_IN_LEN: usize = 100;
+
+// Discord's limit on select menu option descriptions.
+pub const MAX_DESCRIPTION_LEN: usize = 100;
+pub const MAX_URL_LEN: usize = 512;
+
+static VALIDATE_POLL_NAME: Lazy<Regex> = Lazy::new(|| Regex::new("^[a-z0-9-]+$").unwrap());
+static RECURRENCE_PERIOD: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d{1,4})\s*([hdw])$").unwrap());
+static LOCALE: Lazy<Regex> = Lazy::new(|| Regex::new("^[a-z]{2,3}(-[A-Z]{2})?$").unwrap());
+static CUSTOM_EMOJI: Lazy<Regex> = Lazy::new(|| Regex::new(r"^<a?:\w+:\d+>$").unwrap());
+
+pub fn poll_name(name: &str) -> Result<String, String> {
+    let name = name.trim().to_lowercase();
+
+    match VALIDATE_POLL_NAME.is_match(&name) && name.len() <= 24 {
+        true => Ok(name),
+        false => Err(format!(
+            "Name '{}' is invalid; may only contain letters, numbers, and dashes (-), and be at most 24 characters long.", name
+        )),
+    }
+}
+
+pub fn ranks(ranks: i64) -> Result<u8, String> {
+    if ranks < 2 || ranks > MAX_RANKS {
+        return Err(format!("`ranks` must be greater than 1 and less than {}; got {}.", MAX_RANKS + 1, ranks));
+    }
+
+    Ok(ranks as u8)
+}
+
+pub fn deadline(hours: i64) -> Result<DateTime<Utc>, String> {
+    hours_from_now("duration", hours)
+}
+
+/// Validates a number of hours given in option `name` and returns the time that many hours from now.
+pub fn hours_from_now(name: &str, hours: i64) -> Result<DateTime<Utc>, String> {
+    if hours < 1 || hours > MAX_DURATION_HOURS {
+        return Err(format!("`{}` must be between 1 and {} hours; got {}.", name, MAX_DURATION_HOURS, hours));
+    }
+
+    Ok(Utc::now() + chrono::Duration::hours(hours))
+}
+
+/// Validates a minimum age in days for option `name`; 0 removes the minimum.
+pub fn days(name: &str, days: i64) -> Result<Option<u32>, String> {
+    match days {
+        0 => Ok(None),
+        v if v < 0 || v > MAX_DAYS => Err(format!("`{}` must be between 0 and {} days; got {}.", name, MAX_DAYS, v)),
+        v => Ok(Some(v as u32)),
+    }
+}
+
+/// Validates how many members must second a nomination.
+pub fn seconds(seconds: i64) -> Result<u32, String> {
+    if seconds < 0 || seconds > MAX_SECONDS {
+        return Err(format!("`seconds` must be between 0 and {}; got {}.", MAX_SECONDS, seconds));
+    }
+
+    Ok(seconds as u32)
+}
+
+/// Validates how many options go to a runoff.
+pub fn runoff_candidates(candidates: i64) -> Result<u8, String> {
+    if candidates < 2 || candidates > MAX_RUNOFF_CANDIDATES {
+        return Err(format!("`runoff` must be between 2 and {}; got {}.", MAX_RUNOFF_CANDIDATES, candidates));
+    }
+
+    Ok(candidates as u8)
+}
+
+/// Validates how many hours a runoff stays open; 0 leaves it open until it is closed manually.
+pub fn runoff_hours(hours: i64) -> Result<Option<u32>, String> {
+    match hours {
+        0 => Ok(None),
+        v if v < 0 || v > MAX_DURATION_HOURS => Err(format!("`runoff-duration` must be between 0 and {} hours; got {}.", MAX_DURATION_HOURS, v)),
+        v => Ok(Some(v as u32)),
+    }
+}
+
+/// Parses how often a poll recurs: `daily`, `weekly`, or a number of hours, days, or weeks such as
+/// `12h`, `3d`, or `2w`. Returns the period in hours.
+pub fn period(v: &str) -> Result<u32, String> {
+    let v = v.trim().to_lowercase();
+
+    let hours = match v.as_str() {
+        "daily" => Some(24),
+        "weekly" => Some(168),
+        _ => RECURRENCE_PERIOD.captures(&v).and_then(|c| {
+            let n = c[1].parse::<i64>().ok()?;
+            match &c[2] {
+                "h" => Some(n),
+                "d" => Some(n * 24),
+                _ => Some(n * 168),
+            }
+        }),
+    };
+
+    match hours {
+        Some(h) if h >= 1 && h <= MAX_DURATION_HOURS => Ok(h as u32),
+        _ => Err(format!(
+            "`every` must be `daily`, `weekly`, or a period such as `12h`, `3d`, or `2w` of at most {} hours; got '{}'.",
+            MAX_DURATION_HOURS, v
+        )),
+    }
+}
+
+/// Parses reminder lead times written as hours separated by commas or spaces, such as `24, 1`;
+/// `none` or an empty list turns reminders off. Returns the hours, latest first.
+pub fn reminder_hours(v: &str) -> Result<Vec<u32>, String> {
+    let v = v.trim().to_lowercase();
+    if v == "none" {
+        return Ok(Vec::new());
+    }
+
+    let mut r = Vec::new();
+    for part in v.split(|c: char| c == ',' || c.is_whitespace()).filter(|v| !v.is_empty()) {
+        match part.trim_end_matches('h').parse::<i64>() {
+            Ok(h) if h >= 1 && h <= MAX_DURATION_HOURS => r.push(h as u32),
+            _ => return Err(format!(
+                "`reminders` must be hours before the deadline between 1 and {}, such as `24, 1`, or `none`; got '{}'.", MAX_DURATION_HOURS, part
+            )),
+        }
+    }
+
+    r.sort_unstable_by(|a, b| b.cmp(a));
+    r.dedup();
+
+    if r.len() > MAX_REMINDERS {
+        return Err(format!("At most {} reminders may be sent per poll; got {}.", MAX_REMINDERS, r.len()));
+    }
+
+    Ok(r)
+}
+
+/// Validates a language tag such as `en` or `en-US`, fixing the case of each part.
+pub fn locale(v: &str) -> Result<String, String> {
+    let v = match v.trim().split_once(['-', '_']) {
+        None => v.trim().to_lowercase(),
+        Some((lang, region)) => format!("{}-{}", lang.to_lowercase(), region.to_uppercase()),
+    };
+
+    match LOCALE.is_match(&v) {
+        true => Ok(v),
+        false => Err(format!("`locale` must be a language tag such as `en` or `en-US`; got '{}'.", v)),
+    }
+}
+
+/// Validates a vote weight; 0 removes a weight rule.
+pub fn weight(weight: i64) -> Result<u32, String> {
+    if weight < 0 || weight > MAX_WEIGHT {
+        return Err(format!("Weights must be between 0 and {}; got {}.", MAX_WEIGHT, weight));
+    }
+
+    Ok(weight as u32)
+}
+
+/// Parses a table of user weights written as `user:weight` pairs separated by commas or spaces,
+/// where each user is an ID or a mention.
+pub fn weight_table(table: &str) -> Result<Vec<(u64, u32)>, String> {
+    let mut r = Vec::new();
+
+    for entry in table.split(|c: char| c == ',' || c.is_whitespace()).filter(|v| !v.is_empty()) {
+        let (user, weight) = match entry.rsplit_once(':') {
+            None => return Err(format!("Expected `user:weight`; got '{}'.", entry)),
+            Some(v) => v,
+        };
+
+        let user = match user.trim_start_matches("<@").trim_start_matches('!').trim_end_matches('>').parse::<u64>() {
+            Ok(v) => v,
+            Err(_) => return Err(format!("'{}' is not a user ID or mention.", user)),
+        };
+
+        let weight = match weight.parse::<i64>() {
+            Ok(v) => self::weight(v)?,
+            Err(_) => return Err(format!("'{}' is not a number.", weight)),
+        };
+
+        r.push((user, weight));
+    }
+
+    if r.is_empty() {
+        return Err("The weight table is empty.".to_owned());
+    }
+
+    Ok(r)
+}
+
+/// Validates a quorum given as a number of ballots or as a percentage of eligible voters, where 0
+/// removes the quorum.
+pub fn quorum(ballots: Option<i64>, percent: Option<i64>) -> Result<Option<Quorum>, String> {
+    match (ballots, percent) {
+        (Some(_), Some(_)) => Err("Give either `quorum` or `quorum-percent`, not both.".to_owned()),
+        (Some(0), None) | (None, Some(0)) | (None, None) => Ok(None),
+        (Some(v), None) if v < 0 || v > MAX_QUORUM => Err(format!("`quorum` must be between 0 and {}; got {}.", MAX_QUORUM, v)),
+        (Some(v), None) => Ok(Some(Quorum::Ballots(v as u32))),
+        (None, Some(v)) if v < 0 || v > 100 => Err(format!("`quorum-percent` must be between 0 and 100; got {}.", v)),
+        (None, Some(v)) => Ok(Some(Quorum::Percent(v as u8))),
+    }
+}
+
+/// Parses a quorum written as a number of ballots, such as `10`, or a percentage of eligible
+/// voters, such as `25%`; an empty value or 0 removes the quorum.
+pub fn quorum_text(v: &str) -> Result<Option<Quorum>, String> {
+    let v = v.trim();
+    if v.is_empty() {
+        return Ok(None);
+    }
+
+    let parsed = match v.strip_suffix('%') {
+        None => v.parse::<i64>().map(|n| (Some(n), None)),
+        Some(p) => p.trim().parse::<i64>().map(|n| (None, Some(n))),
+    };
+
+    match parsed {
+        Ok((ballots, percent)) => quorum(ballots, percent),
+        Err(_) => Err(format!("The quorum must be a number of ballots, such as `10`, or a percentage, such as `25%`; got '{}'.", v)),
+    }
+}
+
+/// Normalizes a write-in candidate by trimming it and collapsing runs of whitespace.
+pub fn write_in(v: &str) -> Result<String, String> {
+    let v = v.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    if v.is_empty() {
+        return Err("Write-in candidates may not be empty.".to_owned());
+    }
+
+    if v.chars().count() > MAX_WRITE_IN_LEN {
+        return Err(format!("Write-in candidates may be at most {} characters long.", MAX_WRITE_IN_LEN));
+    }
+
+    Ok(v)
+}
+
+pub fn seats(method: Method, seats: i64, n_options: usize) -> Result<u8, String> {
+    match method {
+        Method::Stv if seats < 1 || seats as usize >= n_options => Err(format!(
+            "`seats` must be at least 1 and fewer than the number of options ({}); got {}.", n_options, seats
+        )),
+        _ => Ok(seats.max(1).min(u8::MAX as i64) as u8),
+    }
+}
+
+/// Parses an option written as `Name | description | link | emoji`, where everything after the
+/// name is optional and may come in any order.
+pub fn option(v: &str) -> Result<(String, OptionDetails), String> {
+    let mut parts = v.split('|').map(|v| v.trim());
+
+    let name = parts.next().unwrap_or_default().to_owned();
+    if name.is_empty() {
+        return Err("Options may not be empty.".to_owned());
+    }
+
+    let mut details = OptionDetails::default();
+
+    for part in parts.filter(|v| !v.is_empty()) {
+        if part.starts_with("http://") || part.starts_with("https://") {
+            if details.url.is_some() || part.contains(char::is_whitespace) || part.len() > MAX_URL_LEN {
+                return Err(format!("Option '{}' may have one link of at most {} characters, without spaces.", name, MAX_URL_LEN));
+            }
+            details.url = Some(part.to_owned());
+        } else if is_emoji(part) {
+            if details.emoji.is_some() {
+                return Err(format!("Option '{}' may only have one emoji.", name));
+            }
+            details.emoji = Some(part.to_owned());
+        } else {
+            if details.description.is_some() || part.chars().count() > MAX_DESCRIPTION_LEN {
+                return Err(format!("Option '{}' may have one description of at most {} characters.", name, MAX_DESCRIPTION_LEN));
+            }
+            details.description = Some(part.to_owned());
+        }
+    }
+
+    Ok((name, details))
+}
+
+// A custom emoji or a single Unicode emoji. Options' emoji are shown on select menus, and Discord
+// rejects a menu with anything else, such as a symbol like ★ that is not an emoji.
+fn is_emoji(v: &str) -> bool {
+    CUSTOM_EMOJI.is_match(v) || is_unicode_emoji(v)
+}
+
+// A Unicode emoji, including keycaps, flags, and sequences joined with zero-width joiners, such as
+// 👩‍💻, whose parts may carry skin tones.
+fn is_unicode_emoji(v: &str) -> bool {
+    let chars = v.chars().collect::<Vec<char>>();
+
+    match chars.as_slice() {
+        [c, '\u{FE0F}', '\u{20E3}'] => c.is_ascii_digit() || *c == '#' || *c == '*',
+        [a, b] if is_regional_indicator(*a) && is_regional_indicator(*b) => true,
+        _ => chars.split(|c| *c == '\u{200D}').enumerate().all(|(i, v)| is_emoji_part(v, i > 0)),
+    }
+}
+
+// One emoji of a sequence, followed by any presentation selector, skin tone, or tags. Symbols that
+// are shown as text by default only count with the emoji presentation selector, or as the later
+// parts of a sequence, like the ⚕ in 🧑‍⚕️.
+fn is_emoji_part(chars: &[char], joined: bool) -> bool {
+    let (base, rest) = match chars.split_first() {
+        None => return false,
+        Some(v) => v,
+    };
+
+    let symbol = ('\u{2000}'..='\u{2BFF}').contains(base);
+    let presented = rest.first() == Some(&'\u{FE0F}');
+
+    (is_pictograph(*base) || (symbol && (presented || joined)))
+        && rest.iter().all(|c| matches!(c, '\u{FE0F}' | '\u{1F3FB}'..='\u{1F3FF}' | '\u{E0020}'..='\u{E007F}'))
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+// Characters shown as emoji by default (Unicode's Emoji_Presentation property).
+fn is_pictograph(c: char) -> bool {
+    matches!(c,
+        '\u{231A}'..='\u{231B}' | '\u{23E9}'..='\u{23EC}' | '\u{23F0}' | '\u{23F3}' | '\u{25FD}'..='\u{25FE}'
+        | '\u{2614}'..='\u{2615}' | '\u{2648}'..='\u{2653}' | '\u{267F}' | '\u{2693}' | '\u{26A1}'
+        | '\u{26AA}'..='\u{26AB}' | '\u{26BD}'..='\u{26BE}' | '\u{26C4}'..='\u{26C5}' | '\u{26CE}' | '\u{26D4}'
+        | '\u{26EA}' | '\u{26F2}'..='\u{26F3}' | '\u{26F5}' | '\u{26FA}' | '\u{26FD}' | '\u{2705}'
+        | '\u{270A}'..='\u{270B}' | '\u{2728}' | '\u{274C}' | '\u{274E}' | '\u{2753}'..='\u{2755}' | '\u{2757}'
+        | '\u{2795}'..='\u{2797}' | '\u{27B0}' | '\u{27BF}' | '\u{2B1B}'..='\u{2B1C}' | '\u{2B50}' | '\u{2B55}'
+        | '\u{1F004}' | '\u{1F0CF}' | '\u{1F18E}' | '\u{1F191}'..='\u{1F19A}' | '\u{1F201}' | '\u{1F21A}'
+        | '\u{1F22F}' | '\u{1F232}'..='\u{1F236}' | '\u{1F238}'..='\u{1F23A}' | '\u{1F250}'..='\u{1F251}'
+        | '\u{1F300}'..='\u{1F64F}' | '\u{1F680}'..='\u{1F6FF}' | '\u{1F7E0}'..='\u{1F7EB}'
+        | '\u{1F90C}'..='\u{1F9FF}' | '\u{1FA70}'..='\u{1FAFF}')
+}
+
+pub fn options(opts: Vec<String>) -> Result<Vec<(String, OptionDetails)>, String> {
+    let opts = opts.iter().map(|v| option(v)).collect::<Result<Vec<_>, String>>()?;
+
+    if opts.len() < 2 {
+        return Err("At least 2 options are required.".to_owned());
+    }
+
+    if opts.len() > MAX_OPTIONS {
+        return Err(format!("At most {} options are allowed; got {}.", MAX_OPTIONS, opts.len()));
+    }
+
+    let mut seen = HashSet::new();
+    for (opt, _) in &opts {
+        if !seen.insert(opt.to_lowercase()) {
+            return Err(format!("Option '{}' was given more than once.", opt));
+        }
+    }
+
+    Ok(opts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll_name() {
+        assert_eq!(Ok("budget-2024".to_owned()), poll_name(" Budget-2024 "));
+        assert!(poll_name("budget vote").is_err());
+        assert!(poll_name(&"a".repeat(25)).is_err());
+    }
+
+    #[test]
+    fn test_ranks() {
+        assert_eq!(Ok(2), ranks(2));
+        assert!(ranks(1).is_err());
+        assert!(ranks(MAX_RANKS + 1).is_err());
+    }
+
+    #[test]
+    fn test_seats() {
+        assert_eq!(Ok(2), seats(Method::Stv, 2, 3));
+        assert!(seats(Method::Stv, 3, 3).is_err());
+        assert!(seats(Method::Stv, 0, 3).is_err());
+        assert_eq!(Ok(1), seats(Method::Ranking, 0, 3));
+    }
+
+    #[test]
+    fn test_options() {
+        assert_eq!(2, options(vec!["a".to_owned(), "b".to_owned()]).unwrap().len());
+        assert!(options(vec!["a".to_owned()]).is_err());
+        assert!(options(vec!["a".to_owned(), "A".to_owned()]).is_err());
+        assert!(options(vec!["a".to_owned(); MAX_OPTIONS + 1]).is_err());
+    }
+
+    #[test]
+    fn test_days() {
+        assert_eq!(Ok(None), days("min-age", 0));
+        assert_eq!(Ok(Some(30)), days("min-age", 30));
+        assert!(days("min-age", -1).is_err());
+        assert!(days("min-age", MAX_DAYS + 1).is_err());
+    }
+
+    #[test]
+    fn test_hours_from_now() {
+        assert!(hours_from_now("in-hours", 1).is_ok());
+        assert!(hours_from_now("in-hours", 0).is_err());
+        assert!(hours_from_now("in-hours", MAX_DURATION_HOURS + 1).is_err());
+    }
+
+    #[test]
+    fn test_weight_table() {
+        assert_eq!(Ok(vec![(123, 2), (456, 0), (789, 5)]), weight_table("123:2, <@456>:0 <@!789>:5"));
+        assert!(weight_table("").is_err());
+        assert!(weight_table("123").is_err());
+        assert!(weight_table("someone:2").is_err());
+        assert!(weight_table("123:two").is_err());
+        assert!(weight_table(&format!("123:{}", MAX_WEIGHT + 1)).is_err());
+    }
+
+    #[test]
+    fn test_quorum() {
+        assert_eq!(Ok(None), quorum(None, None));
+        assert_eq!(Ok(None), quorum(Some(0), None));
+        assert_eq!(Ok(None), quorum(None, Some(0)));
+        assert_eq!(Ok(Some(Quorum::Ballots(10))), quorum(Some(10), None));
+        assert_eq!(Ok(Some(Quorum::Percent(50))), quorum(None, Some(50)));
+        assert!(quorum(Some(10), Some(50)).is_err());
+        assert!(quorum(Some(-1), None).is_err());
+        assert!(quorum(None, Some(101)).is_err());
+    }
+
+    #[test]
+    fn test_quorum_text() {
+        assert_eq!(Ok(None), quorum_text(" "));
+        assert_eq!(Ok(None), quorum_text("0"));
+        assert_eq!(Ok(Some(Quorum::Ballots(10))), quorum_text("10"));
+        assert_eq!(Ok(Some(Quorum::Percent(25))), quorum_text(" 25 % "));
+        assert!(quorum_text("ten").is_err());
+        assert!(quorum_text("150%").is_err());
+    }
+
+    #[test]
+    fn test_write_in() {
+        assert_eq!(Ok("Jane Doe".to_owned()), write_in("  Jane \t Doe "));
+        assert!(write_in("   ").is_err());
+        assert!(write_in(&"a".repeat(MAX_WRITE_IN_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_option() {
+        let (name, details) = option("Pizza | Cheese and tomato | https://example.com/pizza | 🍕").unwrap();
+        assert_eq!("Pizza", name);
+        assert_eq!(Some("Cheese and tomato".to_owned()), details.description);
+        assert_eq!(Some("https://example.com/pizza".to_owned()), details.url);
+        assert_eq!(Some("🍕".to_owned()), details.emoji);
+
+        assert_eq!(Ok(("Tacos".to_owned(), OptionDetails::default())), option(" Tacos | "));
+        assert_eq!(Some("<:taco:1234>".to_owned()), option("Tacos | <:taco:1234>").unwrap().1.emoji);
+        assert!(option(" | a description").is_err());
+        assert!(option("Tacos | one | two").is_err());
+        assert!(option("Tacos | 🌮 | 🌯").is_err());
+        assert!(options(vec!["a".to_owned(), "A | description".to_owned()]).is_err());
+    }
+
+    #[test]
+    fn test_is_emoji() {
+        for v in ["🍕", "⭐", "❤\u{FE0F}", "👍🏽", "👩\u{200D}💻", "🧑\u{200D}⚕\u{FE0F}", "🇺🇸", "#\u{FE0F}\u{20E3}", "<a:dance:1234>"] {
+            assert!(is_emoji(v), "{}", v);
+        }
+        for v in ["★", "→", "«»", "🍕🍕", "🇺", "#", "a", "♀"] {
+            assert!(!is_emoji(v), "{}", v);
+        }
+    }
+
+    #[test]
+    fn test_runoff_rules() {
+        assert_eq!(Ok(2), runoff_candidates(2));
+        assert!(runoff_candidates(1).is_err());
+        assert!(runoff_candidates(MAX_RUNOFF_CANDIDATES + 1).is_err());
+        assert_eq!(Ok(None), runoff_hours(0));
+        assert_eq!(Ok(Some(48)), runoff_hours(48));
+        assert!(runoff_hours(-1).is_err());
+    }
+
+    #[test]
+    fn test_period() {
+        assert_eq!(Ok(24), period("daily"));
+        assert_eq!(Ok(168), period("Weekly"));
+        assert_eq!(Ok(12), period("12h"));
+        assert_eq!(Ok(72), period("3 d"));
+        assert_eq!(Ok(336), period("2w"));
+        assert!(period("0h").is_err());
+        assert!(period("monthly").is_err());
+        assert!(period(&format!("{}h", MAX_DURATION_HOURS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_reminder_hours() {
+        assert_eq!(Ok(vec![24, 2, 1]), reminder_hours("1, 24h 2 24"));
+        assert_eq!(Ok(Vec::new()), reminder_hours("none"));
+        assert_eq!(Ok(Vec::new()), reminder_hours(""));
+        assert!(reminder_hours("0").is_err());
+        assert!(reminder_hours("soon").is_err());
+        assert!(reminder_hours("1 2 3 4 5 6").is_err());
+    }
+
+    #[test]
+    fn test_locale() {
+        assert_eq!(Ok("en-US".to_owned()), locale(" EN_us "));
+        assert_eq!(Ok("de".to_owned()), locale("DE"));
+        assert!(locale("english").is_err());
+        assert!(locale("en-USA").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use evlog::meta;
+use once_cell::sync::Lazy;
+use serenity::http::{Http, HttpError};
+use sqlx::PgPool;
+
+use crate::db;
+use crate::db::schema::Poll;
+use crate::runtime::get_logger;
+use crate::support::eligibility;
+
+// Discord returns at most 1000 members per request.
+const MEMBER_PAGE_SIZE: u64 = 1000;
+
+// Counting eligible members pages through the whole member list, so counts are reused for this
+// long before they are fetched again.
+const ELIGIBLE_COUNT_LIFETIME_MINUTES: i64 = 10;
+
+// Eligible member counts by poll ID, with when they were counted.
+static ELIGIBLE_COUNTS: Lazy<DashMap<i32, (DateTime<Utc>, i64)>> = Lazy::new(DashMap::new);
+
+/// Returned when Discord refuses to list a server's members, which it does unless the Server
+/// Members intent is enabled for the application.
+#[derive(Debug)]
+pub struct MissingMembersIntent;
+
+impl fmt::Display for MissingMembersIntent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the bot may not list this server's members until the Server Members intent is enabled for it in the Discord developer portal")
+    }
+}
+
+impl std::error::Error for MissingMembersIntent {}
+
+/// Lists the user IDs of every member of the poll's server who its eligibility rules currently
+/// allow to vote, ignoring any voter roll. Fails with [`MissingMembersIntent`] if Discord refuses
+/// to list them.
+pub async fn eligible_members(http: &Http, poll: &Poll) -> anyhow::Result<Vec<u64>> {
+    let mut r = Vec::new();
+    let mut after = None;
+
+    loop {
+        let members = match http.get_guild_members(poll.id_server, Some(MEMBER_PAGE_SIZE), after).await {
+            Ok(v) => v,
+            Err(serenity::Error::Http(e)) if matches!(&*e, HttpError::UnsuccessfulRequest(v) if v.status_code.as_u16() == 403) => {
+                return Err(MissingMembersIntent.into());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        for member in &members {
+            if !member.user.bot && eligibility::ineligibility(&poll.settings.eligibility, member).is_none() {
+                r.push(*member.user.id.as_u64());
+            }
+        }
+
+        if (members.len() as u64) < MEMBER_PAGE_SIZE {
+            break;
+        }
+        after = members.last().map(|v| *v.user.id.as_u64());
+    }
+
+    Ok(r)
+}
+
+/// Counts the members of the poll's server who its eligibility rules currently allow to vote,
+/// reusing a count made in the last few minutes.
+pub async fn eligible_count(http: &Http, poll: &Poll) -> anyhow::Result<i64> {
+    let lifetime = Duration::minutes(ELIGIBLE_COUNT_LIFETIME_MINUTES);
+
+    if let Some(v) = ELIGIBLE_COUNTS.get(&poll.id) {
+        let (time_counted, count) = *v.value();
+        if Utc::now() - time_counted < lifetime {
+            return Ok(count);
+        }
+    }
+
+    let count = eligible_members(http, poll).await?.len() as i64;
+
+    let now = Utc::now();
+    ELIGIBLE_COUNTS.retain(|_, (time_counted, _)| now - *time_counted < lifetime);
+    ELIGIBLE_COUNTS.insert(poll.id, (now, count));
+
+    Ok(count)
+}
+
+/// Discards the poll's cached eligible member count, for when its eligibility rules change.
+pub fn forget_eligible_count(id_poll: i32) {
+    ELIGIBLE_COUNTS.remove(&id_poll);
+}
+
+/// Snapshots every member of the poll's server who is currently eligible to vote on it into the
+/// poll's voter roll. Returns the number of members on the roll.
+pub async fn freeze(http: &Http, conn: &PgPool, poll: &Poll) -> anyhow::Result<usize> {
+    let roll = eligible_members(http, poll).await?;
+
+    db::model::set_voter_roll(conn, poll.id, &roll).await?;
+
+    get_logger().info("Froze poll voter roll.", meta! {
+        "PollID" => poll.id,
+        "GuildID" => poll.id_server,
+        "RollSize" => roll.len(),
+    });
+
+    Ok(roll.len())
+}
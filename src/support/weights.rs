@@ -0,0 +1,52 @@
+use serenity::http::Http;
+use serenity::model::guild::Member;
+use sqlx::PgPool;
+
+use crate::db;
+use crate::db::schema::Poll;
+
+/// Determines how many votes a member's ballot on a poll counts as: the weight set for them, if
+/// any, otherwise the highest weight among their roles, otherwise 1. `member` is the member on the
+/// interaction; interactions from direct messages have none, so it is fetched from the poll's server.
+pub async fn weight(http: &Http, conn: &PgPool, poll: &Poll, member: Option<&Member>, id_user: u64) -> anyhow::Result<u32> {
+    if let Some(v) = db::model::get_user_weight(conn, poll.id, id_user).await? {
+        return Ok(v);
+    }
+
+    if poll.settings.role_weights.is_empty() {
+        return Ok(1);
+    }
+
+    let fetched;
+    let member = match member {
+        Some(v) => v,
+        None => {
+            fetched = http.get_member(poll.id_server, id_user).await?;
+            &fetched
+        }
+    };
+
+    Ok(poll.settings.role_weights.iter()
+        .filter(|(role, _)| member.roles.iter().any(|v| v.as_u64() == role))
+        .map(|(_, weight)| *weight)
+        .max()
+        .unwrap_or(1))
+}
+
+/// Describes a poll's weight rules, or returns `None` if every ballot counts once.
+#[must_use]
+pub fn describe(poll: &Poll, n_user_weights: i64) -> Option<String> {
+    if poll.settings.role_weights.is_empty() && n_user_weights == 0 {
+        return None;
+    }
+
+    let mut r = poll.settings.role_weights.iter()
+        .map(|(role, weight)| format!("<@&{}>: ×{}", role, weight))
+        .collect::<Vec<String>>();
+
+    if n_user_weights > 0 {
+        r.push(format!("{} member(s) with their own weight", n_user_weights));
+    }
+
+    Some(r.join("\n"))
+}